@@ -1,18 +1,33 @@
-use netstat2::ProtocolFlags;
-use regex::Regex;
-use tui::widgets::TableState;
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::time::Instant;
 
-use crate::os::{get_all_socket_info, SocketInfoWithProcName};
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use netstat2::TcpState;
+use tui::layout::Rect;
+use tui::widgets::TableState;
 
-pub const ITEMS: [&str; 24] = [
-    "Item1", "Item2", "Item3", "Item4", "Item5", "Item6", "Item7", "Item8", "Item9", "Item10",
-    "Item11", "Item12", "Item13", "Item14", "Item15", "Item16", "Item17", "Item18", "Item19",
-    "Item20", "Item21", "Item22", "Item23", "Item24",
-];
+use crate::baseline::Baseline;
+use crate::columns::Column;
+use crate::command::{self, CommandLine};
+use crate::config::Config;
+use crate::filter::{expand_saved_filters, FilterQuery};
+use crate::keymap::Action;
+use crate::os::{
+    can_close_tcp_connections, close_tcp_connection, get_all_socket_info, get_capabilities,
+    get_net_namespaces, get_socket_info_in_namespace, NetNamespace, Protocol,
+    SocketInfoWithProcName, Throughput,
+};
+use crate::resolve::{HostNames, Services, UserNames};
+use crate::signal::{parse_signal, signal_name, signal_processes};
+use crate::stats::{StatsHistory, StatsSample};
+use crate::utils::{get_total_sockets_state_count, TCP_STATES};
 
 pub struct StatefulTable {
     pub state: TableState,
     pub items: Vec<SocketInfoWithProcName>,
+    /// First visible row, kept the way tui scrolls `TableState` which doesn't expose it.
+    pub offset: usize,
 }
 
 impl StatefulTable {
@@ -20,10 +35,28 @@ impl StatefulTable {
         StatefulTable {
             state: TableState::default(),
             items,
+            offset: 0,
+        }
+    }
+
+    /// Scrolls just enough to keep the selected row within `height` rows.
+    pub fn update_offset(&mut self, height: usize) {
+        let selected = self.state.selected().unwrap_or(0);
+
+        self.offset = self.offset.min(self.items.len().saturating_sub(1));
+
+        if selected >= self.offset + height {
+            self.offset = selected + 1 - height.max(1);
+        } else if selected < self.offset {
+            self.offset = selected;
         }
     }
 
     pub fn next(&mut self) {
+        if self.items.is_empty() {
+            return;
+        }
+
         let i = match self.state.selected() {
             Some(i) => {
                 if i >= self.items.len() - 1 {
@@ -38,6 +71,10 @@ impl StatefulTable {
     }
 
     pub fn previous(&mut self) {
+        if self.items.is_empty() {
+            return;
+        }
+
         let i = match self.state.selected() {
             Some(i) => {
                 if i == 0 {
@@ -56,13 +93,31 @@ impl StatefulTable {
     }
 
     pub fn last(&mut self) {
-        self.state.select(Some(self.items.len() - 1));
+        if !self.items.is_empty() {
+            self.state.select(Some(self.items.len() - 1));
+        }
+    }
+
+    pub fn next_page(&mut self, rows: usize) {
+        if !self.items.is_empty() {
+            let i = self.state.selected().map_or(0, |i| i + rows);
+            self.state.select(Some(i.min(self.items.len() - 1)));
+        }
+    }
+
+    pub fn previous_page(&mut self, rows: usize) {
+        if !self.items.is_empty() {
+            let i = self.state.selected().unwrap_or(0).saturating_sub(rows);
+            self.state.select(Some(i));
+        }
     }
 }
 
 pub struct StatefulTabItem {
     pub title: String,
-    pub protocol: ProtocolFlags,
+    pub protocol: Protocol,
+    /// Only lists TCP listeners and UDP sockets.
+    pub listeners: bool,
 }
 
 pub struct StatefulTabs {
@@ -86,20 +141,228 @@ impl StatefulTabs {
         }
     }
 
-    pub fn selected_protocol(&self) -> ProtocolFlags {
+    pub fn selected_protocol(&self) -> Protocol {
         self.items[self.index].protocol
     }
+
+    pub fn is_listeners(&self) -> bool {
+        self.items[self.index].listeners
+    }
+}
+
+struct ByteCounters {
+    time: Instant,
+    acked: u64,
+    received: u64,
+}
+
+/// Derives transfer rates from the byte counters of two consecutive snapshots.
+#[derive(Default)]
+pub struct ThroughputTracker {
+    previous: HashMap<u64, ByteCounters>,
+}
+
+impl ThroughputTracker {
+    pub fn update(&mut self, connections: &mut [SocketInfoWithProcName]) {
+        let now = Instant::now();
+        let mut current = HashMap::new();
+
+        for connection in connections.iter_mut() {
+            let inode = match connection.inode() {
+                Some(inode) => inode,
+                None => continue,
+            };
+
+            let (acked, received) = match &connection.tcp_internals {
+                Some(internals) => match (internals.bytes_acked, internals.bytes_received) {
+                    (Some(acked), Some(received)) => (acked, received),
+                    _ => continue,
+                },
+                None => continue,
+            };
+
+            if let Some(previous) = self.previous.get(&inode) {
+                let elapsed = now.duration_since(previous.time).as_secs_f64();
+
+                // A counter going backwards means the inode got reused by a new socket
+                if elapsed > 0.0 && acked >= previous.acked && received >= previous.received {
+                    connection.throughput = Some(Throughput {
+                        rx: (received - previous.received) as f64 / elapsed,
+                        tx: (acked - previous.acked) as f64 / elapsed,
+                    });
+                }
+            }
+
+            current.insert(
+                inode,
+                ByteCounters {
+                    time: now,
+                    acked,
+                    received,
+                },
+            );
+        }
+
+        self.previous = current;
+    }
+}
+
+/// Remembers when each socket was first seen, those open before nets started are as old as it.
+#[derive(Default)]
+pub struct AgeTracker {
+    first_seen: HashMap<u64, Instant>,
+}
+
+impl AgeTracker {
+    pub fn update(&mut self, connections: &mut [SocketInfoWithProcName]) {
+        let now = Instant::now();
+        let mut current = HashMap::new();
+
+        for connection in connections.iter_mut() {
+            // Sockets without an inode, like TIME_WAIT ones, all share inode 0
+            let inode = match connection.inode() {
+                Some(inode) if inode != 0 => inode,
+                _ => continue,
+            };
+
+            let first_seen = self.first_seen.get(&inode).copied().unwrap_or(now);
+            connection.age = Some(now.duration_since(first_seen));
+            current.insert(inode, first_seen);
+        }
+
+        self.first_seen = current;
+    }
+}
+
+/// Number of consecutive updates after which a non-empty queue is highlighted.
+pub const PERSISTENT_QUEUE_TICKS: u32 = 3;
+
+/// Counts for how many consecutive updates each socket had data sitting in its queues.
+#[derive(Default)]
+pub struct QueueTracker {
+    busy: HashMap<u64, u32>,
+}
+
+impl QueueTracker {
+    pub fn update(&mut self, connections: &[SocketInfoWithProcName]) {
+        self.busy = connections
+            .iter()
+            .filter(|connection| connection.has_queued_data())
+            .filter_map(|connection| {
+                let inode = connection.inode()?;
+                Some((inode, self.busy.get(&inode).copied().unwrap_or(0) + 1))
+            })
+            .collect();
+    }
+
+    pub fn is_persistent(&self, connection: &SocketInfoWithProcName) -> bool {
+        connection
+            .inode()
+            .and_then(|inode| self.busy.get(&inode))
+            .is_some_and(|ticks| *ticks >= PERSISTENT_QUEUE_TICKS)
+    }
+}
+
+/// Where the parts of the interface that react to the mouse were last drawn.
+#[derive(Default)]
+pub struct ScreenAreas {
+    pub filter: Rect,
+    pub tabs: Vec<Rect>,
+    pub table: Rect,
+    pub columns: Vec<(Rect, Column)>,
+    pub column_editor: Rect,
+    pub info: Rect,
+    pub help: Rect,
+    pub states: Vec<(Rect, TcpState)>,
+    pub users: Vec<(Rect, String)>,
+}
+
+fn contains(area: &Rect, column: u16, row: u16) -> bool {
+    (area.left()..area.right()).contains(&column) && (area.top()..area.bottom()).contains(&row)
 }
 
 pub enum FilterMode {
     Normal,
     Typing,
+    /// Typing a `:` command.
+    Command,
 }
 
 pub struct FilterField {
     pub input: String,
     pub mode: FilterMode,
-    pub regex: Option<Regex>,
+    pub query: Option<FilterQuery>,
+    pub error: Option<String>,
+}
+
+#[derive(Clone, Copy)]
+pub struct SortOrder {
+    pub column: Column,
+    pub descending: bool,
+}
+
+/// The list of every column, where columns are shown, hidden and reordered.
+#[derive(Default)]
+pub struct ColumnEditor {
+    pub cursor: usize,
+    /// First visible entry, set when drawing.
+    pub offset: usize,
+}
+
+/// Choosing a signal for the processes of a connection, then confirming it.
+pub struct KillDialog {
+    pub pids: Vec<u32>,
+    pub process_name: String,
+    /// Position in `KillDialog::SIGNALS`, the entry after them is a custom signal.
+    pub cursor: usize,
+    pub custom: String,
+    /// The chosen signal, waiting for confirmation.
+    pub confirm: Option<libc::c_int>,
+    pub error: Option<String>,
+}
+
+impl KillDialog {
+    pub const SIGNALS: [libc::c_int; 4] =
+        [libc::SIGTERM, libc::SIGKILL, libc::SIGHUP, libc::SIGINT];
+
+    pub fn is_custom(&self) -> bool {
+        self.cursor == KillDialog::SIGNALS.len()
+    }
+
+    fn chosen_signal(&self) -> Result<libc::c_int, String> {
+        match KillDialog::SIGNALS.get(self.cursor) {
+            Some(signal) => Ok(*signal),
+            None if self.custom.is_empty() => Err(String::from("type a signal name or number")),
+            None => parse_signal(&self.custom)
+                .ok_or_else(|| format!("unknown signal '{}'", self.custom)),
+        }
+    }
+}
+
+/// Confirming that a TCP connection should be closed.
+pub struct CloseDialog {
+    pub local: SocketAddr,
+    pub remote: SocketAddr,
+    pub process_name: String,
+    /// When the connection is in another network namespace than nets.
+    pub namespace: Option<NetNamespace>,
+}
+
+/// Which network namespaces connections are listed from.
+#[derive(Clone, PartialEq, Eq)]
+pub enum NamespaceView {
+    Current,
+    One(NetNamespace),
+    All,
+}
+
+/// Entries kept in the event log, older ones are dropped.
+const MAX_EVENTS: usize = 100;
+
+/// Something done from nets, like sending a signal or closing a connection.
+pub struct LogEntry {
+    pub time: Instant,
+    pub result: Result<String, String>,
 }
 
 pub struct App {
@@ -110,17 +373,48 @@ pub struct App {
     pub tabs: StatefulTabs,
     pub connections: Vec<SocketInfoWithProcName>,
     pub connection_table: StatefulTable,
-    pub printable_lines: Vec<Vec<String>>,
+    pub columns: Vec<Column>,
+    /// Columns the Listeners tab added, taken out again when leaving it.
+    listener_columns: Vec<Column>,
+    pub column_editor: Option<ColumnEditor>,
+    pub command: CommandLine,
+    pub kill_dialog: Option<KillDialog>,
+    pub close_dialog: Option<CloseDialog>,
+    pub events: Vec<LogEntry>,
+    pub show_events: bool,
+    pub namespace_view: NamespaceView,
+    /// Namespaces as last enumerated, the current one first.
+    pub namespaces: Vec<NetNamespace>,
+    /// Listeners and outbound destinations that are expected, the others are highlighted.
+    pub baseline: Option<Baseline>,
+    /// Capabilities needed to find the processes of sockets that nets doesn't have.
+    pub missing_capabilities: Vec<&'static str>,
+    /// Narrow layout without side borders, set when drawing.
+    pub compact: bool,
+    pub sort: SortOrder,
+    pub throughput: ThroughputTracker,
+    pub queues: QueueTracker,
+    pub ages: AgeTracker,
+    pub stats: StatsHistory,
+    pub show_stats: bool,
+    /// Only TCP sockets in this state are listed.
+    pub state_filter: Option<TcpState>,
+    pub areas: ScreenAreas,
+    pub info_scroll: u16,
+    pub help_scroll: u16,
+    pub config: Config,
+    services: Services,
+    users: UserNames,
+    host_names: Option<HostNames>,
     is_paused: bool,
 }
 
 impl App {
-    pub fn new() -> App {
-        let mut initial_connections =
-            get_all_socket_info(ProtocolFlags::TCP | ProtocolFlags::UDP).unwrap();
-        initial_connections.sort_by(|a, b| a.info.local_port().cmp(&b.info.local_port()).reverse());
+    pub fn new(config: Config) -> App {
+        let mut initial_connections = get_all_socket_info(Protocol::all()).unwrap();
+        initial_connections.sort_by(|a, b| a.local_port().cmp(&b.local_port()).reverse());
 
-        App {
+        let mut app = App {
             should_quit: false,
             show_connection_info: false,
             show_help: false,
@@ -128,26 +422,103 @@ impl App {
             filter: FilterField {
                 input: String::new(),
                 mode: FilterMode::Normal,
-                regex: None,
+                query: None,
+                error: None,
             },
             tabs: StatefulTabs::with_items(vec![
                 StatefulTabItem {
                     title: String::from("All"),
-                    protocol: ProtocolFlags::TCP | ProtocolFlags::UDP,
+                    protocol: Protocol::all(),
+                    listeners: false,
                 },
                 StatefulTabItem {
                     title: String::from("TCP"),
-                    protocol: ProtocolFlags::TCP,
+                    protocol: Protocol::TCP,
+                    listeners: false,
                 },
                 StatefulTabItem {
                     title: String::from("UDP"),
-                    protocol: ProtocolFlags::UDP,
+                    protocol: Protocol::UDP,
+                    listeners: false,
+                },
+                StatefulTabItem {
+                    title: String::from("Unix"),
+                    protocol: Protocol::UNIX,
+                    listeners: false,
+                },
+                StatefulTabItem {
+                    title: String::from("Raw"),
+                    protocol: Protocol::RAW,
+                    listeners: false,
+                },
+                StatefulTabItem {
+                    title: String::from("ICMP"),
+                    protocol: Protocol::ICMP,
+                    listeners: false,
+                },
+                StatefulTabItem {
+                    title: String::from("SCTP"),
+                    protocol: Protocol::SCTP,
+                    listeners: false,
+                },
+                StatefulTabItem {
+                    title: String::from("Packet"),
+                    protocol: Protocol::PACKET,
+                    listeners: false,
+                },
+                StatefulTabItem {
+                    title: String::from("Listeners"),
+                    protocol: Protocol::TCP | Protocol::UDP,
+                    listeners: true,
                 },
             ]),
             connections: initial_connections.clone(),
             connection_table: StatefulTable::with_items(initial_connections),
-            printable_lines: Vec::new(),
+            columns: config.columns.clone(),
+            listener_columns: Vec::new(),
+            column_editor: None,
+            command: CommandLine::default(),
+            kill_dialog: None,
+            close_dialog: None,
+            events: Vec::new(),
+            show_events: false,
+            namespace_view: NamespaceView::Current,
+            namespaces: get_net_namespaces(),
+            baseline: None,
+            missing_capabilities: get_capabilities()
+                .into_iter()
+                .filter(|capability| capability.attributes_sockets && !capability.granted)
+                .map(|capability| capability.name)
+                .collect(),
+            compact: false,
+            sort: config.sort,
+            throughput: ThroughputTracker::default(),
+            queues: QueueTracker::default(),
+            ages: AgeTracker::default(),
+            stats: StatsHistory::default(),
+            show_stats: false,
+            state_filter: None,
+            areas: ScreenAreas::default(),
+            info_scroll: 0,
+            help_scroll: 0,
+            services: Services::load(),
+            users: UserNames::load(),
+            host_names: (config.resolve_dns || config.columns.contains(&Column::Hostname))
+                .then(HostNames::new),
+            config,
+        };
+
+        if let Some(index) = app
+            .tabs
+            .items
+            .iter()
+            .position(|tab| tab.title.to_lowercase() == app.config.default_tab)
+        {
+            app.tabs.index = index;
+            app.on_tab_changed();
         }
+
+        app
     }
 
     pub fn update_connections(&mut self) {
@@ -156,8 +527,26 @@ impl App {
         if self.is_paused() {
             connections = self.connections.clone();
         } else {
-            connections = get_all_socket_info(ProtocolFlags::TCP | ProtocolFlags::UDP).unwrap();
-            self.connections = connections.clone();
+            // Host names are looked up in the background, only once they are needed
+            if self.host_names.is_none() && self.columns.contains(&Column::Hostname) {
+                self.host_names = Some(HostNames::new());
+            }
+
+            let mut snapshot = self.collect_sockets();
+            self.apply_names(&mut snapshot);
+
+            if let Some(baseline) = &self.baseline {
+                baseline.mark_drift(&mut snapshot);
+            }
+
+            self.ages.update(&mut snapshot);
+            self.throughput.update(&mut snapshot);
+            self.queues.update(&snapshot);
+            self.connections = snapshot.clone();
+            connections = snapshot;
+
+            let sample = StatsSample::new(self);
+            self.stats.record(sample, &self.connections);
         };
 
         let mut filtered: Vec<SocketInfoWithProcName> = connections
@@ -166,23 +555,360 @@ impl App {
                 connection.protocol_flags | self.tabs.selected_protocol()
                     == self.tabs.selected_protocol()
             })
+            .filter(|connection| !self.tabs.is_listeners() || connection.is_listener())
             .collect();
 
-        filtered.sort_by(|a, b| a.info.local_port().cmp(&b.info.local_port()).reverse());
+        filtered.sort_by(|a, b| {
+            let ordering = self.sort.column.compare(a, b);
+
+            match self.sort.descending {
+                true => ordering.reverse(),
+                false => ordering,
+            }
+        });
 
         self.connection_table.items = filtered
             .into_iter()
-            .filter(|connection| connection.should_print(&self.filter.regex))
+            .filter(|connection| match self.state_filter {
+                Some(state) => connection.tcp_state() == Some(state),
+                None => true,
+            })
+            .filter(|connection| match &self.filter.query {
+                Some(query) => query.matches(connection),
+                None => true,
+            })
+            .collect();
+    }
+
+    /// Sockets of the namespaces being viewed. Going back to the current namespace when the
+    /// chosen one can't be entered.
+    fn collect_sockets(&mut self) -> Vec<SocketInfoWithProcName> {
+        let result = match &self.namespace_view {
+            NamespaceView::Current => Ok(Vec::new()),
+            NamespaceView::One(namespace) => {
+                get_socket_info_in_namespace(Protocol::all(), namespace).map_err(|e| e.to_string())
+            }
+            NamespaceView::All => {
+                self.namespaces = get_net_namespaces();
+
+                let mut errors = Vec::new();
+                let mut sockets = Vec::new();
+
+                for namespace in &self.namespaces {
+                    match get_socket_info_in_namespace(Protocol::all(), namespace) {
+                        Ok(found) => sockets.extend(found),
+                        Err(e) => errors.push(e.to_string()),
+                    }
+                }
+
+                // Namespaces go away with their processes, only fail if none could be read
+                match sockets.is_empty() && !errors.is_empty() {
+                    true => Err(errors.join(", ")),
+                    false => Ok(sockets),
+                }
+            }
+        };
+
+        match (result, &self.namespace_view) {
+            (Ok(sockets), NamespaceView::One(_) | NamespaceView::All) => return sockets,
+            (Ok(_), NamespaceView::Current) => {}
+            (Err(e), _) => {
+                self.namespace_view = NamespaceView::Current;
+                self.command.message = Some(Err(format!("netns: {}", e)));
+            }
+        }
+
+        let mut sockets = get_all_socket_info(Protocol::all()).unwrap();
+        let current = self
+            .namespaces
+            .iter()
+            .find(|namespace| namespace.is_current);
+
+        for socket in &mut sockets {
+            socket.namespace = current.map(|namespace| namespace.name.clone());
+        }
+
+        sockets
+    }
+
+    /// Goes from the current namespace through the others, then to all of them at once.
+    pub fn on_next_namespace(&mut self) {
+        self.namespaces = get_net_namespaces();
+
+        let others: Vec<&NetNamespace> = self
+            .namespaces
+            .iter()
+            .filter(|namespace| !namespace.is_current)
             .collect();
+
+        let next = match &self.namespace_view {
+            NamespaceView::Current => others.first().copied(),
+            NamespaceView::One(current) => others
+                .iter()
+                .skip_while(|namespace| namespace.inode != current.inode)
+                .nth(1)
+                .copied(),
+            NamespaceView::All => {
+                self.namespace_view = NamespaceView::Current;
+                return;
+            }
+        };
+
+        match next {
+            Some(namespace) => self.namespace_view = NamespaceView::One(namespace.clone()),
+            None if others.is_empty() => {
+                self.command.message = Some(Err(String::from(
+                    "no other network namespace can be seen, try as root",
+                )));
+            }
+            None => self.show_all_namespaces(),
+        }
+    }
+
+    /// Lists every namespace together, with the namespace column to tell them apart.
+    fn show_all_namespaces(&mut self) {
+        self.namespace_view = NamespaceView::All;
+
+        if !self.columns.contains(&Column::Namespace) {
+            self.columns.insert(0, Column::Namespace);
+        }
+    }
+
+    /// Highlights what isn't in the baseline, with the drift column to find it.
+    pub fn set_baseline(&mut self, baseline: Baseline) {
+        self.baseline = Some(baseline);
+
+        if !self.columns.contains(&Column::Drift) {
+            self.columns.insert(0, Column::Drift);
+        }
+    }
+
+    /// Views a namespace by its name or inode, `all` for every namespace.
+    pub fn set_namespace(&mut self, name: &str) -> Result<String, String> {
+        self.namespaces = get_net_namespaces();
+
+        if name == "all" {
+            self.show_all_namespaces();
+            return Ok(String::from("listing all network namespaces"));
+        }
+
+        let namespace = self
+            .namespaces
+            .iter()
+            .find(|namespace| namespace.name == name || namespace.inode.to_string() == name)
+            .ok_or_else(|| format!("unknown network namespace '{}'", name))?;
+
+        self.namespace_view = match namespace.is_current {
+            true => NamespaceView::Current,
+            false => NamespaceView::One(namespace.clone()),
+        };
+
+        Ok(format!(
+            "listing network namespace {} ({})",
+            namespace.name, namespace.inode
+        ))
     }
 
-    pub fn update_regex(&mut self) {
+    /// Fills in host, service and user names, and shows them in place of addresses and port
+    /// numbers as enabled in the config.
+    fn apply_names(&self, connections: &mut [SocketInfoWithProcName]) {
+        for connection in connections {
+            connection.exposed = self.config.allowed_listeners.is_exposed(connection);
+
+            // Users missing from /etc/passwd, e.g. in containers, keep their uid
+            connection.user = connection.uid().map(|uid| match self.users.name(uid) {
+                Some(name) => name.to_string(),
+                None => uid.to_string(),
+            });
+
+            if let (Some(host_names), Some((addr, _))) =
+                (&self.host_names, connection.remote_endpoint())
+            {
+                if let Some(host) = host_names.get(addr) {
+                    match self.config.resolve_dns {
+                        true => connection.set_remote_host(host),
+                        false => connection.remote_host = Some(host),
+                    }
+                }
+            }
+
+            if let Some(protocol) = connection.service_protocol() {
+                let name =
+                    |port: Option<u16>| Some(self.services.name(port?, protocol)?.to_string());
+
+                let local_port = connection.local_port();
+                let remote_port = connection.remote_endpoint().map(|(_, port)| port);
+
+                // The service is usually on the lower port, the other one being ephemeral
+                connection.service = match (local_port, remote_port) {
+                    (Some(local), Some(remote)) if remote != 0 && remote < local => {
+                        name(Some(remote)).or_else(|| name(Some(local)))
+                    }
+                    _ => name(local_port).or_else(|| name(remote_port)),
+                };
+
+                if self.config.service_names {
+                    connection.set_service_names(name(local_port), name(remote_port));
+                }
+            }
+        }
+    }
+
+    pub fn update_filter(&mut self) {
+        self.filter.error = None;
+
         if self.filter.input.is_empty() {
-            self.filter.regex = None;
+            self.filter.query = None;
             return;
         }
 
-        self.filter.regex = Some(Regex::new(&regex::escape(&self.filter.input)).unwrap());
+        let input = match expand_saved_filters(&self.filter.input, &self.config.filters) {
+            Ok(input) => input,
+            Err(e) => {
+                self.filter.error = Some(e);
+                return;
+            }
+        };
+
+        match FilterQuery::parse(&input) {
+            Ok(query) => self.filter.query = Some(query),
+            Err(e) => {
+                // Keep showing the last valid results while the filter is being typed
+                self.filter.error = Some(e);
+            }
+        }
+    }
+
+    /// Sorts by the next visible column.
+    pub fn on_next_sort_column(&mut self) {
+        let position = self
+            .columns
+            .iter()
+            .position(|column| *column == self.sort.column)
+            .map_or(0, |i| (i + 1) % self.columns.len());
+
+        self.sort.column = self.columns[position];
+    }
+
+    pub fn on_reverse_sort(&mut self) {
+        self.sort.descending = !self.sort.descending;
+    }
+
+    /// Sorts by combined transfer rate, showing the rate columns if needed.
+    pub fn on_top_talkers(&mut self) {
+        for column in [Column::RxRate, Column::TxRate] {
+            if !self.columns.contains(&column) {
+                self.columns.push(column);
+            }
+        }
+
+        self.sort = SortOrder {
+            column: Column::TotalRate,
+            descending: true,
+        };
+    }
+
+    pub fn on_toggle_tcp_internals(&mut self) {
+        self.toggle_columns(&Column::TCP_INTERNALS);
+    }
+
+    pub fn on_toggle_queues(&mut self) {
+        self.toggle_columns(&Column::QUEUES);
+    }
+
+    /// Shows or hides a group of columns depending on whether its first column is visible.
+    fn toggle_columns(&mut self, group: &[Column]) {
+        if self.columns.contains(&group[0]) {
+            self.columns.retain(|column| !group.contains(column));
+        } else {
+            self.columns.extend(group);
+        }
+
+        self.keep_sort_column();
+    }
+
+    /// Goes back to the default sort when the sort column gets hidden.
+    pub fn keep_sort_column(&mut self) {
+        if !self.columns.contains(&self.sort.column) && self.sort.column != Column::TotalRate {
+            self.sort.column = Column::LocalPort;
+        }
+    }
+
+    /// Visible columns in their order, followed by the hidden ones.
+    pub fn column_list(&self) -> Vec<(Column, bool)> {
+        let hidden = Column::ALL
+            .iter()
+            .filter(|column| !self.columns.contains(column))
+            .map(|column| (*column, false));
+
+        self.columns
+            .iter()
+            .map(|column| (*column, true))
+            .chain(hidden)
+            .collect()
+    }
+
+    pub fn on_toggle_column_editor(&mut self) {
+        self.column_editor = match self.column_editor {
+            Some(_) => None,
+            None => Some(ColumnEditor::default()),
+        };
+    }
+
+    /// Shows or hides the column under the cursor of the column list.
+    pub fn on_toggle_column(&mut self) {
+        let cursor = match &self.column_editor {
+            Some(editor) => editor.cursor,
+            None => return,
+        };
+
+        let (column, visible) = match self.column_list().get(cursor) {
+            Some(entry) => *entry,
+            None => return,
+        };
+
+        if visible {
+            // The table needs at least one column
+            if self.columns.len() > 1 {
+                self.columns.retain(|c| *c != column);
+                self.keep_sort_column();
+            }
+        } else {
+            self.columns.push(column);
+
+            if let Some(editor) = &mut self.column_editor {
+                editor.cursor = self.columns.len() - 1;
+            }
+        }
+    }
+
+    /// Moves the visible column under the cursor of the column list left or right.
+    pub fn on_move_column(&mut self, right: bool) {
+        let editor = match &mut self.column_editor {
+            Some(editor) => editor,
+            None => return,
+        };
+
+        let target = match right {
+            true => editor.cursor + 1,
+            false => match editor.cursor.checked_sub(1) {
+                Some(target) => target,
+                None => return,
+            },
+        };
+
+        if editor.cursor < self.columns.len() && target < self.columns.len() {
+            self.columns.swap(editor.cursor, target);
+            editor.cursor = target;
+        }
+    }
+
+    fn move_column_cursor(&mut self, to: impl FnOnce(usize, usize) -> usize) {
+        let len = self.column_list().len();
+
+        if let Some(editor) = &mut self.column_editor {
+            editor.cursor = to(editor.cursor, len).min(len - 1);
+        }
     }
 
     pub fn is_paused(&self) -> bool {
@@ -193,62 +919,483 @@ impl App {
         self.is_paused = !self.is_paused;
     }
 
+    /// Cycles through the states that have sockets, then back to showing every state.
+    pub fn on_next_state_filter(&mut self) {
+        let states: Vec<TcpState> = TCP_STATES
+            .iter()
+            .copied()
+            .filter(|state| get_total_sockets_state_count(self, *state) > 0)
+            .collect();
+
+        let next = match self.state_filter {
+            Some(current) => states.iter().skip_while(|state| **state != current).nth(1),
+            None => states.first(),
+        };
+
+        self.state_filter = next.copied();
+    }
+
+    pub fn on_click(&mut self, column: u16, row: u16) {
+        let areas = &self.areas;
+
+        if let Some((_, state)) = areas.states.iter().find(|(a, _)| contains(a, column, row)) {
+            self.state_filter = match self.state_filter == Some(*state) {
+                true => None,
+                false => Some(*state),
+            };
+        } else if let Some((_, user)) = areas.users.iter().find(|(a, _)| contains(a, column, row)) {
+            // Clicking the user of the current filter clears it
+            let filter = format!("user:{}", user);
+
+            self.filter.input = match self.filter.input == filter {
+                true => String::new(),
+                false => filter,
+            };
+
+            self.update_filter();
+        } else if let Some(index) = areas.tabs.iter().position(|a| contains(a, column, row)) {
+            self.tabs.index = index;
+            self.on_tab_changed();
+        } else if let Some((_, clicked)) =
+            areas.columns.iter().find(|(a, _)| contains(a, column, row))
+        {
+            match self.sort.column == *clicked {
+                true => self.on_reverse_sort(),
+                false => self.sort.column = *clicked,
+            }
+        } else if contains(&areas.filter, column, row) {
+            self.filter.mode = FilterMode::Typing;
+        } else if contains(&areas.column_editor, column, row) {
+            // Below the top border, every row is a column of the list
+            let index = match (
+                row.checked_sub(areas.column_editor.y + 1),
+                &self.column_editor,
+            ) {
+                (Some(clicked), Some(editor)) => editor.offset + clicked as usize,
+                _ => return,
+            };
+
+            if index < self.column_list().len() {
+                self.move_column_cursor(|_, _| index);
+                self.on_toggle_column();
+            }
+        } else if contains(&areas.table, column, row) {
+            // The first two rows are the border and the column titles
+            let clicked = row.saturating_sub(areas.table.y + 2) as usize;
+            let index = self.connection_table.offset + clicked;
+
+            if row >= areas.table.y + 2 && index < self.connection_table.items.len() {
+                self.connection_table.state.select(Some(index));
+            }
+        }
+    }
+
+    pub fn on_scroll(&mut self, column: u16, row: u16, down: bool) {
+        let scroll = |offset: u16| match down {
+            true => offset.saturating_add(1),
+            false => offset.saturating_sub(1),
+        };
+
+        if contains(&self.areas.info, column, row) {
+            self.info_scroll = scroll(self.info_scroll);
+        } else if contains(&self.areas.help, column, row) {
+            self.help_scroll = scroll(self.help_scroll);
+        } else if contains(&self.areas.column_editor, column, row) {
+            self.move_column_cursor(|cursor, _| match down {
+                true => cursor + 1,
+                false => cursor.saturating_sub(1),
+            });
+        } else if contains(&self.areas.table, column, row)
+            && !self.connection_table.items.is_empty()
+        {
+            match down {
+                true => self.on_down(),
+                false => self.on_up(),
+            }
+        }
+    }
+
+    pub fn on_toggle_stats(&mut self) {
+        self.show_stats = !self.show_stats;
+    }
+
     pub fn on_show_help(&mut self) {
         self.show_help = !self.show_help;
     }
 
+    // While the column list is open, moving selects in the list rather than in the table
+
     pub fn on_up(&mut self) {
-        self.connection_table.previous();
+        match self.column_editor {
+            Some(_) => self.move_column_cursor(|cursor, len| (cursor + len - 1) % len),
+            None => self.connection_table.previous(),
+        }
     }
 
     pub fn on_down(&mut self) {
-        self.connection_table.next();
+        match self.column_editor {
+            Some(_) => self.move_column_cursor(|cursor, len| (cursor + 1) % len),
+            None => self.connection_table.next(),
+        }
     }
 
     pub fn on_first(&mut self) {
-        self.connection_table.first();
+        match self.column_editor {
+            Some(_) => self.move_column_cursor(|_, _| 0),
+            None => self.connection_table.first(),
+        }
     }
 
     pub fn on_last(&mut self) {
-        self.connection_table.last();
+        match self.column_editor {
+            Some(_) => self.move_column_cursor(|_, len| len - 1),
+            None => self.connection_table.last(),
+        }
+    }
+
+    pub fn on_jump_to_peer(&mut self) {
+        let selected = match self.connection_table.state.selected() {
+            Some(i) => i,
+            None => return,
+        };
+
+        let connection = match self.connection_table.items.get(selected) {
+            Some(connection) => connection,
+            None => return,
+        };
+
+        if let Some(i) = self
+            .connection_table
+            .items
+            .iter()
+            .position(|other| connection.is_peer_of(other))
+        {
+            self.connection_table.state.select(Some(i));
+        }
+    }
+
+    /// Opens the dialog to signal the processes of the selected connection, asking right
+    /// away for confirmation when the signal is already known.
+    pub fn open_kill_dialog(&mut self, signal: Option<libc::c_int>) -> Result<(), String> {
+        let connection = self
+            .connection_table
+            .state
+            .selected()
+            .and_then(|i| self.connection_table.items.get(i))
+            .ok_or("no connection selected")?;
+
+        let pids = connection.associated_pids().to_vec();
+
+        if pids.is_empty() {
+            return Err(String::from("the selected connection has no known process"));
+        }
+
+        let mut dialog = KillDialog {
+            pids,
+            process_name: connection.process_name.clone(),
+            cursor: 0,
+            custom: String::new(),
+            confirm: signal,
+            error: None,
+        };
+
+        if let Some(signal) = signal {
+            dialog.cursor = match KillDialog::SIGNALS.iter().position(|s| *s == signal) {
+                Some(position) => position,
+                None => {
+                    dialog.custom = signal_name(signal);
+                    KillDialog::SIGNALS.len()
+                }
+            };
+        }
+
+        self.kill_dialog = Some(dialog);
+        Ok(())
+    }
+
+    /// Opens the confirmation to close the selected TCP connection.
+    pub fn open_close_dialog(&mut self) -> Result<(), String> {
+        let connection = self
+            .connection_table
+            .state
+            .selected()
+            .and_then(|i| self.connection_table.items.get(i))
+            .ok_or("no connection selected")?;
+
+        let (local, remote) = connection
+            .tcp_endpoints()
+            .ok_or("only connected TCP sockets can be closed")?;
+
+        // Viewing other namespaces, the connection is closed from inside the one of its row
+        let namespace = match &connection.namespace {
+            Some(name) => self
+                .namespaces
+                .iter()
+                .find(|namespace| namespace.name == *name && !namespace.is_current)
+                .cloned(),
+            None => None,
+        };
+
+        can_close_tcp_connections()?;
+
+        self.close_dialog = Some(CloseDialog {
+            local,
+            remote,
+            process_name: connection.process_name.clone(),
+            namespace,
+        });
+
+        Ok(())
+    }
+
+    pub fn has_dialog(&self) -> bool {
+        self.kill_dialog.is_some() || self.close_dialog.is_some()
+    }
+
+    /// Keys of the open dialog, which are fixed rather than taken from the keymap.
+    pub fn on_dialog_key(&mut self, key: KeyEvent) {
+        self.command.message = None;
+
+        match &self.close_dialog {
+            Some(dialog) => match key.code {
+                KeyCode::Char('y') | KeyCode::Enter => {
+                    let result = close_tcp_connection(
+                        dialog.local,
+                        dialog.remote,
+                        dialog.namespace.as_ref(),
+                    )
+                    .map(|()| {
+                        format!(
+                            "closed {} -> {} ({})",
+                            dialog.local, dialog.remote, dialog.process_name
+                        )
+                    });
+
+                    self.close_dialog = None;
+                    self.report(result);
+                }
+                KeyCode::Char('n') | KeyCode::Esc => self.close_dialog = None,
+                _ => {}
+            },
+            None => self.on_kill_dialog_key(key),
+        }
+    }
+
+    /// Shows the outcome of an action and keeps it in the event log.
+    fn report(&mut self, result: Result<String, String>) {
+        if self.events.len() == MAX_EVENTS {
+            self.events.remove(0);
+        }
+
+        self.events.push(LogEntry {
+            time: Instant::now(),
+            result: result.clone(),
+        });
+
+        self.command.message = Some(result);
+    }
+
+    fn on_kill_dialog_key(&mut self, key: KeyEvent) {
+        let dialog = match &mut self.kill_dialog {
+            Some(dialog) => dialog,
+            None => return,
+        };
+
+        if let Some(signal) = dialog.confirm {
+            match key.code {
+                KeyCode::Char('y') | KeyCode::Enter => {
+                    let result = signal_processes(&dialog.pids, signal, &dialog.process_name);
+                    self.kill_dialog = None;
+                    self.report(result);
+                }
+                KeyCode::Char('n') | KeyCode::Esc => dialog.confirm = None,
+                _ => {}
+            }
+
+            return;
+        }
+
+        dialog.error = None;
+        let custom = dialog.is_custom();
+
+        match key.code {
+            KeyCode::Esc => self.kill_dialog = None,
+            KeyCode::Enter => match dialog.chosen_signal() {
+                Ok(signal) => dialog.confirm = Some(signal),
+                Err(e) => dialog.error = Some(e),
+            },
+            KeyCode::Up => dialog.cursor = dialog.cursor.saturating_sub(1),
+            KeyCode::Down => dialog.cursor = (dialog.cursor + 1).min(KillDialog::SIGNALS.len()),
+            KeyCode::Backspace if custom => {
+                dialog.custom.pop();
+            }
+            KeyCode::Char(c)
+                if custom
+                    && !key
+                        .modifiers
+                        .intersects(KeyModifiers::CONTROL | KeyModifiers::ALT) =>
+            {
+                dialog.custom.push(c);
+            }
+            KeyCode::Char('k') => dialog.cursor = dialog.cursor.saturating_sub(1),
+            KeyCode::Char('j') => {
+                dialog.cursor = (dialog.cursor + 1).min(KillDialog::SIGNALS.len())
+            }
+            _ => {}
+        }
     }
 
     pub fn on_left(&mut self) {
         self.tabs.previous();
+        self.on_tab_changed();
     }
 
     pub fn on_right(&mut self) {
         self.tabs.next();
+        self.on_tab_changed();
     }
 
-    pub fn on_key(&mut self, c: char) {
-        match c {
-            '/' => {
-                self.filter.mode = FilterMode::Typing;
+    /// The Listeners tab shows how reachable each listener is, next to its port. The other
+    /// tabs keep the columns they had.
+    fn on_tab_changed(&mut self) {
+        if !self.tabs.is_listeners() {
+            let added = std::mem::take(&mut self.listener_columns);
+
+            // Unless they are all that is left
+            if self.columns.iter().any(|column| !added.contains(column)) {
+                self.columns.retain(|column| !added.contains(column));
+                self.keep_sort_column();
+            }
+
+            return;
+        }
+
+        let mut index = self
+            .columns
+            .iter()
+            .position(|column| *column == Column::LocalPort)
+            .map_or(self.columns.len(), |index| index + 1);
+
+        for column in Column::LISTENERS {
+            if !self.columns.contains(&column) {
+                self.columns.insert(index, column);
+                self.listener_columns.push(column);
+                index += 1;
             }
-            'c' => {
+        }
+    }
+
+    pub fn on_action(&mut self, action: Action) {
+        self.command.message = None;
+
+        match action {
+            Action::Quit => self.should_quit = true,
+            Action::Help => self.on_show_help(),
+            Action::Pause => self.on_pause(),
+            Action::Filter => self.filter.mode = FilterMode::Typing,
+            Action::Command => self.filter.mode = FilterMode::Command,
+            Action::ClearFilter => {
                 self.filter.input.clear();
-                self.update_regex();
+                self.update_filter();
             }
-            'G' => {
-                self.on_last();
+            Action::ConfirmFilter => {
+                self.update_filter();
+                self.filter.mode = FilterMode::Normal;
             }
-            'h' => {
-                self.on_left();
+            Action::DeleteChar => {
+                self.filter.input.pop();
+                self.update_filter();
             }
-            'j' => {
-                self.on_down();
+            Action::Up => self.on_up(),
+            Action::Down => self.on_down(),
+            Action::PageUp => self.connection_table.previous_page(self.page_size()),
+            Action::PageDown => self.connection_table.next_page(self.page_size()),
+            Action::First => self.on_first(),
+            Action::Last => self.on_last(),
+            Action::Left => self.on_left(),
+            Action::Right => self.on_right(),
+            Action::Info => self.show_connection_info = !self.show_connection_info,
+            Action::JumpToPeer => self.on_jump_to_peer(),
+            Action::Kill => {
+                if let Err(e) = self.open_kill_dialog(None) {
+                    self.command.message = Some(Err(e));
+                }
             }
-            'k' => {
-                self.on_up();
+            Action::CloseConnection => {
+                if let Err(e) = self.open_close_dialog() {
+                    self.command.message = Some(Err(e));
+                }
             }
-            'l' => {
-                self.on_right();
+            Action::Events => self.show_events = !self.show_events,
+            Action::NextNamespace => self.on_next_namespace(),
+            Action::NextSort => self.on_next_sort_column(),
+            Action::ReverseSort => self.on_reverse_sort(),
+            Action::TcpInternals => self.on_toggle_tcp_internals(),
+            Action::TopTalkers => self.on_top_talkers(),
+            Action::Queues => self.on_toggle_queues(),
+            Action::Columns => self.on_toggle_column_editor(),
+            Action::ToggleColumn => self.on_toggle_column(),
+            Action::MoveColumnLeft => self.on_move_column(false),
+            Action::MoveColumnRight => self.on_move_column(true),
+            Action::Stats => self.on_toggle_stats(),
+            Action::NextState => self.on_next_state_filter(),
+            Action::AllStates => self.state_filter = None,
+        }
+    }
+
+    /// Keys of the command line, which are fixed like in a shell.
+    pub fn on_command_key(&mut self, key: KeyEvent) {
+        match key.code {
+            KeyCode::Enter => {
+                let input = self.command.take();
+                self.filter.mode = FilterMode::Normal;
+
+                self.command.message = match command::run(self, &input) {
+                    Ok(message) if message.is_empty() => None,
+                    result => Some(result),
+                };
             }
-            'i' => {
-                self.show_connection_info = !self.show_connection_info;
+            KeyCode::Esc => {
+                self.command.clear();
+                self.filter.mode = FilterMode::Normal;
+            }
+            KeyCode::Tab => {
+                let candidates = command::candidates(self, &self.command.input);
+                self.command.complete(candidates);
+            }
+            KeyCode::Up => self.command.previous(),
+            KeyCode::Down => self.command.next(),
+            KeyCode::Backspace if self.command.input.is_empty() => {
+                self.filter.mode = FilterMode::Normal;
+            }
+            KeyCode::Backspace => self.command.pop(),
+            KeyCode::Char('u') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                self.command.clear();
+            }
+            KeyCode::Char(c)
+                if !key
+                    .modifiers
+                    .intersects(KeyModifiers::CONTROL | KeyModifiers::ALT) =>
+            {
+                self.command.push(c);
             }
             _ => {}
         }
     }
+
+    /// Typed characters go to the filter.
+    pub fn on_filter_input(&mut self, c: char) {
+        self.filter.input.push(c);
+        self.update_filter();
+    }
+
+    /// Rows visible in the table, without its borders and column titles.
+    pub fn page_size(&self) -> usize {
+        let chrome = match self.compact {
+            true => 2,
+            false => 3,
+        };
+
+        (self.areas.table.height.saturating_sub(chrome) as usize).max(1)
+    }
 }
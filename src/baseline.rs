@@ -0,0 +1,409 @@
+// The listeners and outbound destinations a host is expected to have, to spot new ones.
+use std::collections::{BTreeSet, HashSet};
+use std::path::{Path, PathBuf};
+
+use netstat2::TcpState;
+use serde::{Deserialize, Serialize};
+
+use crate::config::Config;
+use crate::os::{get_ephemeral_port_range, Protocol, SocketInfoWithProcName};
+
+/// A listening port, by scope rather than address so a baseline fits hosts with other IPs.
+#[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ListenerEntry {
+    pub protocol: String,
+    pub port: u16,
+    /// all, address or loopback
+    pub scope: String,
+    pub process: String,
+}
+
+/// A remote endpoint a process connects to, `*` as the host matches any host.
+#[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct OutboundEntry {
+    pub process: String,
+    pub host: String,
+    pub port: u16,
+}
+
+/// Sockets of processes nets couldn't find, e.g. other users' when it isn't root.
+const UNKNOWN_PROCESS: &str = "-";
+
+/// An unknown process matches any, so a baseline saved as root can be checked without
+/// privileges and the other way around.
+fn same_process(a: &str, b: &str) -> bool {
+    a == b || a == UNKNOWN_PROCESS || b == UNKNOWN_PROCESS
+}
+
+impl ListenerEntry {
+    fn matches(&self, other: &ListenerEntry) -> bool {
+        self.protocol == other.protocol
+            && self.port == other.port
+            && self.scope == other.scope
+            && same_process(&self.process, &other.process)
+    }
+}
+
+impl OutboundEntry {
+    fn matches(&self, other: &OutboundEntry) -> bool {
+        same_process(&self.process, &other.process)
+            && self.port == other.port
+            && (self.host == "*" || self.host == other.host)
+    }
+}
+
+#[derive(Default, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Baseline {
+    #[serde(default)]
+    pub listeners: BTreeSet<ListenerEntry>,
+    #[serde(default)]
+    pub outbound: BTreeSet<OutboundEntry>,
+}
+
+/// What changed since a baseline was saved.
+#[derive(Default, Serialize)]
+pub struct Drift {
+    pub new_listeners: BTreeSet<ListenerEntry>,
+    pub new_outbound: BTreeSet<OutboundEntry>,
+    /// Listeners of the baseline that are gone, reported but not counted as drift.
+    pub missing_listeners: BTreeSet<ListenerEntry>,
+}
+
+impl Drift {
+    pub fn is_empty(&self) -> bool {
+        self.new_listeners.is_empty() && self.new_outbound.is_empty()
+    }
+}
+
+/// What a socket stands for in a baseline, if anything.
+enum Entry {
+    Listener(ListenerEntry),
+    Outbound(OutboundEntry),
+}
+
+/// Describes the listeners and outbound connections of a snapshot, the other sockets are
+/// left out. Connections to a listening port are accepted ones, not outbound.
+fn entries(sockets: &[SocketInfoWithProcName]) -> Vec<Option<Entry>> {
+    let ephemeral = get_ephemeral_port_range();
+    let listening: HashSet<u16> = sockets
+        .iter()
+        .filter(|socket| socket.tcp_state() == Some(TcpState::Listen))
+        .filter_map(|socket| socket.local_port())
+        .collect();
+
+    sockets
+        .iter()
+        .map(|socket| {
+            if socket.is_listener() {
+                let port = socket.local_port()?;
+
+                // Unconnected UDP sockets on ephemeral ports are clients, e.g. DNS lookups
+                if socket.protocol_flags == Protocol::UDP && ephemeral.contains(&port) {
+                    return None;
+                }
+
+                return Some(Entry::Listener(ListenerEntry {
+                    protocol: socket.service_protocol()?.to_string(),
+                    port,
+                    scope: socket.bind_scope()?.to_string(),
+                    process: socket.process_name.clone(),
+                }));
+            }
+
+            let (local, remote) = socket.tcp_endpoints()?;
+
+            match socket.tcp_state()? {
+                TcpState::Established | TcpState::SynSent if !listening.contains(&local.port()) => {
+                    Some(Entry::Outbound(OutboundEntry {
+                        process: socket.process_name.clone(),
+                        host: remote.ip().to_string(),
+                        port: remote.port(),
+                    }))
+                }
+                _ => None,
+            }
+        })
+        .collect()
+}
+
+impl Baseline {
+    /// `baseline.toml` next to the default config file.
+    pub fn default_path() -> Option<PathBuf> {
+        Some(Config::default_path()?.with_file_name("baseline.toml"))
+    }
+
+    pub fn load(path: &Path) -> Result<Baseline, String> {
+        let contents = std::fs::read_to_string(path)
+            .map_err(|e| format!("can't read baseline {}: {}", path.display(), e))?;
+
+        toml::from_str(&contents).map_err(|e| format!("invalid baseline {}: {}", path.display(), e))
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        let contents = toml::to_string(self).map_err(|e| e.to_string())?;
+
+        if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+            std::fs::create_dir_all(dir)
+                .map_err(|e| format!("can't create {}: {}", dir.display(), e))?;
+        }
+
+        std::fs::write(path, contents)
+            .map_err(|e| format!("can't write baseline {}: {}", path.display(), e))
+    }
+
+    /// Adds the listeners and outbound destinations of a snapshot. Outbound destinations
+    /// are recorded by port for any host, unless `hosts` keeps their addresses.
+    pub fn add_snapshot(&mut self, sockets: &[SocketInfoWithProcName], hosts: bool) {
+        for entry in entries(sockets).into_iter().flatten() {
+            match entry {
+                Entry::Listener(listener) => self.listeners.insert(listener),
+                Entry::Outbound(outbound) if hosts => self.outbound.insert(outbound),
+                Entry::Outbound(outbound) => self.outbound.insert(OutboundEntry {
+                    host: String::from("*"),
+                    ..outbound
+                }),
+            };
+        }
+    }
+
+    fn contains(&self, entry: &Entry) -> bool {
+        match entry {
+            Entry::Listener(listener) => self.listeners.iter().any(|known| known.matches(listener)),
+            Entry::Outbound(outbound) => self.outbound.iter().any(|known| known.matches(outbound)),
+        }
+    }
+
+    /// Flags the listeners and outbound connections that aren't in the baseline.
+    pub fn mark_drift(&self, sockets: &mut [SocketInfoWithProcName]) {
+        let entries = entries(sockets);
+
+        for (socket, entry) in sockets.iter_mut().zip(entries) {
+            socket.drift = entry.is_some_and(|entry| !self.contains(&entry));
+        }
+    }
+
+    pub fn check(&self, sockets: &[SocketInfoWithProcName]) -> Drift {
+        let mut drift = Drift::default();
+        let mut seen = Vec::new();
+
+        for entry in entries(sockets).into_iter().flatten() {
+            if let Entry::Listener(listener) = &entry {
+                seen.push(listener.clone());
+            }
+
+            if self.contains(&entry) {
+                continue;
+            }
+
+            match entry {
+                Entry::Listener(listener) => drift.new_listeners.insert(listener),
+                Entry::Outbound(outbound) => drift.new_outbound.insert(outbound),
+            };
+        }
+
+        drift.missing_listeners = self
+            .listeners
+            .iter()
+            .filter(|known| !seen.iter().any(|listener| known.matches(listener)))
+            .cloned()
+            .collect();
+        drift
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::os::fixtures::{owned_by, tcp, tcp_listener, udp};
+
+    #[test]
+    fn listeners_leave_out_udp_clients_on_ephemeral_ports() {
+        let client_port = *get_ephemeral_port_range().start();
+
+        let mut baseline = Baseline::default();
+        baseline.add_snapshot(
+            &[
+                tcp_listener("0.0.0.0:22", "sshd"),
+                udp("127.0.0.1:53", "unbound"),
+                udp(&format!("0.0.0.0:{}", client_port), "unbound"),
+            ],
+            false,
+        );
+
+        let listeners: Vec<(&str, u16, &str)> = baseline
+            .listeners
+            .iter()
+            .map(|listener| {
+                (
+                    listener.protocol.as_str(),
+                    listener.port,
+                    listener.scope.as_str(),
+                )
+            })
+            .collect();
+
+        assert_eq!(listeners, [("tcp", 22, "all"), ("udp", 53, "loopback")]);
+    }
+
+    #[test]
+    fn check_reports_new_and_missing_entries() {
+        let mut baseline = Baseline::default();
+        baseline.add_snapshot(
+            &[
+                tcp_listener("0.0.0.0:22", "sshd"),
+                tcp_listener("127.0.0.1:5432", "postgres"),
+            ],
+            false,
+        );
+        baseline.outbound.insert(OutboundEntry {
+            process: String::from("app"),
+            host: String::from("*"),
+            port: 443,
+        });
+
+        let drift = baseline.check(&[
+            tcp_listener("0.0.0.0:22", "sshd"),
+            tcp_listener("0.0.0.0:8080", "app"),
+            tcp(
+                "10.0.0.1:40000",
+                "10.0.0.9:443",
+                TcpState::Established,
+                "app",
+            ),
+            tcp(
+                "10.0.0.1:40001",
+                "10.0.0.9:25",
+                TcpState::Established,
+                "app",
+            ),
+            // Accepted, not outbound
+            tcp(
+                "10.0.0.1:22",
+                "10.0.0.7:50000",
+                TcpState::Established,
+                "sshd",
+            ),
+        ]);
+
+        assert_eq!(
+            drift
+                .new_listeners
+                .iter()
+                .map(|l| l.port)
+                .collect::<Vec<_>>(),
+            [8080]
+        );
+        assert_eq!(
+            drift
+                .new_outbound
+                .iter()
+                .map(|o| o.port)
+                .collect::<Vec<_>>(),
+            [25]
+        );
+        assert_eq!(
+            drift
+                .missing_listeners
+                .iter()
+                .map(|l| l.port)
+                .collect::<Vec<_>>(),
+            [5432]
+        );
+    }
+
+    fn outbound(baseline: &Baseline) -> Vec<(&str, &str, u16)> {
+        baseline
+            .outbound
+            .iter()
+            .map(|o| (o.process.as_str(), o.host.as_str(), o.port))
+            .collect()
+    }
+
+    #[test]
+    fn records_outbound_ports_for_any_host_by_default() {
+        let sockets = [
+            tcp(
+                "10.0.0.1:40000",
+                "10.0.0.9:443",
+                TcpState::Established,
+                "app",
+            ),
+            tcp(
+                "10.0.0.1:40001",
+                "10.0.0.8:443",
+                TcpState::Established,
+                "app",
+            ),
+        ];
+
+        let mut baseline = Baseline::default();
+        baseline.add_snapshot(&sockets, false);
+        assert_eq!(outbound(&baseline), [("app", "*", 443)]);
+
+        let mut baseline = Baseline::default();
+        baseline.add_snapshot(&sockets, true);
+        assert_eq!(
+            outbound(&baseline),
+            [("app", "10.0.0.8", 443), ("app", "10.0.0.9", 443)]
+        );
+        assert!(baseline
+            .check(&[tcp(
+                "10.0.0.1:40002",
+                "10.0.0.7:443",
+                TcpState::Established,
+                "app"
+            )])
+            .new_outbound
+            .iter()
+            .any(|o| o.host == "10.0.0.7"));
+    }
+
+    #[test]
+    fn unknown_processes_match_any_process() {
+        let mut baseline = Baseline::default();
+        baseline.add_snapshot(
+            &[
+                tcp_listener("0.0.0.0:22", "sshd"),
+                tcp(
+                    "10.0.0.1:40000",
+                    "10.0.0.9:443",
+                    TcpState::Established,
+                    "app",
+                ),
+            ],
+            false,
+        );
+
+        // Checked without the privileges to find root's processes
+        let drift = baseline.check(&[
+            owned_by(tcp_listener("0.0.0.0:22", "sshd"), None),
+            owned_by(
+                tcp(
+                    "10.0.0.1:40001",
+                    "10.0.0.9:443",
+                    TcpState::Established,
+                    "app",
+                ),
+                None,
+            ),
+        ]);
+
+        assert!(drift.is_empty());
+        assert!(drift.missing_listeners.is_empty());
+
+        // The port and scope still have to match
+        let drift = baseline.check(&[owned_by(tcp_listener("0.0.0.0:2222", "sshd"), None)]);
+        assert_eq!(
+            drift
+                .new_listeners
+                .iter()
+                .map(|l| (l.port, l.process.as_str()))
+                .collect::<Vec<_>>(),
+            [(2222, "-")]
+        );
+        assert_eq!(drift.missing_listeners.len(), 1);
+    }
+}
@@ -0,0 +1,170 @@
+use std::path::PathBuf;
+use std::time::Duration;
+
+use clap::{ArgGroup, Args, Parser, Subcommand, ValueEnum};
+
+use crate::utils::parse_duration;
+
+#[derive(Parser)]
+#[command(
+    name = "nets",
+    version,
+    about = "A cross-platform network connection tui"
+)]
+pub struct Cli {
+    /// Config file to use instead of $XDG_CONFIG_HOME/nets/config.toml
+    #[arg(long, global = true, value_name = "PATH")]
+    pub config: Option<PathBuf>,
+
+    /// Network namespace to list, by name or inode, or `all` for every namespace
+    #[arg(long, value_name = "NAME")]
+    pub netns: Option<String>,
+
+    /// Baseline file whose missing listeners and outbound destinations are highlighted
+    #[arg(long, value_name = "PATH")]
+    pub baseline: Option<PathBuf>,
+
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(Subcommand)]
+pub enum Command {
+    /// Export a graph of which processes talk to which endpoints
+    Graph(GraphArgs),
+    /// Check the config file, or print the default one
+    Config(ConfigArgs),
+    /// Show which capabilities and data sources are available
+    Doctor,
+    /// List TCP listeners and UDP sockets and how reachable they are
+    Listeners(ListenersArgs),
+    /// Record the expected listeners and outbound destinations, or check for new ones
+    #[command(subcommand)]
+    Baseline(BaselineCommand),
+    /// Check rules about listeners and connections, exits with 1 when one fails
+    Assert(AssertArgs),
+    /// Wait for a listener or connection to appear or go away, exits with 1 on timeout
+    Wait(WaitArgs),
+}
+
+#[derive(Args)]
+#[command(group(ArgGroup::new("target").required(true).args(["listen", "connect"])))]
+pub struct WaitArgs {
+    /// Listener to wait for, as [tcp:|udp:][ADDRESS:]PORT, e.g. tcp:8080 or 127.0.0.1:53
+    #[arg(long, value_name = "LISTENER")]
+    pub listen: Option<String>,
+
+    /// Established TCP connection to wait for, as HOST:PORT or only a port for any host
+    #[arg(long, value_name = "ENDPOINT")]
+    pub connect: Option<String>,
+
+    /// Wait for the listener or connection to be gone instead
+    #[arg(long)]
+    pub closed: bool,
+
+    /// Only count sockets of this process, waiting for it to listen fails if it exits
+    #[arg(long)]
+    pub pid: Option<u32>,
+
+    /// Give up after this long (e.g. 30s), waits forever by default
+    #[arg(long, value_parser = parse_duration)]
+    pub timeout: Option<Duration>,
+
+    /// Time between two checks
+    #[arg(long, default_value = "200ms", value_parser = parse_duration)]
+    pub interval: Duration,
+}
+
+#[derive(Args)]
+pub struct AssertArgs {
+    /// Rules file, see the README for the kinds of rules
+    #[arg(long, value_name = "PATH")]
+    pub rules: PathBuf,
+
+    /// Output format of the report
+    #[arg(long, value_enum, default_value_t = ReportFormat::Text)]
+    pub format: ReportFormat,
+}
+
+#[derive(Subcommand)]
+pub enum BaselineCommand {
+    /// Save the current listeners and outbound destinations
+    Save(BaselineSaveArgs),
+    /// Compare with a saved baseline, exits with 1 when something is new
+    Check(BaselineCheckArgs),
+}
+
+#[derive(Args)]
+pub struct BaselineSaveArgs {
+    /// Baseline file, $XDG_CONFIG_HOME/nets/baseline.toml by default
+    pub path: Option<PathBuf>,
+
+    /// Number of snapshots to accumulate, to catch connections that come and go
+    #[arg(long, default_value_t = 1)]
+    pub samples: u32,
+
+    /// Time to wait between two snapshots (e.g. 500ms, 2s)
+    #[arg(long, default_value = "1s", value_parser = parse_duration)]
+    pub interval: Duration,
+
+    /// Record the remote address of outbound connections, not only their port
+    #[arg(long)]
+    pub hosts: bool,
+}
+
+#[derive(Args)]
+pub struct BaselineCheckArgs {
+    /// Baseline file, $XDG_CONFIG_HOME/nets/baseline.toml by default
+    pub path: Option<PathBuf>,
+
+    /// Output format of the report
+    #[arg(long, value_enum, default_value_t = ReportFormat::Text)]
+    pub format: ReportFormat,
+}
+
+#[derive(Args)]
+pub struct ConfigArgs {
+    /// Print the default config, a starting point for your own
+    #[arg(long)]
+    pub print_default: bool,
+}
+
+#[derive(Args)]
+pub struct GraphArgs {
+    /// Output format of the graph
+    #[arg(long, value_enum, default_value_t = GraphFormat::Dot)]
+    pub format: GraphFormat,
+
+    /// Number of snapshots to accumulate into the graph
+    #[arg(long, default_value_t = 1)]
+    pub samples: u32,
+
+    /// Time to wait between two snapshots (e.g. 500ms, 2s)
+    #[arg(long, default_value = "1s", value_parser = parse_duration)]
+    pub interval: Duration,
+}
+
+#[derive(Args)]
+pub struct ListenersArgs {
+    /// Output format of the report
+    #[arg(long, value_enum, default_value_t = ReportFormat::Text)]
+    pub format: ReportFormat,
+
+    /// Port allowed on all interfaces, like 22, tcp:443 or udp:53, on top of the
+    /// config's allowed_listeners
+    #[arg(long, value_name = "PORT")]
+    pub allow: Vec<String>,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+pub enum ReportFormat {
+    Text,
+    Json,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+pub enum GraphFormat {
+    Dot,
+    Mermaid,
+    Json,
+}
@@ -0,0 +1,371 @@
+use std::cmp::Ordering;
+
+use crate::os::{Cgroup, SocketInfoWithProcName};
+use crate::utils::{format_age, format_rate};
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum Column {
+    Protocol,
+    LocalAddress,
+    LocalPort,
+    RemoteAddress,
+    RemotePort,
+    State,
+    Pid,
+    ProcessName,
+    Peer,
+    Rtt,
+    RttVar,
+    Cwnd,
+    Ssthresh,
+    Retransmits,
+    Lost,
+    Unacked,
+    Mss,
+    Congestion,
+    RxRate,
+    TxRate,
+    TotalRate,
+    RecvQueue,
+    SendQueue,
+    /// Bytes held by the receive and send buffers, `skmem` in `ss -m`.
+    RecvMemory,
+    SendMemory,
+    Uid,
+    User,
+    Inode,
+    Age,
+    Service,
+    Hostname,
+    Namespace,
+    Unit,
+    Container,
+    Pod,
+    Scope,
+    Family,
+    Exposed,
+    Drift,
+}
+
+impl Column {
+    pub const ALL: [Column; 39] = [
+        Column::Protocol,
+        Column::LocalAddress,
+        Column::LocalPort,
+        Column::RemoteAddress,
+        Column::RemotePort,
+        Column::State,
+        Column::Pid,
+        Column::ProcessName,
+        Column::Peer,
+        Column::Rtt,
+        Column::RttVar,
+        Column::Cwnd,
+        Column::Ssthresh,
+        Column::Retransmits,
+        Column::Lost,
+        Column::Unacked,
+        Column::Mss,
+        Column::Congestion,
+        Column::RxRate,
+        Column::TxRate,
+        Column::TotalRate,
+        Column::RecvQueue,
+        Column::SendQueue,
+        Column::RecvMemory,
+        Column::SendMemory,
+        Column::Uid,
+        Column::User,
+        Column::Inode,
+        Column::Age,
+        Column::Service,
+        Column::Hostname,
+        Column::Namespace,
+        Column::Unit,
+        Column::Container,
+        Column::Pod,
+        Column::Scope,
+        Column::Family,
+        Column::Exposed,
+        Column::Drift,
+    ];
+
+    /// Added when going to the Listeners tab, to tell how reachable each listener is.
+    pub const LISTENERS: [Column; 3] = [Column::Scope, Column::Family, Column::Exposed];
+
+    /// TCP internals toggled on and off together, the rest is shown in the info pane.
+    pub const TCP_INTERNALS: [Column; 4] = [
+        Column::Rtt,
+        Column::Cwnd,
+        Column::Retransmits,
+        Column::Congestion,
+    ];
+
+    /// Socket queues, for listeners the accept queue and its backlog.
+    pub const QUEUES: [Column; 2] = [Column::RecvQueue, Column::SendQueue];
+
+    pub fn title(&self) -> &'static str {
+        match self {
+            Column::Protocol => "Protocol",
+            Column::LocalAddress => "Local Address",
+            Column::LocalPort => "Local Port",
+            Column::RemoteAddress => "Remote Address",
+            Column::RemotePort => "Remote Port",
+            Column::State => "State",
+            Column::Pid => "PID",
+            Column::ProcessName => "Process Name",
+            Column::Peer => "Peer",
+            Column::Rtt => "RTT",
+            Column::RttVar => "RTT Var",
+            Column::Cwnd => "Cwnd",
+            Column::Ssthresh => "Ssthresh",
+            Column::Retransmits => "Retrans",
+            Column::Lost => "Lost",
+            Column::Unacked => "Unacked",
+            Column::Mss => "MSS",
+            Column::Congestion => "CC",
+            Column::RxRate => "Rx/s",
+            Column::TxRate => "Tx/s",
+            Column::TotalRate => "Rx+Tx/s",
+            Column::RecvQueue => "Recv-Q",
+            Column::SendQueue => "Send-Q",
+            Column::RecvMemory => "Rmem",
+            Column::SendMemory => "Wmem",
+            Column::Uid => "UID",
+            Column::User => "User",
+            Column::Inode => "Inode",
+            Column::Age => "Age",
+            Column::Service => "Service",
+            Column::Hostname => "Hostname",
+            Column::Namespace => "Netns",
+            Column::Unit => "Unit",
+            Column::Container => "Container",
+            Column::Pod => "Pod",
+            Column::Scope => "Scope",
+            Column::Family => "Family",
+            Column::Exposed => "Exposed",
+            Column::Drift => "Drift",
+        }
+    }
+
+    /// Name used to refer to the column in filters, e.g. `rtt:>100ms`.
+    pub fn key(&self) -> &'static str {
+        match self {
+            Column::Protocol => "proto",
+            Column::LocalAddress => "laddr",
+            Column::LocalPort => "lport",
+            Column::RemoteAddress => "raddr",
+            Column::RemotePort => "rport",
+            Column::State => "state",
+            Column::Pid => "pid",
+            Column::ProcessName => "process",
+            Column::Peer => "peer",
+            Column::Rtt => "rtt",
+            Column::RttVar => "rttvar",
+            Column::Cwnd => "cwnd",
+            Column::Ssthresh => "ssthresh",
+            Column::Retransmits => "retrans",
+            Column::Lost => "lost",
+            Column::Unacked => "unacked",
+            Column::Mss => "mss",
+            Column::Congestion => "cc",
+            Column::RxRate => "rx",
+            Column::TxRate => "tx",
+            Column::TotalRate => "rate",
+            Column::RecvQueue => "recvq",
+            Column::SendQueue => "sendq",
+            Column::RecvMemory => "rmem",
+            Column::SendMemory => "wmem",
+            Column::Uid => "uid",
+            Column::User => "user",
+            Column::Inode => "inode",
+            Column::Age => "age",
+            Column::Service => "service",
+            Column::Hostname => "host",
+            Column::Namespace => "netns",
+            Column::Unit => "unit",
+            Column::Container => "container",
+            Column::Pod => "pod",
+            Column::Scope => "scope",
+            Column::Family => "family",
+            Column::Exposed => "exposed",
+            Column::Drift => "drift",
+        }
+    }
+
+    pub fn from_key(key: &str) -> Option<Column> {
+        Column::ALL
+            .iter()
+            .find(|column| column.key().eq_ignore_ascii_case(key))
+            .copied()
+    }
+
+    /// Whether the column holds a rate in bytes per second.
+    pub fn is_rate(&self) -> bool {
+        matches!(self, Column::RxRate | Column::TxRate | Column::TotalRate)
+    }
+
+    /// Whether the column holds a duration, filtered in milliseconds.
+    pub fn is_duration(&self) -> bool {
+        matches!(self, Column::Rtt | Column::RttVar | Column::Age)
+    }
+
+    pub fn text(&self, socket: &SocketInfoWithProcName) -> String {
+        match self.printable_index() {
+            Some(index) => socket.printable_string[index].clone(),
+            None => match (self, &socket.tcp_internals) {
+                (Column::Congestion, Some(internals)) => {
+                    internals.congestion.clone().unwrap_or_default()
+                }
+                (Column::Ssthresh, Some(internals)) if internals.ssthresh >= 0x7fff_ffff => {
+                    String::from("inf")
+                }
+                (Column::Peer, _) => socket
+                    .peer
+                    .as_ref()
+                    .map_or_else(String::new, |peer| peer.to_string()),
+                (Column::Age, _) => socket.age.map(format_age).unwrap_or_default(),
+                (Column::User, _) => socket.user.clone().unwrap_or_default(),
+                (Column::Service, _) => socket.service.clone().unwrap_or_default(),
+                (Column::Hostname, _) => socket.remote_host.clone().unwrap_or_default(),
+                (Column::Namespace, _) => socket.namespace.clone().unwrap_or_default(),
+                (Column::Unit, _) => cgroup_text(socket, |cgroup| &cgroup.unit),
+                (Column::Container, _) => cgroup_text(socket, |cgroup| &cgroup.container),
+                (Column::Pod, _) => cgroup_text(socket, |cgroup| &cgroup.pod),
+                (Column::Scope, _) => socket
+                    .bind_scope()
+                    .map_or_else(String::new, |scope| scope.to_string()),
+                (Column::Family, _) => socket
+                    .address_families()
+                    .map_or_else(String::new, |families| families.to_string()),
+                (Column::Exposed, _) => match socket.exposed {
+                    true => String::from("yes"),
+                    false => String::new(),
+                },
+                (Column::Drift, _) => match socket.drift {
+                    true => String::from("new"),
+                    false => String::new(),
+                },
+                _ => match self.number(socket) {
+                    Some(value) if self.is_duration() => format!("{:.2}ms", value),
+                    Some(value) if self.is_rate() => format_rate(value),
+                    Some(value) => value.to_string(),
+                    None => String::new(),
+                },
+            },
+        }
+    }
+
+    /// The text as shown in the table, filters and exports use the whole `text`.
+    pub fn cell(&self, socket: &SocketInfoWithProcName) -> String {
+        match self {
+            // Short IDs like docker ps shows them
+            Column::Container => self.text(socket).chars().take(12).collect(),
+            _ => self.text(socket),
+        }
+    }
+
+    /// Numeric value of the column, used to sort and to compare in filters.
+    pub fn number(&self, socket: &SocketInfoWithProcName) -> Option<f64> {
+        let internals = socket.tcp_internals.as_ref();
+        let queues = socket.queues.as_ref();
+
+        let value = match self {
+            Column::LocalPort => return socket.local_port().map(f64::from),
+            // The port cells can hold service names instead of numbers
+            Column::RemotePort => return socket.remote_endpoint().map(|(_, port)| f64::from(port)),
+            Column::Pid => {
+                return socket.printable_string[self.printable_index()?]
+                    .parse()
+                    .ok()
+            }
+            Column::RxRate => return socket.throughput.map(|t| t.rx),
+            Column::TxRate => return socket.throughput.map(|t| t.tx),
+            Column::TotalRate => return socket.throughput.map(|t| t.total()),
+            Column::Rtt => return internals.map(|i| f64::from(i.rtt_us) / 1000.0),
+            Column::RttVar => return internals.map(|i| f64::from(i.rttvar_us) / 1000.0),
+            Column::Cwnd => internals?.cwnd,
+            Column::Ssthresh => internals?.ssthresh,
+            Column::Retransmits => internals?.total_retransmits,
+            Column::Lost => internals?.lost,
+            Column::Unacked => internals?.unacked,
+            Column::Mss => internals?.mss,
+            Column::RecvQueue => queues?.recv_queue,
+            Column::SendQueue => queues?.send_queue,
+            Column::RecvMemory => queues?.memory?.rmem_alloc,
+            Column::SendMemory => queues?.memory?.wmem_alloc,
+            Column::Uid => socket.uid()?,
+            Column::Inode => return socket.inode().map(|inode| inode as f64),
+            Column::Age => return socket.age.map(|age| age.as_secs_f64() * 1000.0),
+            _ => return None,
+        };
+
+        Some(f64::from(value))
+    }
+
+    pub fn compare(&self, a: &SocketInfoWithProcName, b: &SocketInfoWithProcName) -> Ordering {
+        match (self.number(a), self.number(b)) {
+            (Some(a), Some(b)) => a.partial_cmp(&b).unwrap_or(Ordering::Equal),
+            (Some(_), None) => Ordering::Greater,
+            (None, Some(_)) => Ordering::Less,
+            (None, None) => self.text(a).cmp(&self.text(b)),
+        }
+    }
+
+    /// Position of the column in `printable_string`, for the columns stored there.
+    fn printable_index(&self) -> Option<usize> {
+        match self {
+            Column::Protocol => Some(0),
+            Column::LocalAddress => Some(1),
+            Column::LocalPort => Some(2),
+            Column::RemoteAddress => Some(3),
+            Column::RemotePort => Some(4),
+            Column::State => Some(5),
+            Column::Pid => Some(6),
+            Column::ProcessName => Some(7),
+            _ => None,
+        }
+    }
+}
+
+fn cgroup_text(
+    socket: &SocketInfoWithProcName,
+    field: impl Fn(&Cgroup) -> &Option<String>,
+) -> String {
+    socket
+        .cgroup
+        .as_ref()
+        .and_then(|cgroup| field(cgroup).clone())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::os::fixtures::udp;
+    use crate::os::{SocketMemory, SocketQueues};
+
+    #[test]
+    fn sorts_udp_sockets_by_queues_and_memory() {
+        let mut busy = udp("0.0.0.0:53", "dnsmasq");
+        busy.queues = Some(SocketQueues {
+            recv_queue: 2048,
+            send_queue: 0,
+            memory: Some(SocketMemory {
+                rmem_alloc: 4352,
+                rcvbuf: 212992,
+                wmem_alloc: 0,
+                sndbuf: 212992,
+                drops: 0,
+            }),
+        });
+        let idle = udp("0.0.0.0:123", "chronyd");
+
+        assert_eq!(Column::RecvQueue.number(&busy), Some(2048.0));
+        assert_eq!(Column::RecvMemory.number(&busy), Some(4352.0));
+        assert_eq!(Column::SendMemory.text(&busy), "0");
+        assert_eq!(Column::RecvMemory.number(&idle), None);
+        assert_eq!(Column::RecvMemory.compare(&busy, &idle), Ordering::Greater);
+        assert!(busy.has_queued_data());
+    }
+}
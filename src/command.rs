@@ -0,0 +1,557 @@
+// The `:` command line, for actions that take arguments.
+use std::path::{Path, PathBuf};
+
+use crate::app::{App, SortOrder};
+use crate::columns::Column;
+use crate::export::{export, ExportFormat};
+use crate::signal::{parse_signal, SIGNALS};
+use crate::utils::{parse_duration, TCP_STATES};
+
+/// Every command with its usage, as listed in the help.
+pub const COMMANDS: [(&str, &str); 9] = [
+    ("sort", "sort <column> [asc|desc]"),
+    ("filter", "filter [query]"),
+    (
+        "columns",
+        "columns +<column> -<column> | columns <column>...",
+    ),
+    ("export", "export json|csv <path>"),
+    ("interval", "interval <duration>"),
+    ("kill", "kill [signal]"),
+    ("close", "close"),
+    ("netns", "netns <name>|all"),
+    ("quit", "quit"),
+];
+
+/// Input, history and completions of the command line.
+#[derive(Default)]
+pub struct CommandLine {
+    pub input: String,
+    history: Vec<String>,
+    /// Position while going through the history, `None` while editing.
+    history_index: Option<usize>,
+    /// What was typed before going through the history.
+    draft: String,
+    pub completion: Option<Completion>,
+    /// Outcome of the last command, shown until the next key.
+    pub message: Option<Result<String, String>>,
+}
+
+pub struct Completion {
+    /// The input without the word being completed.
+    base: String,
+    pub candidates: Vec<String>,
+    pub index: usize,
+}
+
+impl CommandLine {
+    pub fn push(&mut self, c: char) {
+        self.input.push(c);
+        self.completion = None;
+    }
+
+    pub fn pop(&mut self) {
+        self.input.pop();
+        self.completion = None;
+    }
+
+    pub fn clear(&mut self) {
+        self.input.clear();
+        self.completion = None;
+        self.history_index = None;
+    }
+
+    /// Returns the input, remembering it in the history.
+    pub fn take(&mut self) -> String {
+        let input = self.input.trim().to_string();
+
+        if !input.is_empty() && self.history.last() != Some(&input) {
+            self.history.push(input.clone());
+        }
+
+        self.clear();
+        input
+    }
+
+    pub fn previous(&mut self) {
+        let index = match self.history_index {
+            Some(index) => index.saturating_sub(1),
+            None if self.history.is_empty() => return,
+            None => {
+                self.draft = self.input.clone();
+                self.history.len() - 1
+            }
+        };
+
+        self.history_index = Some(index);
+        self.input = self.history[index].clone();
+        self.completion = None;
+    }
+
+    pub fn next(&mut self) {
+        match self.history_index {
+            Some(index) if index + 1 < self.history.len() => {
+                self.history_index = Some(index + 1);
+                self.input = self.history[index + 1].clone();
+            }
+            Some(_) => {
+                self.history_index = None;
+                self.input = std::mem::take(&mut self.draft);
+            }
+            None => {}
+        }
+
+        self.completion = None;
+    }
+
+    /// Completes the last word, pressing tab again goes to the next candidate.
+    pub fn complete(&mut self, candidates: Vec<String>) {
+        if let Some(completion) = &mut self.completion {
+            let current = format!(
+                "{}{}",
+                completion.base, completion.candidates[completion.index]
+            );
+
+            if self.input == current {
+                completion.index = (completion.index + 1) % completion.candidates.len();
+                self.input = format!(
+                    "{}{}",
+                    completion.base, completion.candidates[completion.index]
+                );
+                return;
+            }
+        }
+
+        let base = self
+            .input
+            .trim_end_matches(|c: char| !c.is_whitespace())
+            .to_string();
+
+        match candidates.len() {
+            0 => self.completion = None,
+            // A single match is done, unless it continues like a directory or a filter term
+            1 if !candidates[0].ends_with(['/', ':']) => {
+                self.input = format!("{}{} ", base, candidates[0]);
+                self.completion = None;
+            }
+            _ => {
+                self.input = format!("{}{}", base, candidates[0]);
+                self.completion = Some(Completion {
+                    base,
+                    candidates,
+                    index: 0,
+                });
+            }
+        }
+    }
+}
+
+/// Finds a command by its name or the start of it, e.g. `so` for `sort`.
+fn find_command(name: &str) -> Result<&'static str, String> {
+    if name == "q" {
+        return Ok("quit");
+    }
+
+    let matches: Vec<&str> = COMMANDS
+        .iter()
+        .map(|(command, _)| *command)
+        .filter(|command| command.starts_with(name))
+        .collect();
+
+    match matches.as_slice() {
+        [command] => Ok(command),
+        [] => Err(format!("unknown command '{}'", name)),
+        _ => Err(format!(
+            "ambiguous command '{}': {}",
+            name,
+            matches.join(", ")
+        )),
+    }
+}
+
+/// The words before the one being completed, and that word. It is empty right after
+/// whitespace.
+fn split_last_word(input: &str) -> (Vec<&str>, &str) {
+    let mut words: Vec<&str> = input.split_whitespace().collect();
+
+    match input.ends_with(char::is_whitespace) {
+        true => (words, ""),
+        false => {
+            let word = words.pop().unwrap_or("");
+            (words, word)
+        }
+    }
+}
+
+/// Candidates for the last word of `input`.
+pub fn candidates(app: &App, input: &str) -> Vec<String> {
+    let (previous, word) = split_last_word(input);
+    let previous = previous.as_slice();
+
+    let column_keys = || Column::ALL.iter().map(|column| column.key().to_string());
+
+    let mut candidates: Vec<String> = match previous {
+        [] => COMMANDS
+            .iter()
+            .map(|(command, _)| command.to_string())
+            .collect(),
+        [command, rest @ ..] => match (find_command(command), rest.len()) {
+            (Ok("sort"), 0) => column_keys().collect(),
+            (Ok("sort"), 1) => vec![String::from("asc"), String::from("desc")],
+            (Ok("filter"), _) => match word.split_once(':') {
+                Some(("state", _)) => TCP_STATES
+                    .iter()
+                    .map(|state| format!("state:{}", state.to_string().to_lowercase()))
+                    .collect(),
+                _ => column_keys()
+                    .map(|key| format!("{}:", key))
+                    .chain(app.config.filters.keys().map(|name| format!("@{}", name)))
+                    .collect(),
+            },
+            (Ok("columns"), _) => {
+                let sign = word.chars().next().filter(|c| *c == '+' || *c == '-');
+
+                column_keys()
+                    .map(|key| match sign {
+                        Some(sign) => format!("{}{}", sign, key),
+                        None => key,
+                    })
+                    .collect()
+            }
+            (Ok("export"), 0) => ExportFormat::NAMES.iter().map(|f| f.to_string()).collect(),
+            (Ok("export"), 1) => complete_path(word),
+            (Ok("interval"), 0) => ["500ms", "1s", "2s", "5s"]
+                .iter()
+                .map(|interval| interval.to_string())
+                .collect(),
+            (Ok("netns"), 0) => app
+                .namespaces
+                .iter()
+                .map(|namespace| namespace.name.clone())
+                .chain([String::from("all")])
+                .collect(),
+            (Ok("kill"), 0) => SIGNALS.iter().map(|(name, _)| name.to_string()).collect(),
+            _ => Vec::new(),
+        },
+    };
+
+    candidates.retain(|candidate| candidate.starts_with(word));
+    candidates.dedup();
+    candidates
+}
+
+/// Files and directories starting with `word`, directories end with a '/'.
+fn complete_path(word: &str) -> Vec<String> {
+    let (dir, prefix) = match word.rfind('/') {
+        Some(i) => (&word[..=i], &word[i + 1..]),
+        None => ("", word),
+    };
+
+    let entries = match std::fs::read_dir(expand_home(if dir.is_empty() { "." } else { dir })) {
+        Ok(entries) => entries,
+        Err(_) => return Vec::new(),
+    };
+
+    let mut paths: Vec<String> = entries
+        .flatten()
+        .filter_map(|entry| {
+            let name = entry.file_name().to_string_lossy().to_string();
+
+            if !name.starts_with(prefix) || (name.starts_with('.') && !prefix.starts_with('.')) {
+                return None;
+            }
+
+            match entry.file_type().ok()?.is_dir() {
+                true => Some(format!("{}{}/", dir, name)),
+                false => Some(format!("{}{}", dir, name)),
+            }
+        })
+        .collect();
+
+    paths.sort();
+    paths
+}
+
+fn expand_home(path: &str) -> PathBuf {
+    match (path.strip_prefix("~/"), std::env::var_os("HOME")) {
+        (Some(rest), Some(home)) => Path::new(&home).join(rest),
+        _ => PathBuf::from(path),
+    }
+}
+
+/// Runs a command line, returning what it did or why it failed.
+pub fn run(app: &mut App, input: &str) -> Result<String, String> {
+    let words: Vec<&str> = input.split_whitespace().collect();
+
+    let (name, args) = match words.split_first() {
+        Some((name, args)) => (find_command(name)?, args),
+        None => return Ok(String::new()),
+    };
+
+    match name {
+        "sort" => sort(app, args),
+        "filter" => {
+            app.filter.input = args.join(" ");
+            app.update_filter();
+
+            match (&app.filter.error, args.is_empty()) {
+                (Some(e), _) => Err(e.clone()),
+                (None, true) => Ok(String::from("filter cleared")),
+                (None, false) => Ok(format!("filtering on {}", app.filter.input)),
+            }
+        }
+        "columns" => columns(app, args),
+        "export" => {
+            let (format, path) = match args {
+                [format, path] => (
+                    ExportFormat::from_name(format).ok_or_else(|| {
+                        format!("unknown format '{}', expected json or csv", format)
+                    })?,
+                    expand_home(path),
+                ),
+                _ => return Err(String::from("usage: export json|csv <path>")),
+            };
+
+            export(&path, format, &app.columns, &app.connection_table.items)
+                .map_err(|e| format!("can't export to {}: {}", path.display(), e))?;
+
+            Ok(format!(
+                "exported {} connections to {}",
+                app.connection_table.items.len(),
+                path.display()
+            ))
+        }
+        "interval" => {
+            let interval = match args {
+                [interval] => parse_duration(interval)?,
+                _ => return Err(String::from("usage: interval <duration>")),
+            };
+
+            if interval.is_zero() {
+                return Err(String::from("the interval must be more than 0"));
+            }
+
+            app.config.refresh_interval = interval;
+            Ok(format!("refreshing every {:?}", interval))
+        }
+        "kill" => kill(app, args),
+        "netns" => match args {
+            [name] => app.set_namespace(name),
+            _ => Err(String::from("usage: netns <name>|all")),
+        },
+        "close" => {
+            app.open_close_dialog()?;
+            Ok(String::new())
+        }
+        "quit" => {
+            app.should_quit = true;
+            Ok(String::new())
+        }
+        _ => unreachable!("every command is handled"),
+    }
+}
+
+fn sort(app: &mut App, args: &[&str]) -> Result<String, String> {
+    let (column, order) = match args {
+        [column] => (column, None),
+        [column, order] => (column, Some(order)),
+        _ => return Err(String::from("usage: sort <column> [asc|desc]")),
+    };
+
+    let column = Column::from_key(column).ok_or_else(|| format!("unknown column '{}'", column))?;
+
+    let descending = match order {
+        None => app.sort.descending,
+        Some(&"asc") => false,
+        Some(&"desc") => true,
+        Some(order) => return Err(format!("unknown order '{}', expected asc or desc", order)),
+    };
+
+    app.sort = SortOrder { column, descending };
+
+    Ok(format!(
+        "sorted by {} {}",
+        column.title(),
+        match descending {
+            true => "descending",
+            false => "ascending",
+        }
+    ))
+}
+
+/// `+key` shows a column, `-key` hides it, and bare keys replace the visible columns.
+fn columns(app: &mut App, args: &[&str]) -> Result<String, String> {
+    if args.is_empty() {
+        return Err(String::from(
+            "usage: columns +<column> -<column> | columns <column>...",
+        ));
+    }
+
+    let parse =
+        |key: &str| Column::from_key(key).ok_or_else(|| format!("unknown column '{}'", key));
+
+    let mut columns = app.columns.clone();
+
+    if args.iter().all(|arg| arg.starts_with(['+', '-'])) {
+        for arg in args {
+            let column = parse(&arg[1..])?;
+
+            match arg.starts_with('+') {
+                true if !columns.contains(&column) => columns.push(column),
+                true => {}
+                false => columns.retain(|c| *c != column),
+            }
+        }
+    } else {
+        columns = args
+            .iter()
+            .map(|key| parse(key))
+            .collect::<Result<_, _>>()?;
+    }
+
+    if columns.is_empty() {
+        return Err(String::from("at least one column is needed"));
+    }
+
+    app.columns = columns;
+    app.keep_sort_column();
+
+    Ok(format!(
+        "columns: {}",
+        app.columns
+            .iter()
+            .map(|column| column.key())
+            .collect::<Vec<_>>()
+            .join(" ")
+    ))
+}
+
+/// Opens the kill dialog for the selected connection, with the signal to confirm if given.
+fn kill(app: &mut App, args: &[&str]) -> Result<String, String> {
+    let signal = match args {
+        [] => None,
+        [signal] => {
+            Some(parse_signal(signal).ok_or_else(|| format!("unknown signal '{}'", signal))?)
+        }
+        _ => return Err(String::from("usage: kill [signal]")),
+    };
+
+    app.open_kill_dialog(signal)?;
+
+    Ok(String::new())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn command_line(history: &[&str]) -> CommandLine {
+        let mut line = CommandLine::default();
+
+        for input in history {
+            line.input = input.to_string();
+            line.take();
+        }
+
+        line
+    }
+
+    fn candidates(words: &[&str]) -> Vec<String> {
+        words.iter().map(|word| word.to_string()).collect()
+    }
+
+    #[test]
+    fn remembers_commands_once() {
+        let mut line = command_line(&["sort rtt", "  sort rtt ", "", "quit"]);
+
+        line.previous();
+        assert_eq!(line.input, "quit");
+        line.previous();
+        assert_eq!(line.input, "sort rtt");
+        // Stays on the oldest command
+        line.previous();
+        assert_eq!(line.input, "sort rtt");
+    }
+
+    #[test]
+    fn goes_back_to_the_draft_after_the_history() {
+        let mut line = command_line(&["sort rtt", "quit"]);
+        line.input = String::from("exp");
+
+        line.previous();
+        line.previous();
+        line.next();
+        assert_eq!(line.input, "quit");
+        line.next();
+        assert_eq!(line.input, "exp");
+        line.next();
+        assert_eq!(line.input, "exp");
+    }
+
+    #[test]
+    fn cycles_through_completions() {
+        let mut line = CommandLine {
+            input: String::from("sort r"),
+            ..Default::default()
+        };
+
+        line.complete(candidates(&["rport", "rtt", "rttvar"]));
+        assert_eq!(line.input, "sort rport");
+        line.complete(Vec::new());
+        assert_eq!(line.input, "sort rtt");
+        line.complete(Vec::new());
+        line.complete(Vec::new());
+        assert_eq!(line.input, "sort rport");
+
+        // Typing starts over
+        line.push('x');
+        line.complete(candidates(&["rportx"]));
+        assert_eq!(line.input, "sort rportx ");
+        assert!(line.completion.is_none());
+    }
+
+    #[test]
+    fn keeps_completing_directories_and_filter_terms() {
+        let mut line = CommandLine {
+            input: String::from("filter st"),
+            ..Default::default()
+        };
+
+        line.complete(candidates(&["state:"]));
+        assert_eq!(line.input, "filter state:");
+        assert!(line.completion.is_some());
+    }
+
+    #[test]
+    fn completes_after_any_whitespace() {
+        assert_eq!(split_last_word(""), (Vec::new(), ""));
+        assert_eq!(split_last_word("so"), (Vec::new(), "so"));
+        assert_eq!(split_last_word("sort r"), (vec!["sort"], "r"));
+        assert_eq!(split_last_word("sort "), (vec!["sort"], ""));
+        assert_eq!(split_last_word("\u{3000}"), (Vec::new(), ""));
+        assert_eq!(split_last_word("sort\u{a0}"), (vec!["sort"], ""));
+
+        let mut line = CommandLine {
+            input: String::from("sort\u{3000}"),
+            ..Default::default()
+        };
+        line.complete(candidates(&["rtt", "rport"]));
+        assert_eq!(line.input, "sort\u{3000}rtt");
+    }
+
+    #[test]
+    fn finds_commands_by_prefix() {
+        assert_eq!(find_command("sort"), Ok("sort"));
+        assert_eq!(find_command("exp"), Ok("export"));
+        assert_eq!(find_command("q"), Ok("quit"));
+        assert_eq!(find_command("qu"), Ok("quit"));
+        assert_eq!(
+            find_command("c"),
+            Err(String::from("ambiguous command 'c': columns, close"))
+        );
+        assert_eq!(
+            find_command("frobnicate"),
+            Err(String::from("unknown command 'frobnicate'"))
+        );
+    }
+}
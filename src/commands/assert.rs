@@ -0,0 +1,45 @@
+use crate::cli::{AssertArgs, ReportFormat};
+use crate::commands::EXIT_FAILED;
+use crate::os::{get_all_socket_info, Protocol};
+use crate::rules::{RuleResult, Rules};
+
+fn to_text(results: &[RuleResult]) -> String {
+    let mut out = String::new();
+
+    for result in results {
+        let status = match result.passed {
+            true => "PASS",
+            false => "FAIL",
+        };
+
+        out.push_str(&format!("{}  {} {}\n", status, result.kind, result.rule));
+
+        for failure in &result.failures {
+            out.push_str(&format!("        {}\n", failure));
+        }
+    }
+
+    let failed = results.iter().filter(|result| !result.passed).count();
+
+    match failed {
+        0 => out.push_str(&format!("\nAll {} rules passed\n", results.len())),
+        _ => out.push_str(&format!("\n{} of {} rules failed\n", failed, results.len())),
+    }
+
+    out
+}
+
+pub fn run(args: AssertArgs) -> Result<i32, Box<dyn std::error::Error>> {
+    let rules = Rules::load(&args.rules)?;
+    let results = rules.evaluate(&get_all_socket_info(Protocol::all())?);
+
+    match args.format {
+        ReportFormat::Text => print!("{}", to_text(&results)),
+        ReportFormat::Json => println!("{}", serde_json::to_string_pretty(&results)?),
+    }
+
+    match results.iter().all(|result| result.passed) {
+        true => Ok(0),
+        false => Ok(EXIT_FAILED),
+    }
+}
@@ -0,0 +1,146 @@
+use std::path::{Path, PathBuf};
+
+use crate::baseline::{Baseline, Drift, ListenerEntry, OutboundEntry};
+use crate::cli::{BaselineCheckArgs, BaselineCommand, BaselineSaveArgs, ReportFormat};
+use crate::commands::EXIT_FAILED;
+use crate::os::{get_all_socket_info, Protocol, SocketInfoWithProcName};
+
+fn path_or_default(path: Option<PathBuf>) -> Result<PathBuf, String> {
+    path.or_else(Baseline::default_path)
+        .ok_or_else(|| String::from("no default baseline path could be found, give one"))
+}
+
+fn listener_text(listener: &ListenerEntry) -> String {
+    format!(
+        "{} {} ({}) {}",
+        listener.protocol, listener.port, listener.scope, listener.process
+    )
+}
+
+fn outbound_text(outbound: &OutboundEntry) -> String {
+    format!(
+        "{} -> {}:{}",
+        outbound.process, outbound.host, outbound.port
+    )
+}
+
+fn to_text(drift: &Drift, path: &Path) -> String {
+    let mut out = String::new();
+
+    let sections = [
+        (
+            "New listeners",
+            drift.new_listeners.iter().map(listener_text).collect(),
+        ),
+        (
+            "New outbound destinations",
+            drift.new_outbound.iter().map(outbound_text).collect(),
+        ),
+        (
+            "Missing listeners",
+            drift
+                .missing_listeners
+                .iter()
+                .map(listener_text)
+                .collect::<Vec<_>>(),
+        ),
+    ];
+
+    for (title, lines) in sections {
+        if lines.is_empty() {
+            continue;
+        }
+
+        out.push_str(title);
+        out.push('\n');
+
+        for line in lines {
+            out.push_str(&format!("  {}\n", line));
+        }
+
+        out.push('\n');
+    }
+
+    match drift.is_empty() {
+        true => out.push_str(&format!("Nothing new since {}\n", path.display())),
+        false => out.push_str(&format!(
+            "{} new since {}\n",
+            drift.new_listeners.len() + drift.new_outbound.len(),
+            path.display()
+        )),
+    }
+
+    out
+}
+
+/// Sockets of processes nets can't find show `-` as their process, which the baseline
+/// matches with any process.
+fn warn_unattributed(sockets: &[SocketInfoWithProcName]) {
+    let unattributed = sockets
+        .iter()
+        .filter(|socket| socket.is_unattributed())
+        .count();
+
+    if unattributed > 0 {
+        eprintln!(
+            "nets: {} sockets show no process, they match any process of the baseline. Run as root or see nets doctor.",
+            unattributed
+        );
+    }
+}
+
+async fn save(args: BaselineSaveArgs) -> Result<i32, Box<dyn std::error::Error>> {
+    let path = path_or_default(args.path)?;
+    let mut baseline = Baseline::default();
+
+    for sample in 0..args.samples.max(1) {
+        if sample > 0 {
+            tokio::time::sleep(args.interval).await;
+        }
+
+        let sockets = get_all_socket_info(Protocol::TCP | Protocol::UDP)?;
+
+        if sample == 0 {
+            warn_unattributed(&sockets);
+        }
+
+        baseline.add_snapshot(&sockets, args.hosts);
+    }
+
+    baseline.save(&path)?;
+
+    println!(
+        "Saved {} listeners and {} outbound destinations to {}",
+        baseline.listeners.len(),
+        baseline.outbound.len(),
+        path.display()
+    );
+
+    Ok(0)
+}
+
+fn check(args: BaselineCheckArgs) -> Result<i32, Box<dyn std::error::Error>> {
+    let path = path_or_default(args.path)?;
+    let baseline = Baseline::load(&path)?;
+    let sockets = get_all_socket_info(Protocol::TCP | Protocol::UDP)?;
+    warn_unattributed(&sockets);
+
+    let drift = baseline.check(&sockets);
+
+    match args.format {
+        ReportFormat::Text => print!("{}", to_text(&drift, &path)),
+        ReportFormat::Json => println!("{}", serde_json::to_string_pretty(&drift)?),
+    }
+
+    match drift.is_empty() {
+        true => Ok(0),
+        false => Ok(EXIT_FAILED),
+    }
+}
+
+pub async fn run(command: BaselineCommand) -> Result<i32, Box<dyn std::error::Error>> {
+    match command {
+        BaselineCommand::Save(args) => save(args).await,
+        BaselineCommand::Check(args) => check(args),
+    }
+}
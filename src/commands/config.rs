@@ -0,0 +1,31 @@
+use std::path::Path;
+
+use crate::cli::ConfigArgs;
+use crate::config::{Config, DEFAULT_CONFIG};
+
+pub fn run(args: ConfigArgs, path: Option<&Path>) -> Result<(), Box<dyn std::error::Error>> {
+    if args.print_default {
+        print!("{}", DEFAULT_CONFIG);
+        return Ok(());
+    }
+
+    let path = match path {
+        Some(path) => path.to_path_buf(),
+        None => match Config::default_path() {
+            Some(path) if path.exists() => path,
+            Some(path) => {
+                println!("No config at {}, using the defaults", path.display());
+                return Ok(());
+            }
+            None => {
+                println!("No config path could be found, using the defaults");
+                return Ok(());
+            }
+        },
+    };
+
+    Config::load(Some(&path))?;
+    println!("{} is valid", path.display());
+
+    Ok(())
+}
@@ -0,0 +1,79 @@
+use crate::os::{get_all_socket_info, get_capabilities, get_data_sources, Protocol};
+
+/// Prints what nets can see and do with its current privileges.
+pub fn run() -> Result<(), Box<dyn std::error::Error>> {
+    let capabilities = get_capabilities();
+
+    if !capabilities.is_empty() {
+        let width = capabilities.iter().map(|c| c.name.len()).max().unwrap_or(0);
+
+        println!("Capabilities");
+
+        for capability in &capabilities {
+            println!(
+                "  {:<3}  {:<width$}  needed to {}",
+                match capability.granted {
+                    true => "yes",
+                    false => "no",
+                },
+                capability.name,
+                capability.needed_for,
+                width = width
+            );
+        }
+
+        println!();
+    }
+
+    let sources = get_data_sources();
+
+    if !sources.is_empty() {
+        let width = sources.iter().map(|s| s.name.len()).max().unwrap_or(0);
+
+        println!("Data sources");
+
+        for source in &sources {
+            let (status, details) = match &source.status {
+                Ok(details) => ("ok", details),
+                Err(e) => ("no", e),
+            };
+
+            println!(
+                "  {:<3}  {:<width$}  {}",
+                status,
+                source.name,
+                details,
+                width = width
+            );
+        }
+
+        println!();
+    }
+
+    let sockets = get_all_socket_info(Protocol::all())?;
+    let unattributed = sockets.iter().filter(|s| s.is_unattributed()).count();
+
+    println!("Sockets");
+    println!(
+        "  {} listed, {} without a known process",
+        sockets.len(),
+        unattributed
+    );
+
+    if unattributed > 0 {
+        let missing: Vec<&str> = capabilities
+            .iter()
+            .filter(|c| c.attributes_sockets && !c.granted)
+            .map(|c| c.name)
+            .collect();
+
+        if !missing.is_empty() {
+            println!(
+                "  Run nets as root or with {} to see their processes",
+                missing.join(" and ")
+            );
+        }
+    }
+
+    Ok(())
+}
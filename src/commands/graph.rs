@@ -0,0 +1,451 @@
+use std::collections::{BTreeMap, HashSet};
+use std::net::{IpAddr, SocketAddr};
+use std::ops::RangeInclusive;
+
+use netstat2::TcpState;
+use serde::Serialize;
+
+use crate::cli::{GraphArgs, GraphFormat};
+use crate::os::{get_all_socket_info, get_ephemeral_port_range, Protocol, SocketInfoWithProcName};
+
+#[derive(Clone, Copy, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum NodeKind {
+    Process,
+    Endpoint,
+}
+
+#[derive(Serialize)]
+pub struct Node {
+    pub id: String,
+    pub label: String,
+    pub kind: NodeKind,
+}
+
+#[derive(Serialize)]
+pub struct Edge {
+    pub from: String,
+    pub to: String,
+    pub connections: usize,
+}
+
+#[derive(Serialize)]
+pub struct Graph {
+    pub nodes: Vec<Node>,
+    pub edges: Vec<Edge>,
+}
+
+/// Accumulates one or more snapshots into a graph of processes and the endpoints they talk to.
+pub struct GraphBuilder {
+    nodes: BTreeMap<String, Node>,
+    edges: BTreeMap<(String, String), HashSet<(SocketAddr, SocketAddr)>>,
+    ephemeral_ports: RangeInclusive<u16>,
+}
+
+impl GraphBuilder {
+    pub fn new(ephemeral_ports: RangeInclusive<u16>) -> GraphBuilder {
+        GraphBuilder {
+            nodes: BTreeMap::new(),
+            edges: BTreeMap::new(),
+            ephemeral_ports,
+        }
+    }
+
+    pub fn add_snapshot(&mut self, sockets: &[SocketInfoWithProcName]) {
+        let listening: Vec<SocketAddr> = sockets
+            .iter()
+            .filter(|socket| socket.tcp_state() == Some(TcpState::Listen))
+            .filter_map(|socket| {
+                let inet = socket.inet()?;
+                Some(SocketAddr::new(inet.local_addr(), inet.local_port()))
+            })
+            .collect();
+
+        for socket in sockets {
+            let (local, remote) = match socket.tcp_endpoints() {
+                Some(endpoints) => endpoints,
+                None => continue,
+            };
+
+            let server_side = is_listened_on(&listening, local);
+            let process = self.owner_node(socket, local, server_side);
+
+            if server_side {
+                // The client end of a paired connection draws the edge itself
+                if socket.peer.is_some() {
+                    continue;
+                }
+
+                let client = self.endpoint_node(remote.ip(), None);
+                self.add_edge(client, process, (local, remote));
+            } else if let Some((pid, peer)) = socket
+                .peer
+                .as_ref()
+                .and_then(|peer| Some((peer.pid?, peer)))
+            {
+                let server = self.process_node(pid, &peer.process_name);
+                self.add_edge(process, server, (local, remote));
+            } else {
+                let port = match self.ephemeral_ports.contains(&remote.port()) {
+                    true => None,
+                    false => Some(remote.port()),
+                };
+
+                let server = self.endpoint_node(remote.ip(), port);
+                self.add_edge(process, server, (local, remote));
+            }
+        }
+    }
+
+    pub fn build(self) -> Graph {
+        Graph {
+            nodes: self.nodes.into_values().collect(),
+            edges: self
+                .edges
+                .into_iter()
+                .map(|((from, to), connections)| Edge {
+                    from,
+                    to,
+                    connections: connections.len(),
+                })
+                .collect(),
+        }
+    }
+
+    /// Processes sharing a name, like the workers of a server, are told apart by pid.
+    fn process_node(&mut self, pid: u32, name: &str) -> String {
+        let id = format!("process:{}/{}", pid, name);
+
+        self.nodes.entry(id.clone()).or_insert_with(|| Node {
+            id: id.clone(),
+            label: format!("{} ({})", name, pid),
+            kind: NodeKind::Process,
+        });
+
+        id
+    }
+
+    /// The process of a socket, or its own address when the process is unknown so that
+    /// unrelated sockets aren't merged.
+    fn owner_node(
+        &mut self,
+        socket: &SocketInfoWithProcName,
+        local: SocketAddr,
+        server_side: bool,
+    ) -> String {
+        match socket.pid() {
+            Some(pid) => self.process_node(pid, &socket.process_name),
+            None => self.endpoint_node(local.ip(), server_side.then_some(local.port())),
+        }
+    }
+
+    /// Adds a remote endpoint, where a `None` port stands for any (ephemeral) port.
+    fn endpoint_node(&mut self, addr: IpAddr, port: Option<u16>) -> String {
+        let port = match port {
+            Some(port) => port.to_string(),
+            None => String::from("*"),
+        };
+
+        let id = match addr {
+            IpAddr::V4(addr) => format!("{}:{}", addr, port),
+            IpAddr::V6(addr) => format!("[{}]:{}", addr, port),
+        };
+
+        self.nodes.entry(id.clone()).or_insert_with(|| Node {
+            id: id.clone(),
+            label: id.clone(),
+            kind: NodeKind::Endpoint,
+        });
+
+        id
+    }
+
+    fn add_edge(&mut self, from: String, to: String, connection: (SocketAddr, SocketAddr)) {
+        self.edges.entry((from, to)).or_default().insert(connection);
+    }
+}
+
+impl Graph {
+    pub fn to_dot(&self) -> String {
+        let mut out = String::from("digraph nets {\n    rankdir=LR;\n");
+
+        for node in &self.nodes {
+            let shape = match node.kind {
+                NodeKind::Process => "box",
+                NodeKind::Endpoint => "ellipse",
+            };
+
+            out.push_str(&format!(
+                "    \"{}\" [label=\"{}\", shape={}];\n",
+                escape_dot(&node.id),
+                escape_dot(&node.label),
+                shape
+            ));
+        }
+
+        for edge in &self.edges {
+            out.push_str(&format!(
+                "    \"{}\" -> \"{}\" [label=\"{}\"];\n",
+                escape_dot(&edge.from),
+                escape_dot(&edge.to),
+                edge.connections
+            ));
+        }
+
+        out.push_str("}\n");
+        out
+    }
+
+    pub fn to_mermaid(&self) -> String {
+        let mut out = String::from("graph LR\n");
+
+        // Mermaid ids can't contain most punctuation, so refer to nodes by their position
+        let ids: BTreeMap<&str, String> = self
+            .nodes
+            .iter()
+            .enumerate()
+            .map(|(i, node)| (node.id.as_str(), format!("n{}", i)))
+            .collect();
+
+        for node in &self.nodes {
+            let label = node.label.replace('"', "#quot;");
+
+            match node.kind {
+                NodeKind::Process => {
+                    out.push_str(&format!("    {}[\"{}\"]\n", ids[node.id.as_str()], label))
+                }
+                NodeKind::Endpoint => {
+                    out.push_str(&format!("    {}([\"{}\"])\n", ids[node.id.as_str()], label))
+                }
+            }
+        }
+
+        for edge in &self.edges {
+            out.push_str(&format!(
+                "    {} -->|{}| {}\n",
+                ids[edge.from.as_str()],
+                edge.connections,
+                ids[edge.to.as_str()]
+            ));
+        }
+
+        out
+    }
+}
+
+/// Whether a connection was accepted by one of the listeners, on its address or a wildcard.
+fn is_listened_on(listening: &[SocketAddr], local: SocketAddr) -> bool {
+    listening.iter().any(|listener| {
+        listener.port() == local.port()
+            && (listener.ip().is_unspecified()
+                || listener.ip().to_canonical() == local.ip().to_canonical())
+    })
+}
+
+fn escape_dot(text: &str) -> String {
+    text.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+pub async fn run(args: GraphArgs) -> Result<(), Box<dyn std::error::Error>> {
+    let mut builder = GraphBuilder::new(get_ephemeral_port_range());
+
+    for sample in 0..args.samples.max(1) {
+        if sample > 0 {
+            tokio::time::sleep(args.interval).await;
+        }
+
+        builder.add_snapshot(&get_all_socket_info(Protocol::TCP)?);
+    }
+
+    let graph = builder.build();
+
+    match args.format {
+        GraphFormat::Dot => print!("{}", graph.to_dot()),
+        GraphFormat::Mermaid => print!("{}", graph.to_mermaid()),
+        GraphFormat::Json => println!("{}", serde_json::to_string_pretty(&graph)?),
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::os::fixtures::{owned_by, tcp, tcp_listener};
+    use crate::os::PeerProcess;
+
+    fn graph(sockets: &[SocketInfoWithProcName]) -> Graph {
+        let mut builder = GraphBuilder::new(32768..=60999);
+        builder.add_snapshot(sockets);
+        builder.build()
+    }
+
+    fn edges(graph: &Graph) -> Vec<(&str, &str, usize)> {
+        graph
+            .edges
+            .iter()
+            .map(|edge| (edge.from.as_str(), edge.to.as_str(), edge.connections))
+            .collect()
+    }
+
+    #[test]
+    fn processes_with_the_same_name_are_separate_nodes() {
+        let graph = graph(&[
+            owned_by(
+                tcp(
+                    "10.0.0.1:40000",
+                    "10.0.0.5:443",
+                    TcpState::Established,
+                    "worker",
+                ),
+                Some(100),
+            ),
+            owned_by(
+                tcp(
+                    "10.0.0.1:40001",
+                    "10.0.0.5:443",
+                    TcpState::Established,
+                    "worker",
+                ),
+                Some(101),
+            ),
+        ]);
+
+        assert_eq!(
+            edges(&graph),
+            [
+                ("process:100/worker", "10.0.0.5:443", 1),
+                ("process:101/worker", "10.0.0.5:443", 1),
+            ]
+        );
+        assert_eq!(graph.nodes[1].label, "worker (100)");
+    }
+
+    #[test]
+    fn unknown_processes_are_not_merged() {
+        let graph = graph(&[
+            owned_by(tcp_listener("0.0.0.0:22", "sshd"), None),
+            owned_by(
+                tcp(
+                    "10.0.0.1:22",
+                    "10.0.0.7:50000",
+                    TcpState::Established,
+                    "sshd",
+                ),
+                None,
+            ),
+            owned_by(
+                tcp(
+                    "10.0.0.1:40000",
+                    "10.0.0.9:443",
+                    TcpState::Established,
+                    "curl",
+                ),
+                None,
+            ),
+        ]);
+
+        assert_eq!(
+            edges(&graph),
+            [
+                ("10.0.0.1:*", "10.0.0.9:443", 1),
+                ("10.0.0.7:*", "10.0.0.1:22", 1),
+            ]
+        );
+        assert!(graph.nodes.iter().all(|node| node.id != "process:-"));
+    }
+
+    #[test]
+    fn accepted_connections_need_a_listener_on_their_address() {
+        let graph = graph(&[
+            tcp_listener("127.0.0.1:8080", "app"),
+            tcp(
+                "127.0.0.1:8080",
+                "127.0.0.1:50000",
+                TcpState::Established,
+                "app",
+            ),
+            // Same port, but an outbound connection from another address
+            tcp(
+                "10.0.0.1:8080",
+                "10.0.0.9:5432",
+                TcpState::Established,
+                "app",
+            ),
+        ]);
+
+        assert_eq!(
+            edges(&graph),
+            [
+                ("127.0.0.1:*", "process:100/app", 1),
+                ("process:100/app", "10.0.0.9:5432", 1),
+            ]
+        );
+    }
+
+    #[test]
+    fn paired_connections_link_both_processes() {
+        let mut client = tcp(
+            "127.0.0.1:50000",
+            "127.0.0.1:5432",
+            TcpState::Established,
+            "app",
+        );
+        client.set_peer(PeerProcess {
+            pid: Some(200),
+            process_name: String::from("postgres"),
+        });
+
+        let mut server = owned_by(
+            tcp(
+                "127.0.0.1:5432",
+                "127.0.0.1:50000",
+                TcpState::Established,
+                "postgres",
+            ),
+            Some(200),
+        );
+        server.set_peer(PeerProcess {
+            pid: Some(100),
+            process_name: String::from("app"),
+        });
+
+        let listener = owned_by(tcp_listener("127.0.0.1:5432", "postgres"), Some(200));
+
+        assert_eq!(
+            edges(&graph(&[listener, client, server])),
+            [("process:100/app", "process:200/postgres", 1)]
+        );
+    }
+
+    #[test]
+    fn formats_dot_and_mermaid() {
+        let graph = graph(&[tcp(
+            "10.0.0.1:40000",
+            "10.0.0.5:443",
+            TcpState::Established,
+            "curl",
+        )]);
+
+        assert_eq!(
+            graph.to_dot(),
+            concat!(
+                "digraph nets {\n",
+                "    rankdir=LR;\n",
+                "    \"10.0.0.5:443\" [label=\"10.0.0.5:443\", shape=ellipse];\n",
+                "    \"process:100/curl\" [label=\"curl (100)\", shape=box];\n",
+                "    \"process:100/curl\" -> \"10.0.0.5:443\" [label=\"1\"];\n",
+                "}\n",
+            )
+        );
+        assert_eq!(
+            graph.to_mermaid(),
+            "graph LR\n    n0([\"10.0.0.5:443\"])\n    n1[\"curl (100)\"]\n    n1 -->|1| n0\n"
+        );
+    }
+
+    #[test]
+    fn escapes_quotes_in_dot() {
+        assert_eq!(escape_dot(r#"a "b" \c"#), r#"a \"b\" \\c"#);
+    }
+}
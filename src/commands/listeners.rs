@@ -0,0 +1,270 @@
+use std::net::IpAddr;
+use std::path::Path;
+
+use serde::Serialize;
+
+use crate::cli::{ListenersArgs, ReportFormat};
+use crate::config::Config;
+use crate::listeners::Allowlist;
+use crate::os::{get_all_socket_info, Protocol, SocketInfoWithProcName};
+use crate::resolve::{Services, UserNames};
+
+/// A row of the listeners report.
+#[derive(Serialize)]
+pub struct Listener {
+    pub protocol: &'static str,
+    pub address: IpAddr,
+    pub port: u16,
+    /// all, address or loopback
+    pub scope: String,
+    /// v4, v6 or dual
+    pub family: String,
+    pub pid: Option<u32>,
+    pub process: Option<String>,
+    pub user: Option<String>,
+    pub service: Option<String>,
+    pub unit: Option<String>,
+    pub container: Option<String>,
+    /// Bound to all interfaces without being allowed to.
+    pub exposed: bool,
+}
+
+impl Listener {
+    fn new(
+        socket: &SocketInfoWithProcName,
+        services: &Services,
+        users: &UserNames,
+    ) -> Option<Listener> {
+        let inet = socket.inet()?;
+        let protocol = socket.service_protocol()?;
+        let cgroup = socket.cgroup.clone().unwrap_or_default();
+
+        Some(Listener {
+            protocol,
+            address: inet.local_addr(),
+            port: inet.local_port(),
+            scope: socket.bind_scope()?.to_string(),
+            family: socket.address_families()?.to_string(),
+            pid: socket.pid(),
+            process: socket.pid().map(|_| socket.process_name.clone()),
+            user: socket.uid().map(|uid| {
+                users
+                    .name(uid)
+                    .map_or_else(|| uid.to_string(), str::to_string)
+            }),
+            service: services
+                .name(inet.local_port(), protocol)
+                .map(str::to_string),
+            unit: cgroup.unit,
+            container: cgroup.container,
+            exposed: socket.exposed,
+        })
+    }
+}
+
+/// Every TCP listener and UDP socket, sorted by protocol and port.
+pub fn get_listeners(allowlist: &Allowlist) -> Result<Vec<Listener>, Box<dyn std::error::Error>> {
+    Ok(listeners_of(
+        get_all_socket_info(Protocol::TCP | Protocol::UDP)?,
+        allowlist,
+        &Services::load(),
+        &UserNames::load(),
+    ))
+}
+
+fn listeners_of(
+    sockets: Vec<SocketInfoWithProcName>,
+    allowlist: &Allowlist,
+    services: &Services,
+    users: &UserNames,
+) -> Vec<Listener> {
+    let mut listeners: Vec<Listener> = sockets
+        .into_iter()
+        .filter(|socket| socket.is_listener())
+        .filter_map(|mut socket| {
+            socket.exposed = allowlist.is_exposed(&socket);
+            Listener::new(&socket, services, users)
+        })
+        .collect();
+
+    listeners.sort_by(|a, b| (a.protocol, a.port, a.address).cmp(&(b.protocol, b.port, b.address)));
+
+    listeners
+}
+
+fn to_text(listeners: &[Listener]) -> String {
+    let header = [
+        "PROTO", "ADDRESS", "PORT", "SCOPE", "FAMILY", "PROCESS", "USER", "SERVICE", "EXPOSED",
+    ];
+
+    let rows: Vec<[String; 9]> = listeners
+        .iter()
+        .map(|listener| {
+            [
+                listener.protocol.to_string(),
+                listener.address.to_string(),
+                listener.port.to_string(),
+                listener.scope.clone(),
+                listener.family.clone(),
+                match (&listener.pid, &listener.process) {
+                    (Some(pid), Some(process)) => format!("{}/{}", pid, process),
+                    _ => String::from("-"),
+                },
+                listener.user.clone().unwrap_or_else(|| String::from("-")),
+                listener
+                    .service
+                    .clone()
+                    .unwrap_or_else(|| String::from("-")),
+                match listener.exposed {
+                    true => String::from("yes"),
+                    false => String::new(),
+                },
+            ]
+        })
+        .collect();
+
+    let widths: Vec<usize> = (0..header.len())
+        .map(|i| {
+            rows.iter()
+                .map(|row| row[i].chars().count())
+                .chain([header[i].len()])
+                .max()
+                .unwrap_or(0)
+        })
+        .collect();
+
+    let line = |cells: Vec<&str>| {
+        cells
+            .iter()
+            .zip(&widths)
+            .map(|(cell, width)| format!("{:<width$}", cell, width = width))
+            .collect::<Vec<_>>()
+            .join("  ")
+            .trim_end()
+            .to_string()
+    };
+
+    let mut out = line(header.to_vec());
+    out.push('\n');
+
+    for row in &rows {
+        out.push_str(&line(row.iter().map(String::as_str).collect()));
+        out.push('\n');
+    }
+
+    let wildcard = listeners.iter().filter(|l| l.scope == "all").count();
+    let exposed = listeners.iter().filter(|l| l.exposed).count();
+
+    out.push_str(&format!(
+        "\n{} listeners, {} on all interfaces, {} of them not in allowed_listeners\n",
+        listeners.len(),
+        wildcard,
+        exposed
+    ));
+
+    out
+}
+
+pub fn run(
+    args: ListenersArgs,
+    config_path: Option<&Path>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut allowlist = Config::load(config_path)?.allowed_listeners;
+    allowlist.extend(Allowlist::parse(&args.allow).map_err(|e| format!("--allow: {}", e))?);
+
+    let listeners = get_listeners(&allowlist)?;
+
+    match args.format {
+        ReportFormat::Text => print!("{}", to_text(&listeners)),
+        ReportFormat::Json => println!("{}", serde_json::to_string_pretty(&listeners)?),
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use netstat2::TcpState;
+
+    use super::*;
+    use crate::os::fixtures::{dual_stack, owned_by, tcp, tcp_listener, udp};
+
+    fn listeners(sockets: Vec<SocketInfoWithProcName>, allowed: &[&str]) -> Vec<Listener> {
+        listeners_of(
+            sockets,
+            &Allowlist::parse(allowed).unwrap(),
+            &Services::parse("ssh\t\t22/tcp\ndomain\t\t53/udp\n"),
+            &UserNames::parse("root:x:0:0:root:/root:/bin/bash\n"),
+        )
+    }
+
+    #[test]
+    fn lists_listeners_sorted_by_protocol_and_port() {
+        let rows = listeners(
+            vec![
+                tcp_listener("0.0.0.0:8080", "app"),
+                udp("127.0.0.53:53", "systemd-resolve"),
+                tcp(
+                    "10.0.0.1:22",
+                    "10.0.0.2:50000",
+                    TcpState::Established,
+                    "sshd",
+                ),
+                tcp_listener("0.0.0.0:22", "sshd"),
+            ],
+            &[],
+        );
+
+        assert_eq!(
+            rows.iter()
+                .map(|row| (row.protocol, row.port))
+                .collect::<Vec<_>>(),
+            [("tcp", 22), ("tcp", 8080), ("udp", 53)]
+        );
+
+        assert_eq!(rows[0].service.as_deref(), Some("ssh"));
+        assert_eq!(rows[0].process.as_deref(), Some("sshd"));
+        assert_eq!(rows[0].pid, Some(100));
+        assert_eq!(rows[0].user.as_deref(), Some("root"));
+        assert_eq!(rows[1].service, None);
+        assert_eq!(rows[2].service.as_deref(), Some("domain"));
+        assert_eq!(rows[2].scope, "loopback");
+    }
+
+    #[test]
+    fn flags_listeners_on_all_interfaces_unless_allowed() {
+        let rows = listeners(
+            vec![
+                tcp_listener("0.0.0.0:22", "sshd"),
+                tcp_listener("0.0.0.0:5432", "postgres"),
+                tcp_listener("127.0.0.1:6379", "redis-server"),
+                udp("0.0.0.0:53", "dnsmasq"),
+            ],
+            &["22", "tcp:53"],
+        );
+
+        assert_eq!(
+            rows.iter()
+                .map(|row| (row.port, row.exposed))
+                .collect::<Vec<_>>(),
+            [(22, false), (5432, true), (6379, false), (53, true)]
+        );
+    }
+
+    #[test]
+    fn reports_families_and_unknown_processes() {
+        let rows = listeners(
+            vec![
+                dual_stack(tcp_listener("[::]:443", "nginx"), true),
+                owned_by(tcp_listener("[::]:80", "nginx"), None),
+            ],
+            &[],
+        );
+
+        assert_eq!(rows[0].port, 80);
+        assert_eq!(rows[0].process, None);
+        assert_eq!(rows[0].pid, None);
+        assert_eq!(rows[1].family, "dual");
+        assert_eq!(rows[1].scope, "all");
+    }
+}
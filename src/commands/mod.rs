@@ -0,0 +1,32 @@
+pub mod assert;
+pub mod baseline;
+pub mod config;
+pub mod doctor;
+pub mod graph;
+pub mod listeners;
+pub mod wait;
+
+use std::path::Path;
+
+use crate::cli::Command;
+
+/// Exit status of a check that found a problem, like a new listener, or of a wait that
+/// timed out.
+pub const EXIT_FAILED: i32 = 1;
+
+/// Runs a subcommand and returns its exit status. Errors exit with 2, so scripts can tell
+/// them from failed checks.
+pub async fn run(
+    command: Command,
+    config_path: Option<&Path>,
+) -> Result<i32, Box<dyn std::error::Error>> {
+    match command {
+        Command::Graph(args) => graph::run(args).await.map(|_| 0),
+        Command::Config(args) => config::run(args, config_path).map(|_| 0),
+        Command::Doctor => doctor::run().map(|_| 0),
+        Command::Listeners(args) => listeners::run(args, config_path).map(|_| 0),
+        Command::Baseline(command) => baseline::run(command).await,
+        Command::Assert(args) => assert::run(args),
+        Command::Wait(args) => wait::run(args).await,
+    }
+}
@@ -0,0 +1,365 @@
+use std::net::{IpAddr, SocketAddr, ToSocketAddrs};
+use std::time::Instant;
+
+use netstat2::TcpState;
+
+use crate::cli::WaitArgs;
+use crate::commands::EXIT_FAILED;
+use crate::os::{get_all_socket_info, Protocol, SocketInfoWithProcName};
+use crate::signal::process_exists;
+
+/// What `nets wait` looks for in each snapshot.
+enum Target {
+    Listener {
+        protocol: Option<Protocol>,
+        address: Option<IpAddr>,
+        port: u16,
+    },
+    /// An established connection to one of the hosts, any host when there are none.
+    Connection { hosts: Vec<IpAddr>, port: u16 },
+}
+
+impl Target {
+    /// Parses `[tcp:|udp:][ADDRESS:]PORT`, IPv6 addresses go in brackets.
+    fn listener(text: &str) -> Result<Target, String> {
+        let (protocol, rest) = match text.split_once(':') {
+            Some(("tcp", rest)) => (Some(Protocol::TCP), rest),
+            Some(("udp", rest)) => (Some(Protocol::UDP), rest),
+            _ => (None, text),
+        };
+
+        let (address, port) = match rest.parse::<u16>() {
+            Ok(port) => (None, port),
+            Err(_) => match rest.parse::<SocketAddr>() {
+                Ok(addr) => (Some(addr.ip()), addr.port()),
+                Err(_) => {
+                    return Err(format!(
+                        "--listen: invalid listener '{}', expected [tcp:|udp:][ADDRESS:]PORT",
+                        text
+                    ))
+                }
+            },
+        };
+
+        Ok(Target::Listener {
+            protocol,
+            address,
+            port,
+        })
+    }
+
+    /// Parses `HOST:PORT`, where the host is looked up once, or a port.
+    fn connection(text: &str) -> Result<Target, String> {
+        if let Ok(port) = text.parse::<u16>() {
+            return Ok(Target::Connection {
+                hosts: Vec::new(),
+                port,
+            });
+        }
+
+        let addrs: Vec<SocketAddr> = text
+            .to_socket_addrs()
+            .map_err(|e| format!("--connect: can't resolve '{}': {}", text, e))?
+            .collect();
+
+        match addrs.first() {
+            Some(addr) => Ok(Target::Connection {
+                hosts: addrs.iter().map(|addr| addr.ip()).collect(),
+                port: addr.port(),
+            }),
+            None => Err(format!("--connect: '{}' has no addresses", text)),
+        }
+    }
+
+    fn matches(&self, socket: &SocketInfoWithProcName) -> bool {
+        match self {
+            Target::Listener {
+                protocol,
+                address,
+                port,
+            } => {
+                socket.is_listener()
+                    && socket.local_port() == Some(*port)
+                    && protocol.is_none_or(|protocol| protocol == socket.protocol_flags)
+                    // A wildcard listener is reachable on the address too
+                    && address.is_none_or(|address| socket.accepts_on(address))
+            }
+            Target::Connection { hosts, port } => match socket.tcp_endpoints() {
+                Some((_, remote)) if socket.tcp_state() == Some(TcpState::Established) => {
+                    // IPv6 sockets see IPv4 peers as mapped addresses
+                    remote.port() == *port
+                        && (hosts.is_empty() || hosts.contains(&remote.ip().to_canonical()))
+                }
+                _ => false,
+            },
+        }
+    }
+
+    fn describe(&self) -> String {
+        match self {
+            Target::Listener {
+                protocol,
+                address,
+                port,
+            } => {
+                let protocol = match *protocol {
+                    Some(Protocol::TCP) => "tcp ",
+                    Some(Protocol::UDP) => "udp ",
+                    _ => "",
+                };
+
+                match address {
+                    Some(address) => format!(
+                        "a listener on {}{}",
+                        protocol,
+                        SocketAddr::new(*address, *port)
+                    ),
+                    None => format!("a listener on {}port {}", protocol, port),
+                }
+            }
+            Target::Connection { hosts, port } => match hosts.first() {
+                Some(host) => format!("a connection to {}", SocketAddr::new(*host, *port)),
+                None => format!("a connection to port {}", port),
+            },
+        }
+    }
+}
+
+/// The first socket of the target, of process `pid` if given. Errors when sockets of the
+/// target show no process, as they could be `pid`'s.
+fn find(
+    sockets: Vec<SocketInfoWithProcName>,
+    target: &Target,
+    pid: Option<u32>,
+) -> Result<Option<SocketInfoWithProcName>, String> {
+    let matching: Vec<SocketInfoWithProcName> = sockets
+        .into_iter()
+        .filter(|socket| target.matches(socket))
+        .collect();
+
+    let pid = match pid {
+        Some(pid) => pid,
+        None => return Ok(matching.into_iter().next()),
+    };
+
+    let unattributed = matching
+        .iter()
+        .filter(|socket| socket.is_unattributed())
+        .count();
+
+    match matching
+        .into_iter()
+        .find(|socket| socket.associated_pids().contains(&pid))
+    {
+        Some(socket) => Ok(Some(socket)),
+        None if unattributed == 0 => Ok(None),
+        None => Err(format!(
+            "{} sockets show no process for {}, they could be pid {}'s. Run as root or see nets doctor.",
+            unattributed,
+            target.describe(),
+            pid
+        )),
+    }
+}
+
+pub async fn run(args: WaitArgs) -> Result<i32, Box<dyn std::error::Error>> {
+    let target = match (&args.listen, &args.connect) {
+        (Some(listener), _) => Target::listener(listener)?,
+        (None, Some(endpoint)) => Target::connection(endpoint)?,
+        (None, None) => return Err("give --listen or --connect".into()),
+    };
+
+    let started = Instant::now();
+
+    loop {
+        let found = find(
+            get_all_socket_info(Protocol::TCP | Protocol::UDP)?,
+            &target,
+            args.pid,
+        )?;
+
+        match (found, args.closed) {
+            (Some(socket), false) => {
+                let process = match socket.pid() {
+                    Some(pid) => format!("{} (pid {})", socket.process_name, pid),
+                    None => String::from("an unknown process"),
+                };
+
+                println!("Found {}: {}", target.describe(), process);
+                return Ok(0);
+            }
+            (None, true) => {
+                println!("No {} anymore", target.describe().trim_start_matches("a "));
+                return Ok(0);
+            }
+            _ => {}
+        }
+
+        // A process that is gone won't start listening, but its sockets are closed
+        if let Some(pid) = args.pid {
+            if !args.closed && !process_exists(pid) {
+                return Err(format!("process {} exited", pid).into());
+            }
+        }
+
+        if let Some(timeout) = args.timeout {
+            if started.elapsed() >= timeout {
+                eprintln!(
+                    "nets: timed out after {:?} waiting for {}{}",
+                    timeout,
+                    target.describe(),
+                    match args.closed {
+                        true => " to close",
+                        false => "",
+                    }
+                );
+
+                return Ok(EXIT_FAILED);
+            }
+        }
+
+        tokio::time::sleep(args.interval).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::os::fixtures::{dual_stack, owned_by, tcp, tcp_listener, udp};
+
+    #[test]
+    fn parses_listeners() {
+        assert!(matches!(
+            Target::listener("8080"),
+            Ok(Target::Listener {
+                protocol: None,
+                address: None,
+                port: 8080
+            })
+        ));
+        assert!(matches!(
+            Target::listener("udp:[::1]:53"),
+            Ok(Target::Listener {
+                protocol: Some(Protocol::UDP),
+                address: Some(IpAddr::V6(_)),
+                port: 53
+            })
+        ));
+        assert!(Target::listener("tcp:").is_err());
+        assert!(Target::listener("localhost:80").is_err());
+    }
+
+    #[test]
+    fn listener_on_an_address_is_found_on_wildcards() {
+        let target = Target::listener("127.0.0.1:8080").unwrap();
+
+        assert!(target.matches(&tcp_listener("127.0.0.1:8080", "app")));
+        assert!(target.matches(&tcp_listener("0.0.0.0:8080", "app")));
+        assert!(target.matches(&dual_stack(tcp_listener("[::]:8080", "app"), true)));
+        assert!(target.matches(&tcp_listener("[::ffff:127.0.0.1]:8080", "app")));
+
+        assert!(!target.matches(&dual_stack(tcp_listener("[::]:8080", "app"), false)));
+        assert!(!target.matches(&tcp_listener("10.0.0.1:8080", "app")));
+        assert!(!target.matches(&tcp_listener("127.0.0.1:8081", "app")));
+    }
+
+    #[test]
+    fn listener_is_narrowed_by_protocol() {
+        let target = Target::listener("tcp:53").unwrap();
+
+        assert!(target.matches(&tcp_listener("127.0.0.1:53", "dns")));
+        assert!(!target.matches(&udp("127.0.0.1:53", "dns")));
+        assert!(Target::listener("53")
+            .unwrap()
+            .matches(&udp("127.0.0.1:53", "dns")));
+    }
+
+    #[test]
+    fn listener_is_not_a_connection() {
+        let target = Target::listener("8080").unwrap();
+
+        assert!(!target.matches(&tcp(
+            "127.0.0.1:8080",
+            "127.0.0.1:40000",
+            TcpState::Established,
+            "app"
+        )));
+    }
+
+    #[test]
+    fn connection_matches_established_ones_to_the_hosts() {
+        let target = Target::connection("10.0.0.5:5432").unwrap();
+
+        assert!(target.matches(&tcp(
+            "10.0.0.1:40000",
+            "10.0.0.5:5432",
+            TcpState::Established,
+            "app"
+        )));
+        assert!(target.matches(&tcp(
+            "[::ffff:10.0.0.1]:40000",
+            "[::ffff:10.0.0.5]:5432",
+            TcpState::Established,
+            "app"
+        )));
+        assert!(!target.matches(&tcp(
+            "10.0.0.1:40000",
+            "10.0.0.5:5432",
+            TcpState::SynSent,
+            "app"
+        )));
+        assert!(!target.matches(&tcp(
+            "10.0.0.1:40000",
+            "10.0.0.6:5432",
+            TcpState::Established,
+            "app"
+        )));
+
+        let any_host = Target::connection("5432").unwrap();
+        assert!(any_host.matches(&tcp(
+            "10.0.0.1:40000",
+            "10.0.0.6:5432",
+            TcpState::Established,
+            "app"
+        )));
+    }
+
+    #[test]
+    fn finds_sockets_of_the_pid() {
+        let target = Target::listener("8080").unwrap();
+        let sockets = || {
+            vec![
+                owned_by(tcp_listener("0.0.0.0:8080", "app"), Some(100)),
+                owned_by(tcp_listener("0.0.0.0:9090", "other"), Some(200)),
+            ]
+        };
+
+        assert!(find(sockets(), &target, None).unwrap().is_some());
+        assert!(find(sockets(), &target, Some(100)).unwrap().is_some());
+        assert!(find(sockets(), &target, Some(200)).unwrap().is_none());
+        assert!(find(Vec::new(), &target, Some(100)).unwrap().is_none());
+    }
+
+    #[test]
+    fn pid_cannot_be_told_apart_from_unknown_processes() {
+        let target = Target::listener("8080").unwrap();
+        let unknown = || vec![owned_by(tcp_listener("0.0.0.0:8080", "-"), None)];
+
+        assert_eq!(
+            find(unknown(), &target, Some(100)).err(),
+            Some(String::from(
+                "1 sockets show no process for a listener on port 8080, they could be pid 100's. Run as root or see nets doctor."
+            ))
+        );
+        // Without --pid any process will do
+        assert!(find(unknown(), &target, None).unwrap().is_some());
+        // Unknown sockets elsewhere don't matter
+        assert!(find(
+            vec![owned_by(tcp_listener("0.0.0.0:9090", "-"), None)],
+            &target,
+            Some(100)
+        )
+        .unwrap()
+        .is_none());
+    }
+}
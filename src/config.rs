@@ -0,0 +1,354 @@
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use serde::Deserialize;
+
+use crate::app::SortOrder;
+use crate::columns::Column;
+use crate::keymap::{Action, Key, Keymap};
+use crate::listeners::Allowlist;
+use crate::os::Protocol;
+use crate::theme::{Theme, ThemeFile};
+use crate::utils::parse_duration;
+
+/// The configuration used when there is no config file, also the base every
+/// config file is applied on top of.
+pub const DEFAULT_CONFIG: &str = r##"# nets configuration
+# Every setting is optional, missing ones keep the value shown here.
+
+# Time between two refreshes of the connection list (e.g. 500ms, 2s)
+refresh_interval = "500ms"
+
+# Tab selected on start: all, tcp, udp, unix, raw, icmp, sctp, packet or listeners
+default_tab = "all"
+
+# Columns shown on start, in order, named like in filters
+# (proto, laddr, lport, raddr, rport, state, pid, process, peer, rtt, rttvar, cwnd,
+# ssthresh, retrans, lost, unacked, mss, cc, rx, tx, rate, recvq, sendq, uid, user,
+# inode, age, service, host, netns, unit, container, pod, scope, family, exposed, drift).
+# They can also be changed from the column list (C).
+columns = ["proto", "laddr", "lport", "raddr", "rport", "state", "pid", "process", "peer"]
+
+# Table layout: auto, compact or full. The compact layout, used by auto on terminals
+# narrower than 100 columns, drops the table's side borders and puts panes below it.
+layout = "auto"
+
+# Show host names of remote addresses, looked up in the background
+resolve_dns = false
+
+# Show names from /etc/services in place of well-known port numbers
+service_names = false
+
+# Ports that may listen on all interfaces, like 22, tcp:443 or udp:53. Other listeners
+# on a wildcard address are flagged as exposed in the Listeners tab and `nets listeners`.
+allowed_listeners = []
+
+# Colour theme: dark, light, high-contrast, monochrome or one from [themes]. Colours are
+# turned off when NO_COLOR is set or TERM is dumb.
+theme = "dark"
+
+# Key preset: default, vim or emacs
+keymap = "default"
+
+[sort]
+column = "lport"
+descending = true
+
+# Fixed share of the table width (0 to 1) for a column, the other columns are sized
+# to fit their content
+[column_widths]
+# laddr = 0.2
+
+# Keys bound to actions, replacing the preset's keys for that action. Keys use vim
+# notation, e.g. quit = ["q", "<C-c>"] or first = "gg". The help (?) lists the actions.
+[keybindings]
+
+# Filters that can be used in the filter box as @name
+[filters]
+# listening = "state:listen"
+
+# Themes based on a built-in one, with colours given as names (red, lightblue), 0-255
+# or #rrggbb. Rows of TCP sockets take the colour of their state.
+[themes]
+# [themes.mine]
+# base = "dark"
+# value = "#ff8800"  # also title, highlight, warning, error, muted and selected
+# states = { established = "#5faf5f", time_wait = "darkgray", close_wait = "lightred" }
+"##;
+
+/// The config file as written, where everything is optional.
+#[derive(Deserialize, Default)]
+#[serde(deny_unknown_fields)]
+struct ConfigFile {
+    refresh_interval: Option<String>,
+    default_tab: Option<String>,
+    columns: Option<Vec<String>>,
+    layout: Option<String>,
+    resolve_dns: Option<bool>,
+    service_names: Option<bool>,
+    allowed_listeners: Option<Vec<String>>,
+    theme: Option<String>,
+    keymap: Option<String>,
+    sort: Option<SortFile>,
+    column_widths: Option<BTreeMap<String, f32>>,
+    keybindings: Option<BTreeMap<String, KeyList>>,
+    filters: Option<BTreeMap<String, String>>,
+    themes: Option<BTreeMap<String, ThemeFile>>,
+}
+
+/// One key or a list of keys for an action.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum KeyList {
+    One(String),
+    Many(Vec<String>),
+}
+
+#[derive(Deserialize, Default)]
+#[serde(deny_unknown_fields)]
+struct SortFile {
+    column: Option<String>,
+    descending: Option<bool>,
+}
+
+#[derive(Clone, Copy)]
+pub enum LayoutMode {
+    /// Compact on narrow terminals, full otherwise.
+    Auto,
+    Compact,
+    Full,
+}
+
+pub struct Config {
+    pub refresh_interval: Duration,
+    /// Title of the tab, in lowercase.
+    pub default_tab: String,
+    pub columns: Vec<Column>,
+    pub layout: LayoutMode,
+    pub resolve_dns: bool,
+    pub service_names: bool,
+    pub allowed_listeners: Allowlist,
+    pub theme: Theme,
+    pub sort: SortOrder,
+    pub column_widths: HashMap<Column, f32>,
+    pub keymap: Keymap,
+    pub filters: BTreeMap<String, String>,
+}
+
+impl Default for Config {
+    fn default() -> Config {
+        Config::empty()
+            .apply(DEFAULT_CONFIG)
+            .expect("the default config is valid")
+    }
+}
+
+impl Config {
+    /// `$XDG_CONFIG_HOME/nets/config.toml`, or `~/.config/nets/config.toml`.
+    pub fn default_path() -> Option<PathBuf> {
+        let base = match std::env::var_os("XDG_CONFIG_HOME") {
+            Some(dir) if !dir.is_empty() => PathBuf::from(dir),
+            _ => PathBuf::from(std::env::var_os("HOME")?).join(".config"),
+        };
+
+        Some(base.join("nets").join("config.toml"))
+    }
+
+    /// Loads `path`, or the default path if it exists. A missing default config isn't an error.
+    pub fn load(path: Option<&Path>) -> Result<Config, String> {
+        let path = match path {
+            Some(path) => Some(path.to_path_buf()),
+            None => Config::default_path().filter(|path| path.exists()),
+        };
+
+        let mut config = match path {
+            Some(path) => {
+                let contents = std::fs::read_to_string(&path)
+                    .map_err(|e| format!("can't read config {}: {}", path.display(), e))?;
+
+                Config::default()
+                    .apply(&contents)
+                    .map_err(|e| format!("invalid config {}: {}", path.display(), e))?
+            }
+            None => Config::default(),
+        };
+
+        if Theme::is_color_disabled() {
+            config.theme = Theme::monochrome();
+        }
+
+        Ok(config)
+    }
+
+    /// Overrides the settings present in `contents`.
+    fn apply(mut self, contents: &str) -> Result<Config, String> {
+        let file: ConfigFile = toml::from_str(contents).map_err(|e| e.to_string())?;
+
+        if let Some(interval) = file.refresh_interval {
+            self.refresh_interval =
+                parse_duration(&interval).map_err(|e| format!("refresh_interval: {}", e))?;
+
+            if self.refresh_interval.is_zero() {
+                return Err(String::from("refresh_interval: must be more than 0"));
+            }
+        }
+
+        if let Some(tab) = file.default_tab {
+            let name = tab.to_lowercase();
+
+            if Protocol::from_name(&name).is_none() && name != "listeners" {
+                return Err(format!(
+                    "default_tab: unknown tab '{}', expected all, tcp, udp, unix, raw, icmp, sctp, packet or listeners",
+                    tab
+                ));
+            }
+
+            self.default_tab = name;
+        }
+
+        if let Some(columns) = file.columns {
+            self.columns = columns
+                .iter()
+                .map(|key| parse_column("columns", key))
+                .collect::<Result<_, _>>()?;
+
+            if self.columns.is_empty() {
+                return Err(String::from("columns: at least one column is needed"));
+            }
+        }
+
+        if let Some(layout) = file.layout {
+            self.layout = match layout.as_str() {
+                "auto" => LayoutMode::Auto,
+                "compact" => LayoutMode::Compact,
+                "full" => LayoutMode::Full,
+                _ => {
+                    return Err(format!(
+                        "layout: unknown layout '{}', expected auto, compact or full",
+                        layout
+                    ))
+                }
+            };
+        }
+
+        if let Some(resolve_dns) = file.resolve_dns {
+            self.resolve_dns = resolve_dns;
+        }
+
+        if let Some(service_names) = file.service_names {
+            self.service_names = service_names;
+        }
+
+        if let Some(allowed) = file.allowed_listeners {
+            self.allowed_listeners =
+                Allowlist::parse(&allowed).map_err(|e| format!("allowed_listeners: {}", e))?;
+        }
+
+        let themes = file.themes.unwrap_or_default();
+
+        for name in themes.keys() {
+            Theme::resolve(name, &themes)?;
+        }
+
+        if let Some(theme) = file.theme {
+            self.theme = Theme::resolve(&theme, &themes).map_err(|e| format!("theme: {}", e))?;
+        }
+
+        if let Some(sort) = file.sort {
+            if let Some(column) = sort.column {
+                self.sort.column = parse_column("sort.column", &column)?;
+            }
+
+            if let Some(descending) = sort.descending {
+                self.sort.descending = descending;
+            }
+        }
+
+        for (key, width) in file.column_widths.unwrap_or_default() {
+            if !(width > 0.0 && width <= 1.0) {
+                return Err(format!(
+                    "column_widths.{}: must be between 0 and 1, got {}",
+                    key, width
+                ));
+            }
+
+            self.column_widths
+                .insert(parse_column("column_widths", &key)?, width);
+        }
+
+        if let Some(preset) = file.keymap {
+            self.keymap = Keymap::preset(&preset).ok_or_else(|| {
+                format!(
+                    "keymap: unknown preset '{}', expected default, vim or emacs",
+                    preset
+                )
+            })?;
+        }
+
+        for (name, keys) in file.keybindings.unwrap_or_default() {
+            let action = Action::from_name(&name)
+                .ok_or_else(|| format!("keybindings: unknown action '{}'", name))?;
+
+            let keys = match keys {
+                KeyList::One(key) => vec![key],
+                KeyList::Many(keys) => keys,
+            };
+
+            self.keymap.unbind(action);
+
+            for key in keys {
+                let key = Key::parse_sequence(&key)
+                    .map_err(|e| format!("keybindings.{}: {}", name, e))?;
+
+                self.keymap
+                    .bind(action, key)
+                    .map_err(|e| format!("keybindings.{}: {}", name, e))?;
+            }
+        }
+
+        self.keymap
+            .validate()
+            .map_err(|e| format!("keybindings: {}", e))?;
+
+        self.filters.extend(file.filters.unwrap_or_default());
+
+        Ok(self)
+    }
+
+    /// The starting point of the default config, which sets every field.
+    fn empty() -> Config {
+        Config {
+            refresh_interval: Duration::ZERO,
+            default_tab: String::from("all"),
+            columns: Vec::new(),
+            layout: LayoutMode::Auto,
+            resolve_dns: false,
+            service_names: false,
+            allowed_listeners: Allowlist::default(),
+            theme: Theme::monochrome(),
+            sort: SortOrder {
+                column: Column::LocalPort,
+                descending: true,
+            },
+            column_widths: HashMap::new(),
+            keymap: Keymap::preset("default").expect("the default keymap exists"),
+            filters: BTreeMap::new(),
+        }
+    }
+}
+
+fn parse_column(setting: &str, key: &str) -> Result<Column, String> {
+    Column::from_key(key).ok_or_else(|| {
+        format!(
+            "{}: unknown column '{}', expected one of {}",
+            setting,
+            key,
+            Column::ALL
+                .iter()
+                .map(|column| column.key())
+                .collect::<Vec<_>>()
+                .join(", ")
+        )
+    })
+}
@@ -0,0 +1,105 @@
+// Snapshots of the connection table written to a file.
+use std::error::Error;
+use std::path::Path;
+
+use serde_json::{Map, Value};
+
+use crate::columns::Column;
+use crate::os::SocketInfoWithProcName;
+
+#[derive(Clone, Copy)]
+pub enum ExportFormat {
+    Json,
+    Csv,
+}
+
+impl ExportFormat {
+    pub const NAMES: [&'static str; 2] = ["json", "csv"];
+
+    pub fn from_name(name: &str) -> Option<ExportFormat> {
+        match name {
+            "json" => Some(ExportFormat::Json),
+            "csv" => Some(ExportFormat::Csv),
+            _ => None,
+        }
+    }
+}
+
+/// Writes one row per connection with the given columns, keyed by their filter names.
+pub fn export(
+    path: &Path,
+    format: ExportFormat,
+    columns: &[Column],
+    connections: &[SocketInfoWithProcName],
+) -> Result<(), Box<dyn Error>> {
+    let contents = match format {
+        ExportFormat::Json => {
+            let rows: Vec<Value> = connections
+                .iter()
+                .map(|connection| {
+                    let row: Map<String, Value> = columns
+                        .iter()
+                        .map(|column| (column.key().to_string(), json_value(*column, connection)))
+                        .collect();
+
+                    Value::Object(row)
+                })
+                .collect();
+
+            serde_json::to_string_pretty(&rows)? + "\n"
+        }
+        ExportFormat::Csv => {
+            let mut lines = vec![columns
+                .iter()
+                .map(|column| column.key())
+                .collect::<Vec<_>>()
+                .join(",")];
+
+            lines.extend(connections.iter().map(|connection| {
+                columns
+                    .iter()
+                    .map(|column| csv_field(&column.text(connection)))
+                    .collect::<Vec<_>>()
+                    .join(",")
+            }));
+
+            lines.join("\n") + "\n"
+        }
+    };
+
+    std::fs::write(path, contents)?;
+
+    Ok(())
+}
+
+/// Numbers stay numbers, e.g. rates in bytes per second and durations in milliseconds.
+fn json_value(column: Column, connection: &SocketInfoWithProcName) -> Value {
+    match column.number(connection) {
+        Some(number) if number.fract() == 0.0 && number.abs() < 1e15 => Value::from(number as i64),
+        Some(number) => Value::from(number),
+        None => Value::String(column.text(connection)),
+    }
+}
+
+fn csv_field(text: &str) -> String {
+    match text.contains([',', '"', '\n', '\r']) {
+        true => format!("\"{}\"", text.replace('"', "\"\"")),
+        false => text.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn quotes_csv_fields_only_when_needed() {
+        assert_eq!(csv_field("nginx"), "nginx");
+        assert_eq!(csv_field(""), "");
+        assert_eq!(csv_field("::1"), "::1");
+        assert_eq!(csv_field("a,b"), "\"a,b\"");
+        assert_eq!(csv_field("say \"hi\""), "\"say \"\"hi\"\"\"");
+        assert_eq!(csv_field("two\nlines"), "\"two\nlines\"");
+        assert_eq!(csv_field("two\r\nlines"), "\"two\r\nlines\"");
+    }
+}
@@ -0,0 +1,260 @@
+use std::collections::BTreeMap;
+
+use regex::Regex;
+
+use crate::columns::Column;
+use crate::os::SocketInfoWithProcName;
+use crate::utils::{parse_duration, parse_rate};
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Comparison {
+    Equal,
+    Less,
+    LessOrEqual,
+    Greater,
+    GreaterOrEqual,
+}
+
+pub enum FilterValue {
+    Number(f64),
+    Text(String),
+}
+
+pub enum FilterTerm {
+    /// Matches any cell of the row.
+    Text(Regex),
+    /// Matches one column, e.g. `rtt:>100ms` or `state:listen`.
+    Field {
+        column: Column,
+        comparison: Comparison,
+        value: FilterValue,
+    },
+}
+
+/// A parsed filter input, every term has to match for a row to be shown.
+pub struct FilterQuery {
+    pub terms: Vec<FilterTerm>,
+}
+
+impl FilterQuery {
+    pub fn parse(input: &str) -> Result<FilterQuery, String> {
+        let mut terms = Vec::new();
+        let mut text: Vec<&str> = Vec::new();
+
+        for token in input.split_whitespace() {
+            // Anything that doesn't start with a known column, like an IPv6 address, is text
+            let field = token
+                .split_once(':')
+                .and_then(|(key, value)| Some((Column::from_key(key)?, value)));
+
+            match field {
+                Some((column, value)) => terms.push(parse_field(column, value)?),
+                None => text.push(token),
+            }
+        }
+
+        if !text.is_empty() {
+            let text = Regex::new(&regex::escape(&text.join(" "))).map_err(|e| e.to_string())?;
+            terms.push(FilterTerm::Text(text));
+        }
+
+        Ok(FilterQuery { terms })
+    }
+
+    pub fn matches(&self, socket: &SocketInfoWithProcName) -> bool {
+        self.terms.iter().all(|term| term.matches(socket))
+    }
+}
+
+impl FilterTerm {
+    pub fn matches(&self, socket: &SocketInfoWithProcName) -> bool {
+        match self {
+            FilterTerm::Text(regex) => socket.should_print(regex),
+            FilterTerm::Field {
+                column,
+                comparison,
+                value: FilterValue::Number(expected),
+            } => match column.number(socket) {
+                Some(actual) => match comparison {
+                    Comparison::Equal => actual == *expected,
+                    Comparison::Less => actual < *expected,
+                    Comparison::LessOrEqual => actual <= *expected,
+                    Comparison::Greater => actual > *expected,
+                    Comparison::GreaterOrEqual => actual >= *expected,
+                },
+                None => false,
+            },
+            FilterTerm::Field {
+                column,
+                value: FilterValue::Text(expected),
+                ..
+            } => column.text(socket).to_lowercase().contains(expected),
+        }
+    }
+}
+
+/// Replaces every `@name` token by the saved filter of that name.
+pub fn expand_saved_filters(
+    input: &str,
+    saved: &BTreeMap<String, String>,
+) -> Result<String, String> {
+    input
+        .split_whitespace()
+        .map(|token| match token.strip_prefix('@') {
+            Some(name) => saved
+                .get(name)
+                .cloned()
+                .ok_or_else(|| format!("unknown saved filter @{}", name)),
+            None => Ok(token.to_string()),
+        })
+        .collect::<Result<Vec<_>, _>>()
+        .map(|tokens| tokens.join(" "))
+}
+
+fn parse_field(column: Column, value: &str) -> Result<FilterTerm, String> {
+    let (comparison, value) = [
+        (">=", Comparison::GreaterOrEqual),
+        ("<=", Comparison::LessOrEqual),
+        (">", Comparison::Greater),
+        ("<", Comparison::Less),
+        ("=", Comparison::Equal),
+    ]
+    .iter()
+    .find_map(|(prefix, comparison)| Some((*comparison, value.strip_prefix(prefix)?)))
+    .unwrap_or((Comparison::Equal, value));
+
+    if value.is_empty() {
+        return Err(format!("missing value for {}", column.key()));
+    }
+
+    let number = match value.parse::<f64>() {
+        Ok(number) => Some(number),
+        Err(_) if column.is_duration() => Some(parse_duration(value)?.as_secs_f64() * 1000.0),
+        Err(_) if column.is_rate() => Some(parse_rate(value)?),
+        Err(_) => None,
+    };
+
+    let value = match number {
+        Some(number) => FilterValue::Number(number),
+        None if comparison != Comparison::Equal => {
+            return Err(format!("{} can only compare numbers", column.key()))
+        }
+        None => FilterValue::Text(value.to_lowercase()),
+    };
+
+    Ok(FilterTerm::Field {
+        column,
+        comparison,
+        value,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn field(input: &str) -> (Column, Comparison, f64) {
+        match FilterQuery::parse(input).unwrap().terms.as_slice() {
+            [FilterTerm::Field {
+                column,
+                comparison,
+                value: FilterValue::Number(value),
+            }] => (*column, *comparison, *value),
+            _ => panic!("expected one numeric term for {}", input),
+        }
+    }
+
+    #[test]
+    fn parses_comparison_operators() {
+        assert_eq!(
+            field("lport:22"),
+            (Column::LocalPort, Comparison::Equal, 22.0)
+        );
+        assert_eq!(
+            field("lport:=22"),
+            (Column::LocalPort, Comparison::Equal, 22.0)
+        );
+        assert_eq!(
+            field("lport:<1024"),
+            (Column::LocalPort, Comparison::Less, 1024.0)
+        );
+        assert_eq!(
+            field("lport:<=1024"),
+            (Column::LocalPort, Comparison::LessOrEqual, 1024.0)
+        );
+        assert_eq!(field("cwnd:>10"), (Column::Cwnd, Comparison::Greater, 10.0));
+        assert_eq!(
+            field("CWND:>=10"),
+            (Column::Cwnd, Comparison::GreaterOrEqual, 10.0)
+        );
+    }
+
+    #[test]
+    fn parses_duration_units() {
+        assert_eq!(
+            field("rtt:>100ms"),
+            (Column::Rtt, Comparison::Greater, 100.0)
+        );
+        assert_eq!(
+            field("rttvar:>=2s"),
+            (Column::RttVar, Comparison::GreaterOrEqual, 2000.0)
+        );
+        // Plain numbers are milliseconds
+        assert_eq!(field("rtt:<5"), (Column::Rtt, Comparison::Less, 5.0));
+    }
+
+    #[test]
+    fn parses_rate_units() {
+        // Plain numbers are bytes per second
+        assert_eq!(
+            field("tx:>500"),
+            (Column::TxRate, Comparison::Greater, 500.0)
+        );
+        assert_eq!(
+            field("rx:>10k"),
+            (Column::RxRate, Comparison::Greater, 10240.0)
+        );
+        assert_eq!(
+            field("rate:>=1MiB/s"),
+            (Column::TotalRate, Comparison::GreaterOrEqual, 1048576.0)
+        );
+        assert!(FilterQuery::parse("rx:>10T").is_err());
+    }
+
+    #[test]
+    fn parses_text_fields_and_free_text() {
+        let query = FilterQuery::parse("state:LISTEN nginx 8080").unwrap();
+
+        match query.terms.as_slice() {
+            [FilterTerm::Field {
+                column: Column::State,
+                comparison: Comparison::Equal,
+                value: FilterValue::Text(state),
+            }, FilterTerm::Text(text)] => {
+                assert_eq!(state, "listen");
+                assert_eq!(text.as_str(), "nginx 8080");
+            }
+            _ => panic!("expected a state term and a text term"),
+        }
+    }
+
+    #[test]
+    fn keeps_unknown_keys_and_ipv6_addresses_as_text() {
+        let query = FilterQuery::parse("fe80::1 foo:bar").unwrap();
+
+        match query.terms.as_slice() {
+            [FilterTerm::Text(text)] => assert_eq!(text.as_str(), "fe80::1 foo:bar"),
+            _ => panic!("expected a single text term"),
+        }
+        assert!(FilterQuery::parse("  ").unwrap().terms.is_empty());
+    }
+
+    #[test]
+    fn rejects_invalid_queries() {
+        assert!(FilterQuery::parse("lport:").is_err());
+        assert!(FilterQuery::parse("rtt:>").is_err());
+        assert!(FilterQuery::parse("rtt:>fast").is_err());
+        assert!(FilterQuery::parse("rtt:>3d").is_err());
+        assert!(FilterQuery::parse("state:>listen").is_err());
+    }
+}
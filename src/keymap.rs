@@ -0,0 +1,575 @@
+use std::fmt;
+
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+use crate::app::FilterMode;
+
+/// Everything a key can do, keys are bound to actions through a `Keymap`.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Action {
+    Quit,
+    Help,
+    Pause,
+    Filter,
+    ClearFilter,
+    ConfirmFilter,
+    DeleteChar,
+    Command,
+    Up,
+    Down,
+    PageUp,
+    PageDown,
+    First,
+    Last,
+    Left,
+    Right,
+    Info,
+    JumpToPeer,
+    Kill,
+    CloseConnection,
+    NextSort,
+    ReverseSort,
+    TcpInternals,
+    TopTalkers,
+    Queues,
+    Columns,
+    ToggleColumn,
+    MoveColumnLeft,
+    MoveColumnRight,
+    Stats,
+    Events,
+    NextNamespace,
+    NextState,
+    AllStates,
+}
+
+impl Action {
+    /// In the order they are listed in the help.
+    pub const ALL: [Action; 34] = [
+        Action::Filter,
+        Action::ClearFilter,
+        Action::ConfirmFilter,
+        Action::DeleteChar,
+        Action::Command,
+        Action::Up,
+        Action::Down,
+        Action::PageUp,
+        Action::PageDown,
+        Action::First,
+        Action::Last,
+        Action::Left,
+        Action::Right,
+        Action::Info,
+        Action::JumpToPeer,
+        Action::Kill,
+        Action::CloseConnection,
+        Action::NextSort,
+        Action::ReverseSort,
+        Action::TcpInternals,
+        Action::TopTalkers,
+        Action::Queues,
+        Action::Columns,
+        Action::ToggleColumn,
+        Action::MoveColumnLeft,
+        Action::MoveColumnRight,
+        Action::Stats,
+        Action::Events,
+        Action::NextNamespace,
+        Action::NextState,
+        Action::AllStates,
+        Action::Pause,
+        Action::Help,
+        Action::Quit,
+    ];
+
+    /// Name used in the `[keybindings]` section of the config.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Action::Quit => "quit",
+            Action::Help => "help",
+            Action::Pause => "pause",
+            Action::Filter => "filter",
+            Action::ClearFilter => "clear_filter",
+            Action::ConfirmFilter => "confirm_filter",
+            Action::DeleteChar => "delete_char",
+            Action::Command => "command",
+            Action::Up => "up",
+            Action::Down => "down",
+            Action::PageUp => "page_up",
+            Action::PageDown => "page_down",
+            Action::First => "first",
+            Action::Last => "last",
+            Action::Left => "left",
+            Action::Right => "right",
+            Action::Info => "info",
+            Action::JumpToPeer => "jump_to_peer",
+            Action::Kill => "kill",
+            Action::CloseConnection => "close_connection",
+            Action::NextSort => "next_sort",
+            Action::ReverseSort => "reverse_sort",
+            Action::TcpInternals => "tcp_internals",
+            Action::TopTalkers => "top_talkers",
+            Action::Queues => "queues",
+            Action::Columns => "columns",
+            Action::ToggleColumn => "toggle_column",
+            Action::MoveColumnLeft => "move_column_left",
+            Action::MoveColumnRight => "move_column_right",
+            Action::Stats => "stats",
+            Action::Events => "events",
+            Action::NextNamespace => "next_namespace",
+            Action::NextState => "next_state",
+            Action::AllStates => "all_states",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Action> {
+        Action::ALL
+            .iter()
+            .find(|action| action.name() == name)
+            .copied()
+    }
+
+    pub fn description(&self) -> &'static str {
+        match self {
+            Action::Quit => "Quit",
+            Action::Help => "Show/hide this help",
+            Action::Pause => "Pause on current connection list",
+            Action::Filter => "Type a filter, e.g. state:listen rtt:>100ms",
+            Action::ClearFilter => "Clear the filter",
+            Action::ConfirmFilter => "Apply the filter and return to the table",
+            Action::DeleteChar => "Delete the last character of the filter",
+            Action::Command => "Type a command, e.g. :sort rport desc",
+            Action::Up => "Select the previous connection",
+            Action::Down => "Select the next connection",
+            Action::PageUp => "Move up a page",
+            Action::PageDown => "Move down a page",
+            Action::First => "Select the first connection",
+            Action::Last => "Select the last connection",
+            Action::Left => "Show the previous tab",
+            Action::Right => "Show the next tab",
+            Action::Info => "Show/hide the connection info",
+            Action::JumpToPeer => "Jump to the other end of a local connection",
+            Action::Kill => "Send a signal to the process of the selected connection",
+            Action::CloseConnection => "Close the selected TCP connection (needs CAP_NET_ADMIN)",
+            Action::NextSort => "Sort by the next column",
+            Action::ReverseSort => "Reverse the sort order",
+            Action::TcpInternals => "Show/hide the TCP internals columns",
+            Action::TopTalkers => "Sort by throughput to show the top talkers",
+            Action::Queues => "Show/hide the Recv-Q and Send-Q columns",
+            Action::Columns => "Open/close the column list to choose and reorder columns",
+            Action::ToggleColumn => "Show/hide the column selected in the column list",
+            Action::MoveColumnLeft => "Move the selected column to the left",
+            Action::MoveColumnRight => "Move the selected column to the right",
+            Action::Stats => "Show/hide the statistics panel",
+            Action::Events => "Show/hide the log of signals sent and connections closed",
+            Action::NextNamespace => "List the next network namespace, then all of them",
+            Action::NextState => "Show only the next TCP state",
+            Action::AllStates => "Show all TCP states",
+        }
+    }
+
+    /// Whether the action is used while typing a filter rather than on the table.
+    pub fn is_typing(&self) -> bool {
+        matches!(
+            self,
+            Action::ConfirmFilter | Action::DeleteChar | Action::ClearFilter
+        )
+    }
+
+    pub fn is_normal(&self) -> bool {
+        !matches!(self, Action::ConfirmFilter | Action::DeleteChar)
+    }
+}
+
+/// Keys the signal and close dialogs handle before the keymap, they can't be rebound.
+const DIALOG_KEYS: [(&str, &str); 3] = [
+    (
+        "y | <Enter>",
+        "Send the signal or close the connection in a dialog",
+    ),
+    ("n | <Esc>", "Go back or cancel in a dialog"),
+    (
+        "k | j | <Up> | <Down>",
+        "Choose a signal, or type a custom one last in the list",
+    ),
+];
+
+/// Keys of the command line, which are also taken before the keymap.
+const COMMAND_KEYS: [(&str, &str); 6] = [
+    ("<Enter>", "Run the command"),
+    ("<Esc>", "Cancel the command"),
+    ("<Tab>", "Complete, again for the next candidate"),
+    ("<Up> | <Down>", "Go through the command history"),
+    (
+        "<BS>",
+        "Delete the last character, on an empty command cancel it",
+    ),
+    ("<C-u>", "Clear the command"),
+];
+
+/// A single key press, with the modifiers that matter for bindings.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Key {
+    pub code: KeyCode,
+    pub modifiers: KeyModifiers,
+}
+
+impl Key {
+    pub fn new(code: KeyCode, modifiers: KeyModifiers) -> Key {
+        // Shift is already part of the character, 'G' is sent with or without it
+        let modifiers = match code {
+            KeyCode::Char(_) => modifiers - KeyModifiers::SHIFT,
+            _ => modifiers,
+        };
+
+        Key { code, modifiers }
+    }
+
+    /// Parses one or more keys in vim notation, e.g. `gg`, `<C-c>` or `<C-x><C-c>`.
+    pub fn parse_sequence(text: &str) -> Result<Vec<Key>, String> {
+        let chars: Vec<char> = text.chars().collect();
+        let mut keys = Vec::new();
+        let mut i = 0;
+
+        while i < chars.len() {
+            if chars[i] != '<' {
+                keys.push(Key::new(KeyCode::Char(chars[i]), KeyModifiers::NONE));
+                i += 1;
+                continue;
+            }
+
+            let mut modifiers = KeyModifiers::NONE;
+            i += 1;
+
+            while i + 2 < chars.len() && chars[i + 1] == '-' {
+                modifiers |= match chars[i] {
+                    'C' => KeyModifiers::CONTROL,
+                    'M' | 'A' => KeyModifiers::ALT,
+                    'S' => KeyModifiers::SHIFT,
+                    _ => break,
+                };
+                i += 2;
+            }
+
+            // A single character may itself be '>' or '-', e.g. <M->>
+            let name: String = match chars.get(i + 1) {
+                Some('>') => chars[i].to_string(),
+                _ => chars[i..].iter().take_while(|c| **c != '>').collect(),
+            };
+
+            i += name.chars().count();
+
+            if chars.get(i) != Some(&'>') {
+                return Err(format!("missing '>' in '{}'", text));
+            }
+
+            i += 1;
+
+            let code = match name.to_lowercase().as_str() {
+                _ if name.chars().count() == 1 => KeyCode::Char(name.chars().next().unwrap()),
+                "up" => KeyCode::Up,
+                "down" => KeyCode::Down,
+                "left" => KeyCode::Left,
+                "right" => KeyCode::Right,
+                "home" => KeyCode::Home,
+                "end" => KeyCode::End,
+                "pageup" => KeyCode::PageUp,
+                "pagedown" => KeyCode::PageDown,
+                "tab" => KeyCode::Tab,
+                "backtab" => KeyCode::BackTab,
+                "enter" | "cr" => KeyCode::Enter,
+                "esc" => KeyCode::Esc,
+                "bs" | "backspace" => KeyCode::Backspace,
+                "del" | "delete" => KeyCode::Delete,
+                "space" => KeyCode::Char(' '),
+                "lt" => KeyCode::Char('<'),
+                name => match name.strip_prefix('f').and_then(|n| n.parse().ok()) {
+                    Some(n) => KeyCode::F(n),
+                    None => return Err(format!("unknown key <{}> in '{}'", name, text)),
+                },
+            };
+
+            keys.push(Key::new(code, modifiers));
+        }
+
+        if keys.is_empty() {
+            return Err(String::from("empty key"));
+        }
+
+        Ok(keys)
+    }
+}
+
+impl From<KeyEvent> for Key {
+    fn from(event: KeyEvent) -> Key {
+        Key::new(event.code, event.modifiers)
+    }
+}
+
+impl fmt::Display for Key {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self.code {
+            KeyCode::Char(' ') => String::from("Space"),
+            KeyCode::Char('<') => String::from("lt"),
+            KeyCode::Char(c) => c.to_string(),
+            KeyCode::F(n) => format!("F{}", n),
+            KeyCode::BackTab => String::from("BackTab"),
+            KeyCode::PageUp => String::from("PageUp"),
+            KeyCode::PageDown => String::from("PageDown"),
+            KeyCode::Backspace => String::from("BS"),
+            code => format!("{:?}", code),
+        };
+
+        let mut prefix = String::new();
+
+        if self.modifiers.contains(KeyModifiers::CONTROL) {
+            prefix.push_str("C-");
+        }
+
+        if self.modifiers.contains(KeyModifiers::ALT) {
+            prefix.push_str("M-");
+        }
+
+        if self.modifiers.contains(KeyModifiers::SHIFT) {
+            prefix.push_str("S-");
+        }
+
+        match (self.code, prefix.is_empty()) {
+            (KeyCode::Char(c), true) if c != ' ' && c != '<' => write!(f, "{}", c),
+            _ => write!(f, "<{}{}>", prefix, name),
+        }
+    }
+}
+
+pub struct Binding {
+    pub keys: Vec<Key>,
+    pub action: Action,
+}
+
+impl fmt::Display for Binding {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.keys.iter().try_for_each(|key| write!(f, "{}", key))
+    }
+}
+
+pub enum Lookup {
+    Action(Action),
+    /// The keys start a longer binding, like the first `g` of `gg`.
+    Pending,
+    None,
+}
+
+/// Key bindings for the table and for the filter box.
+pub struct Keymap {
+    pub normal: Vec<Binding>,
+    pub typing: Vec<Binding>,
+}
+
+impl Keymap {
+    /// Bindings of a preset: default, vim or emacs.
+    pub fn preset(name: &str) -> Option<Keymap> {
+        let mut bindings = vec![
+            (Action::Quit, "q"),
+            (Action::Quit, "<C-c>"),
+            (Action::Help, "?"),
+            (Action::Pause, "p"),
+            (Action::Filter, "/"),
+            (Action::ClearFilter, "c"),
+            (Action::ConfirmFilter, "<Enter>"),
+            (Action::ConfirmFilter, "<Esc>"),
+            (Action::DeleteChar, "<BS>"),
+            (Action::Command, ":"),
+            (Action::Up, "k"),
+            (Action::Up, "<Up>"),
+            (Action::Down, "j"),
+            (Action::Down, "<Down>"),
+            (Action::PageUp, "<PageUp>"),
+            (Action::PageDown, "<PageDown>"),
+            (Action::First, "gg"),
+            (Action::First, "<Home>"),
+            (Action::Last, "G"),
+            (Action::Last, "<End>"),
+            (Action::Left, "h"),
+            (Action::Left, "<Left>"),
+            (Action::Left, "<BackTab>"),
+            (Action::Right, "l"),
+            (Action::Right, "<Right>"),
+            (Action::Right, "<Tab>"),
+            (Action::Info, "i"),
+            (Action::JumpToPeer, "o"),
+            (Action::Kill, "K"),
+            (Action::CloseConnection, "X"),
+            (Action::NextSort, "s"),
+            (Action::ReverseSort, "S"),
+            (Action::TcpInternals, "t"),
+            (Action::TopTalkers, "T"),
+            (Action::Queues, "Q"),
+            (Action::Columns, "C"),
+            (Action::ToggleColumn, "<Space>"),
+            (Action::MoveColumnLeft, "<lt>"),
+            (Action::MoveColumnRight, ">"),
+            (Action::Stats, "v"),
+            (Action::Events, "e"),
+            (Action::NextNamespace, "n"),
+            (Action::NextState, "f"),
+            (Action::AllStates, "F"),
+        ];
+
+        match name {
+            "default" => {}
+            "vim" => bindings.extend([
+                (Action::PageDown, "<C-d>"),
+                (Action::PageUp, "<C-u>"),
+                (Action::PageDown, "<C-f>"),
+                (Action::PageUp, "<C-b>"),
+                (Action::DeleteChar, "<C-h>"),
+            ]),
+            "emacs" => {
+                // Arrows move as before, h/j/k/l give way to C-p/C-n/C-b/C-f
+                bindings.retain(|(action, key)| {
+                    !matches!(
+                        action,
+                        Action::Up | Action::Down | Action::Left | Action::Right
+                    ) || key.starts_with('<')
+                });
+
+                bindings.extend([
+                    (Action::Up, "<C-p>"),
+                    (Action::Down, "<C-n>"),
+                    (Action::Left, "<C-b>"),
+                    (Action::Right, "<C-f>"),
+                    (Action::PageDown, "<C-v>"),
+                    (Action::PageUp, "<M-v>"),
+                    (Action::First, "<M-lt>"),
+                    (Action::Last, "<M->>"),
+                    (Action::Filter, "<C-s>"),
+                    (Action::ConfirmFilter, "<C-g>"),
+                    (Action::ClearFilter, "<C-k>"),
+                    (Action::DeleteChar, "<C-h>"),
+                    (Action::Quit, "<C-x><C-c>"),
+                ]);
+            }
+            _ => return None,
+        }
+
+        let mut keymap = Keymap {
+            normal: Vec::new(),
+            typing: Vec::new(),
+        };
+
+        for (action, keys) in bindings {
+            let keys = Key::parse_sequence(keys).expect("preset keys are valid");
+            keymap.bind(action, keys).expect("preset keys are valid");
+        }
+
+        Some(keymap)
+    }
+
+    /// Binds `keys` in every mode the action applies to.
+    pub fn bind(&mut self, action: Action, keys: Vec<Key>) -> Result<(), String> {
+        // Control keys are the only ones that can act in the filter box, the rest are typed
+        let typable = keys.len() > 1
+            || keys.iter().any(|key| {
+                matches!(key.code, KeyCode::Char(_))
+                    && !key
+                        .modifiers
+                        .intersects(KeyModifiers::CONTROL | KeyModifiers::ALT)
+            });
+
+        if action.is_typing() && !typable {
+            self.typing.push(Binding {
+                keys: keys.clone(),
+                action,
+            });
+        } else if !action.is_normal() {
+            return Err(format!(
+                "{} would be typed into the filter, {} needs a special or control key",
+                Binding { keys, action },
+                action.name()
+            ));
+        }
+
+        if action.is_normal() {
+            self.normal.push(Binding { keys, action });
+        }
+
+        Ok(())
+    }
+
+    /// Drops every binding of an action, before the config binds it to other keys.
+    pub fn unbind(&mut self, action: Action) {
+        self.normal.retain(|binding| binding.action != action);
+        self.typing.retain(|binding| binding.action != action);
+    }
+
+    /// Errors if a key sequence is bound twice, or is the start of another binding.
+    pub fn validate(&self) -> Result<(), String> {
+        for bindings in [&self.normal, &self.typing] {
+            for (i, a) in bindings.iter().enumerate() {
+                for b in &bindings[i + 1..] {
+                    if a.keys.starts_with(&b.keys) || b.keys.starts_with(&a.keys) {
+                        return Err(format!(
+                            "{} ({}) conflicts with {} ({})",
+                            a,
+                            a.action.name(),
+                            b,
+                            b.action.name()
+                        ));
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn lookup(&self, typing: bool, keys: &[Key]) -> Lookup {
+        let bindings = match typing {
+            true => &self.typing,
+            false => &self.normal,
+        };
+
+        if let Some(binding) = bindings.iter().find(|binding| binding.keys == keys) {
+            return Lookup::Action(binding.action);
+        }
+
+        match bindings
+            .iter()
+            .any(|binding| binding.keys.starts_with(keys))
+        {
+            true => Lookup::Pending,
+            false => Lookup::None,
+        }
+    }
+
+    /// The keys and what they do in `mode`: every action with the keys bound to it, in help
+    /// order, then the fixed keys of the dialogs or the command line.
+    pub fn help(&self, mode: &FilterMode) -> Vec<(String, &'static str)> {
+        let (bindings, fixed) = match mode {
+            FilterMode::Normal => (self.normal.as_slice(), DIALOG_KEYS.as_slice()),
+            FilterMode::Typing => (self.typing.as_slice(), [].as_slice()),
+            FilterMode::Command => ([].as_slice(), COMMAND_KEYS.as_slice()),
+        };
+
+        Action::ALL
+            .iter()
+            .filter_map(|action| {
+                let keys: Vec<String> = bindings
+                    .iter()
+                    .filter(|binding| binding.action == *action)
+                    .map(|binding| binding.to_string())
+                    .collect();
+
+                match keys.is_empty() {
+                    true => None,
+                    false => Some((keys.join(" | "), action.description())),
+                }
+            })
+            .chain(
+                fixed
+                    .iter()
+                    .map(|(keys, description)| (keys.to_string(), *description)),
+            )
+            .collect()
+    }
+}
@@ -0,0 +1,140 @@
+// Listening sockets that are reachable from every interface, and the ones that are expected to be.
+use crate::os::{BindScope, Protocol, SocketInfoWithProcName};
+
+/// A port, for one protocol or both, that may listen on all interfaces.
+#[derive(Clone, PartialEq, Eq, Debug)]
+struct AllowedListener {
+    protocol: Option<Protocol>,
+    port: u16,
+}
+
+/// The `allowed_listeners` of the config, entries like `22`, `tcp:443` or `udp:53`.
+#[derive(Clone, Default, Debug)]
+pub struct Allowlist {
+    entries: Vec<AllowedListener>,
+}
+
+impl Allowlist {
+    pub fn parse<S: AsRef<str>>(entries: &[S]) -> Result<Allowlist, String> {
+        let entries = entries
+            .iter()
+            .map(|entry| {
+                let entry = entry.as_ref();
+
+                let (protocol, port) = match entry.split_once(':') {
+                    Some(("tcp", port)) => (Some(Protocol::TCP), port),
+                    Some(("udp", port)) => (Some(Protocol::UDP), port),
+                    Some((protocol, _)) => {
+                        return Err(format!(
+                            "unknown protocol '{}' in '{}', expected tcp or udp",
+                            protocol, entry
+                        ))
+                    }
+                    None => (None, entry),
+                };
+
+                let port = port
+                    .parse()
+                    .map_err(|_| format!("invalid port in '{}'", entry))?;
+
+                Ok(AllowedListener { protocol, port })
+            })
+            .collect::<Result<_, String>>()?;
+
+        Ok(Allowlist { entries })
+    }
+
+    pub fn extend(&mut self, other: Allowlist) {
+        self.entries.extend(other.entries);
+    }
+
+    fn allows(&self, socket: &SocketInfoWithProcName) -> bool {
+        self.entries.iter().any(|entry| {
+            Some(entry.port) == socket.local_port()
+                && entry
+                    .protocol
+                    .is_none_or(|protocol| protocol == socket.protocol_flags)
+        })
+    }
+
+    /// A listener bound to all interfaces that isn't allowed to be.
+    pub fn is_exposed(&self, socket: &SocketInfoWithProcName) -> bool {
+        socket.is_listener() && socket.bind_scope() == Some(BindScope::All) && !self.allows(socket)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use netstat2::TcpState;
+
+    use super::*;
+    use crate::os::fixtures::{dual_stack, tcp, tcp_listener, udp};
+
+    #[test]
+    fn parses_ports_with_and_without_a_protocol() {
+        let allowlist = Allowlist::parse(&["22", "tcp:443", "udp:53"]).unwrap();
+
+        assert_eq!(
+            allowlist.entries,
+            [
+                AllowedListener {
+                    protocol: None,
+                    port: 22,
+                },
+                AllowedListener {
+                    protocol: Some(Protocol::TCP),
+                    port: 443,
+                },
+                AllowedListener {
+                    protocol: Some(Protocol::UDP),
+                    port: 53,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_entries() {
+        assert_eq!(
+            Allowlist::parse(&["sctp:9"]).unwrap_err(),
+            "unknown protocol 'sctp' in 'sctp:9', expected tcp or udp"
+        );
+        assert_eq!(
+            Allowlist::parse(&["tcp:"]).unwrap_err(),
+            "invalid port in 'tcp:'"
+        );
+        assert!(Allowlist::parse(&["65536"]).is_err());
+        assert!(Allowlist::parse(&["ssh"]).is_err());
+        assert!(Allowlist::parse::<&str>(&[]).unwrap().entries.is_empty());
+    }
+
+    #[test]
+    fn only_exposes_listeners_on_all_interfaces() {
+        let allowlist = Allowlist::default();
+
+        assert!(allowlist.is_exposed(&tcp_listener("0.0.0.0:8080", "app")));
+        assert!(allowlist.is_exposed(&dual_stack(tcp_listener("[::]:8080", "app"), true)));
+        assert!(allowlist.is_exposed(&udp("0.0.0.0:5353", "avahi-daemon")));
+        assert!(!allowlist.is_exposed(&tcp_listener("127.0.0.1:8080", "app")));
+        assert!(!allowlist.is_exposed(&tcp_listener("[::1]:8080", "app")));
+        assert!(!allowlist.is_exposed(&tcp_listener("10.0.0.1:8080", "app")));
+        assert!(!allowlist.is_exposed(&tcp(
+            "0.0.0.0:8080",
+            "10.0.0.2:50000",
+            TcpState::Established,
+            "app"
+        )));
+    }
+
+    #[test]
+    fn allows_ports_per_protocol() {
+        let allowlist = Allowlist::parse(&["22", "tcp:443", "udp:53"]).unwrap();
+
+        assert!(!allowlist.is_exposed(&tcp_listener("0.0.0.0:22", "sshd")));
+        assert!(!allowlist.is_exposed(&udp("0.0.0.0:22", "app")));
+        assert!(!allowlist.is_exposed(&tcp_listener("0.0.0.0:443", "nginx")));
+        assert!(allowlist.is_exposed(&udp("0.0.0.0:443", "quic")));
+        assert!(!allowlist.is_exposed(&udp("0.0.0.0:53", "dnsmasq")));
+        assert!(allowlist.is_exposed(&tcp_listener("0.0.0.0:53", "dnsmasq")));
+    }
+}
@@ -1,39 +1,96 @@
 mod app;
+mod baseline;
+mod cli;
+mod columns;
+mod command;
+mod commands;
+mod config;
+mod export;
+mod filter;
+mod keymap;
+mod listeners;
 mod os;
+mod resolve;
+mod rules;
+mod signal;
+mod stats;
+mod theme;
 mod ui;
 mod utils;
 
 use app::{App, FilterMode};
+use baseline::Baseline;
+use clap::Parser;
+use cli::Cli;
+use config::Config;
+use keymap::{Key, Lookup};
 
 use crossterm::{
-    event::{self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode, KeyEvent},
+    event::{
+        self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode, KeyModifiers, MouseButton,
+        MouseEventKind,
+    },
     execute,
     terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
 };
 
 use std::io;
-use std::{sync::Arc, time::Duration};
+use std::sync::Arc;
 use tokio::sync::Mutex;
 use tui::{backend::CrosstermBackend, Terminal};
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
-    // FIXME: Add cli arguments to take a tick rate
-    let tick_rate = Duration::from_millis(500);
+    let cli = Cli::parse();
+
+    if let Some(command) = cli.command {
+        match commands::run(command, cli.config.as_deref()).await {
+            Ok(0) => return Ok(()),
+            Ok(status) => std::process::exit(status),
+            Err(e) => {
+                eprintln!("nets: {}", e);
+                std::process::exit(2);
+            }
+        }
+    }
+
+    let config = match Config::load(cli.config.as_deref()) {
+        Ok(config) => config,
+        Err(e) => {
+            eprintln!("nets: {}", e);
+            std::process::exit(2);
+        }
+    };
+
+    let mut app = App::new(config);
 
-    let app = Arc::new(Mutex::new(App::new()));
+    if let Some(name) = &cli.netns {
+        if let Err(e) = app.set_namespace(name) {
+            eprintln!("nets: {}", e);
+            std::process::exit(2);
+        }
+    }
+
+    if let Some(path) = &cli.baseline {
+        match Baseline::load(path) {
+            Ok(baseline) => app.set_baseline(baseline),
+            Err(e) => {
+                eprintln!("nets: {}", e);
+                std::process::exit(2);
+            }
+        }
+    }
+
+    let app = Arc::new(Mutex::new(app));
 
     let cloned_app = Arc::clone(&app);
 
-    try_main(&cloned_app, tick_rate).await?;
+    try_main(&cloned_app).await?;
 
     Ok(())
 }
 
-async fn try_main(
-    app: &Arc<Mutex<App>>,
-    tick_rate: Duration,
-) -> Result<(), Box<dyn std::error::Error>> {
+async fn try_main(app: &Arc<Mutex<App>>) -> Result<(), Box<dyn std::error::Error>> {
     enable_raw_mode()?;
 
     let mut stdout = io::stdout();
@@ -41,53 +98,70 @@ async fn try_main(
     let backend = CrosstermBackend::new(stdout);
     let mut terminal = Terminal::new(backend)?;
 
-    let mut previous_key_event: Option<KeyEvent> = None;
-    let mut skip_key: bool;
+    // Keys typed so far of a binding made of several keys, like gg
+    let mut pending: Vec<Key> = Vec::new();
 
     loop {
         let mut app = app.lock().await;
 
         terminal.draw(|f| ui::draw_ui(f, &mut app))?;
 
-        if crossterm::event::poll(tick_rate)? {
-            skip_key = false;
-            if let Event::Key(key) = event::read()? {
-                match app.filter.mode {
-                    FilterMode::Normal => match key.code {
-                        KeyCode::Char('q') => app.should_quit = true,
-                        KeyCode::Char('?') => app.on_show_help(),
-                        KeyCode::Char('p') => app.on_pause(),
-                        KeyCode::Char('g') => {
-                            if previous_key_event == Some(KeyEvent::from(KeyCode::Char('g'))) {
-                                app.on_first();
-                                skip_key = true;
-                            }
-                        }
-                        KeyCode::Char(c) => app.on_key(c),
-                        KeyCode::Up => app.on_up(),
-                        KeyCode::Down => app.on_down(),
-                        KeyCode::Left => app.on_left(),
-                        KeyCode::Right => app.on_right(),
-                        KeyCode::Tab => app.on_right(),
-                        _ => {}
-                    },
-                    FilterMode::Typing => match key.code {
-                        KeyCode::Enter | KeyCode::Esc => {
-                            app.update_regex();
-                            app.filter.mode = FilterMode::Normal;
-                        }
-                        KeyCode::Char(c) => {
-                            app.filter.input.push(c);
-                            app.update_regex();
+        // The interval can be changed with :interval
+        if crossterm::event::poll(app.config.refresh_interval)? {
+            match event::read()? {
+                Event::Key(key) if app.has_dialog() => {
+                    pending.clear();
+                    app.on_dialog_key(key);
+                }
+                Event::Key(key) if matches!(app.filter.mode, FilterMode::Command) => {
+                    pending.clear();
+                    app.on_command_key(key);
+                }
+                Event::Key(key) => {
+                    let typing = matches!(app.filter.mode, FilterMode::Typing);
+
+                    pending.push(Key::from(key));
+
+                    let mut lookup = app.config.keymap.lookup(typing, &pending);
+
+                    // A key that doesn't continue a binding starts a new one
+                    if matches!(lookup, Lookup::None) && pending.len() > 1 {
+                        pending = vec![Key::from(key)];
+                        lookup = app.config.keymap.lookup(typing, &pending);
+                    }
+
+                    match lookup {
+                        Lookup::Action(action) => {
+                            pending.clear();
+                            app.on_action(action);
                         }
-                        KeyCode::Backspace => {
-                            app.filter.input.pop();
-                            app.update_regex();
+                        Lookup::Pending => {}
+                        Lookup::None => {
+                            pending.clear();
+
+                            if let KeyCode::Char(c) = key.code {
+                                if typing
+                                    && !key
+                                        .modifiers
+                                        .intersects(KeyModifiers::CONTROL | KeyModifiers::ALT)
+                                {
+                                    app.on_filter_input(c);
+                                }
+                            }
                         }
-                        _ => {}
-                    },
+                    }
                 }
-                previous_key_event = if skip_key { None } else { Some(key) };
+                // Dialogs are answered with the keyboard
+                Event::Mouse(_) if app.has_dialog() => {}
+                Event::Mouse(mouse) => match mouse.kind {
+                    MouseEventKind::Down(MouseButton::Left) => {
+                        app.on_click(mouse.column, mouse.row)
+                    }
+                    MouseEventKind::ScrollDown => app.on_scroll(mouse.column, mouse.row, true),
+                    MouseEventKind::ScrollUp => app.on_scroll(mouse.column, mouse.row, false),
+                    _ => {}
+                },
+                _ => {}
             }
         }
 
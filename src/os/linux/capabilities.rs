@@ -0,0 +1,51 @@
+// Effective capabilities of nets, which decide what it can see and do as a normal user.
+use crate::os::shared::Capability;
+
+/// Bit numbers from linux/capability.h.
+pub const CAP_DAC_READ_SEARCH: u32 = 2;
+pub const CAP_NET_ADMIN: u32 = 12;
+pub const CAP_SYS_PTRACE: u32 = 19;
+pub const CAP_SYS_ADMIN: u32 = 21;
+
+/// The CapEff mask from /proc/self/status, `None` when it can't be read.
+pub fn effective() -> Option<u64> {
+    let status = std::fs::read_to_string("/proc/self/status").ok()?;
+
+    status
+        .lines()
+        .find_map(|line| line.strip_prefix("CapEff:"))
+        .and_then(|mask| u64::from_str_radix(mask.trim(), 16).ok())
+}
+
+pub fn has_capability(capability: u32) -> bool {
+    effective().is_some_and(|mask| mask & (1 << capability) != 0)
+}
+
+pub fn get_os_capabilities() -> Vec<Capability> {
+    [
+        (
+            "CAP_SYS_PTRACE",
+            CAP_SYS_PTRACE,
+            "find the processes of other users' sockets",
+        ),
+        (
+            "CAP_DAC_READ_SEARCH",
+            CAP_DAC_READ_SEARCH,
+            "list the open files of other users' processes",
+        ),
+        ("CAP_NET_ADMIN", CAP_NET_ADMIN, "close connections (X)"),
+        (
+            "CAP_SYS_ADMIN",
+            CAP_SYS_ADMIN,
+            "enter other network namespaces (n)",
+        ),
+    ]
+    .into_iter()
+    .map(|(name, capability, needed_for)| Capability {
+        name,
+        granted: has_capability(capability),
+        needed_for,
+        attributes_sockets: matches!(capability, CAP_SYS_PTRACE | CAP_DAC_READ_SEARCH),
+    })
+    .collect()
+}
@@ -0,0 +1,192 @@
+// Units and containers of processes, from the path of their cgroup.
+use std::fs;
+
+use crate::os::shared::Cgroup;
+
+pub fn get_os_cgroup(pid: u32) -> Option<Cgroup> {
+    let contents = fs::read_to_string(format!("/proc/{}/cgroup", pid)).ok()?;
+
+    Some(parse_cgroup_path(cgroup_path(&contents)?))
+}
+
+/// The path in a /proc/<pid>/cgroup file. cgroup v2 has a single "0::" line, v1 has one
+/// per controller with systemd's named.
+fn cgroup_path(contents: &str) -> Option<&str> {
+    contents
+        .lines()
+        .find_map(|line| line.strip_prefix("0::"))
+        .filter(|path| *path != "/")
+        .or_else(|| {
+            contents
+                .lines()
+                .find_map(|line| Some(line.split_once(":name=systemd:")?.1))
+        })
+}
+
+/// Reads paths like /system.slice/nginx.service, /system.slice/docker-<id>.scope or
+/// /kubepods.slice/kubepods-burstable-pod<uid>.slice/cri-containerd-<id>.scope.
+fn parse_cgroup_path(path: &str) -> Cgroup {
+    let mut cgroup = Cgroup::default();
+
+    for component in path.split('/') {
+        if let Some(id) = container_id(component) {
+            cgroup.container = Some(id.to_string());
+        } else if let Some(uid) = pod_uid(component) {
+            cgroup.pod = Some(uid);
+        } else if component.ends_with(".service") || component.ends_with(".scope") {
+            // The innermost unit, e.g. an app scope inside user@1000.service
+            cgroup.unit = Some(component.to_string());
+        }
+    }
+
+    cgroup
+}
+
+/// Container runtimes name cgroups after the 64 hex digit ID, with their own prefix.
+fn container_id(component: &str) -> Option<&str> {
+    component
+        .split(['-', '.', '_', ':'])
+        .find(|part| part.len() == 64 && part.chars().all(|c| c.is_ascii_hexdigit()))
+}
+
+/// The systemd driver writes pod UIDs with underscores instead of dashes.
+fn pod_uid(component: &str) -> Option<String> {
+    let start = component.rfind("pod")? + 3;
+    let uid = component[start..]
+        .trim_end_matches(".slice")
+        .replace('_', "-");
+
+    let is_uid = uid.len() == 36
+        && uid.chars().enumerate().all(|(i, c)| match i {
+            8 | 13 | 18 | 23 => c == '-',
+            _ => c.is_ascii_hexdigit(),
+        });
+
+    is_uid.then_some(uid)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ID: &str = "4f1c2a9d3b7e8f60a1b2c3d4e5f60718293a4b5c6d7e8f9012a3b4c5d6e7f809";
+
+    fn cgroup(unit: Option<&str>, container: Option<&str>, pod: Option<&str>) -> Cgroup {
+        Cgroup {
+            unit: unit.map(String::from),
+            container: container.map(String::from),
+            pod: pod.map(String::from),
+        }
+    }
+
+    fn parse(contents: &str) -> Cgroup {
+        parse_cgroup_path(cgroup_path(contents).unwrap())
+    }
+
+    #[test]
+    fn reads_systemd_units() {
+        assert_eq!(
+            parse("0::/system.slice/nginx.service\n"),
+            cgroup(Some("nginx.service"), None, None)
+        );
+        assert_eq!(
+            parse("0::/user.slice/user-1000.slice/user@1000.service/app.slice/app-firefox-2481.scope\n"),
+            cgroup(Some("app-firefox-2481.scope"), None, None)
+        );
+        assert_eq!(
+            parse(
+                "12:pids:/system.slice/sshd.service\n\
+                 1:name=systemd:/system.slice/sshd.service\n\
+                 0::/system.slice/sshd.service\n"
+            ),
+            cgroup(Some("sshd.service"), None, None)
+        );
+    }
+
+    #[test]
+    fn falls_back_to_the_v1_systemd_hierarchy() {
+        // Hybrid hierarchies leave the v2 line at the root
+        let contents = format!(
+            "11:memory:/docker/{id}\n1:name=systemd:/docker/{id}\n0::/\n",
+            id = ID
+        );
+
+        assert_eq!(
+            cgroup_path(&contents),
+            Some(format!("/docker/{}", ID).as_str())
+        );
+        assert_eq!(parse(&contents), cgroup(None, Some(ID), None));
+        assert_eq!(cgroup_path("0::/\n"), None);
+        assert_eq!(cgroup_path("4:cpu,cpuacct:/\n"), None);
+    }
+
+    #[test]
+    fn reads_container_ids_of_each_runtime() {
+        for path in [
+            format!("/system.slice/docker-{}.scope", ID),
+            format!("/docker/{}", ID),
+            format!("/machine.slice/libpod-{}.scope/container", ID),
+            format!("/system.slice/containerd.service/default/{}", ID),
+        ] {
+            assert_eq!(
+                parse_cgroup_path(&path).container.as_deref(),
+                Some(ID),
+                "{}",
+                path
+            );
+        }
+
+        assert_eq!(container_id(&format!("crio-{}.scope", ID)), Some(ID));
+        assert_eq!(
+            container_id(&format!("cri-containerd-{}.scope", ID)),
+            Some(ID)
+        );
+        assert_eq!(container_id("docker-4f1c2a.scope"), None);
+        assert_eq!(container_id("nginx.service"), None);
+    }
+
+    #[test]
+    fn reads_kubernetes_pods() {
+        // systemd cgroup driver, with containerd and CRI-O
+        assert_eq!(
+            parse(&format!(
+                "0::/kubepods.slice/kubepods-burstable.slice/kubepods-burstable-pod3b5d9b0e_1f0c_4c39_9f2a_0d6f8c3d1e27.slice/cri-containerd-{}.scope\n",
+                ID
+            )),
+            cgroup(
+                None,
+                Some(ID),
+                Some("3b5d9b0e-1f0c-4c39-9f2a-0d6f8c3d1e27")
+            )
+        );
+        assert_eq!(
+            parse(&format!(
+                "0::/kubepods.slice/kubepods-pod3b5d9b0e_1f0c_4c39_9f2a_0d6f8c3d1e27.slice/crio-{}.scope\n",
+                ID
+            ))
+            .pod
+            .as_deref(),
+            Some("3b5d9b0e-1f0c-4c39-9f2a-0d6f8c3d1e27")
+        );
+
+        // cgroupfs driver on cgroup v1
+        assert_eq!(
+            parse(&format!(
+                "1:name=systemd:/kubepods/besteffort/pod3b5d9b0e-1f0c-4c39-9f2a-0d6f8c3d1e27/{}\n",
+                ID
+            )),
+            cgroup(None, Some(ID), Some("3b5d9b0e-1f0c-4c39-9f2a-0d6f8c3d1e27"))
+        );
+    }
+
+    #[test]
+    fn ignores_components_that_only_look_like_pods() {
+        assert_eq!(pod_uid("kubepods.slice"), None);
+        assert_eq!(pod_uid("kubepods-burstable.slice"), None);
+        assert_eq!(pod_uid("pod1234.slice"), None);
+        assert_eq!(
+            pod_uid("podman-3b5d9b0e_1f0c_4c39_9f2a_0d6f8c3d1e27.scope"),
+            None
+        );
+    }
+}
@@ -0,0 +1,151 @@
+// Checks of every data source nets reads, for `nets doctor`.
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use crate::os::linux::capabilities::{has_capability, CAP_DAC_READ_SEARCH, CAP_SYS_PTRACE};
+use crate::os::linux::sock_diag;
+use crate::os::shared::DataSource;
+
+/// Pids of every process, as directories of /proc.
+fn pids() -> Vec<u32> {
+    match fs::read_dir("/proc") {
+        Ok(entries) => entries
+            .flatten()
+            .filter_map(|entry| entry.file_name().to_str()?.parse().ok())
+            .collect(),
+        Err(_) => Vec::new(),
+    }
+}
+
+/// How many processes have a readable `/proc/<pid>/<name>`. The capabilities it `needs` are
+/// only blamed when nets lacks them, otherwise the first error is shown (e.g. from Yama).
+fn readable_per_process(name: &str, needs: &[(&str, u32)]) -> Result<String, String> {
+    let mut total = 0;
+    let mut readable = 0;
+    let mut error = None;
+
+    for pid in pids() {
+        let result = match name {
+            "fd" => fs::read_dir(format!("/proc/{}/fd", pid)).map(|_| ()),
+            _ => fs::metadata(format!("/proc/{}/{}", pid, name)).map(|_| ()),
+        };
+
+        match result {
+            Ok(()) => readable += 1,
+            // Gone since /proc was listed
+            Err(e) if e.kind() == io::ErrorKind::NotFound => continue,
+            Err(e) => {
+                error.get_or_insert(e);
+            }
+        }
+
+        total += 1;
+    }
+
+    let missing: Vec<&str> = needs
+        .iter()
+        .filter(|(_, capability)| !has_capability(*capability))
+        .map(|(name, _)| *name)
+        .collect();
+
+    match (error, missing.is_empty()) {
+        (None, _) => Ok(format!("readable for all {} processes", total)),
+        (Some(_), false) => Err(format!(
+            "readable for {} of {} processes, the others need {}",
+            readable,
+            total,
+            missing.join(" and ")
+        )),
+        (Some(e), true) => Err(format!(
+            "readable for {} of {} processes, the others fail with: {}",
+            readable, total, e
+        )),
+    }
+}
+
+fn table_rows(path: &str) -> Result<String, String> {
+    fs::read_to_string(path)
+        .map(|contents| format!("{} sockets", contents.lines().count().saturating_sub(1)))
+        .map_err(|e| e.to_string())
+}
+
+fn file_lines(path: &str, what: &str) -> Result<String, String> {
+    fs::read_to_string(path)
+        .map(|contents| format!("{} {}", contents.lines().count(), what))
+        .map_err(|e| e.to_string())
+}
+
+pub fn get_os_data_sources() -> Vec<DataSource> {
+    vec![
+        DataSource {
+            name: "sock_diag tcp",
+            status: sock_diag::inet_sockets(libc::IPPROTO_TCP)
+                .map(|sockets| format!("{} sockets with TCP internals", sockets.len()))
+                .map_err(|e| e.to_string()),
+        },
+        DataSource {
+            name: "sock_diag udp",
+            status: sock_diag::inet_sockets(libc::IPPROTO_UDP)
+                .map(|sockets| format!("{} sockets with their queues", sockets.len()))
+                .map_err(|e| e.to_string()),
+        },
+        DataSource {
+            name: "sock_diag unix",
+            status: sock_diag::unix_sockets()
+                .map(|sockets| format!("{} sockets with their peers", sockets.len()))
+                .map_err(|e| format!("{}, falling back to /proc/net/unix", e)),
+        },
+        DataSource {
+            name: "/proc/net/udp",
+            status: table_rows("/proc/net/udp"),
+        },
+        DataSource {
+            name: "/proc/net/unix",
+            status: table_rows("/proc/net/unix"),
+        },
+        DataSource {
+            name: "/proc/net/raw",
+            status: table_rows("/proc/net/raw"),
+        },
+        DataSource {
+            name: "/proc/net/packet",
+            status: table_rows("/proc/net/packet"),
+        },
+        DataSource {
+            name: "/proc/<pid>/fd",
+            status: readable_per_process(
+                "fd",
+                &[
+                    ("CAP_SYS_PTRACE", CAP_SYS_PTRACE),
+                    ("CAP_DAC_READ_SEARCH", CAP_DAC_READ_SEARCH),
+                ],
+            ),
+        },
+        DataSource {
+            name: "/proc/<pid>/ns/net",
+            status: readable_per_process("ns/net", &[("CAP_SYS_PTRACE", CAP_SYS_PTRACE)]),
+        },
+        DataSource {
+            name: "/proc/<pid>/cgroup",
+            status: readable_per_process("cgroup", &[("CAP_SYS_PTRACE", CAP_SYS_PTRACE)]),
+        },
+        DataSource {
+            name: "/run/netns",
+            status: match Path::new("/run/netns").exists() {
+                true => fs::read_dir("/run/netns")
+                    .map(|entries| format!("{} named namespaces", entries.count()))
+                    .map_err(|e| e.to_string()),
+                false => Ok(String::from("no namespaces named with ip netns")),
+            },
+        },
+        DataSource {
+            name: "/etc/passwd",
+            status: file_lines("/etc/passwd", "users"),
+        },
+        DataSource {
+            name: "/etc/services",
+            status: file_lines("/etc/services", "lines"),
+        },
+    ]
+}
@@ -1 +1,6 @@
+pub mod capabilities;
+pub mod cgroup;
+pub mod diagnostics;
+pub mod netns;
 pub mod proc;
+pub mod sock_diag;
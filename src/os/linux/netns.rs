@@ -0,0 +1,109 @@
+// Network namespaces, found through the processes using them and the names given by
+// `ip netns add`, and entered with setns(2) to list their sockets.
+use std::collections::btree_map::Entry;
+use std::collections::BTreeMap;
+use std::fs::{self, File};
+use std::io;
+use std::os::fd::AsRawFd;
+use std::os::unix::fs::MetadataExt;
+use std::path::{Path, PathBuf};
+
+use crate::os::shared::{get_proc_name, NetNamespace};
+
+/// The inode of the namespace a /proc/<pid>/ns/net link or /run/netns file points to.
+fn namespace_inode(path: &Path) -> Option<u64> {
+    fs::metadata(path).ok().map(|metadata| metadata.ino())
+}
+
+pub fn get_os_net_namespaces() -> Vec<NetNamespace> {
+    let current = namespace_inode(Path::new("/proc/thread-self/ns/net"));
+    // kthreadd is in the initial namespace too, for when init can't be looked at
+    let host = namespace_inode(Path::new("/proc/1/ns/net"))
+        .or_else(|| namespace_inode(Path::new("/proc/2/ns/net")));
+
+    let mut namespaces: BTreeMap<u64, NetNamespace> = BTreeMap::new();
+
+    let namespace = |inode: u64, name: String, path: PathBuf| NetNamespace {
+        inode,
+        name,
+        path,
+        is_current: Some(inode) == current,
+    };
+
+    // Names from `ip netns` are preferred over the processes using the namespace
+    if let Ok(entries) = fs::read_dir("/run/netns") {
+        for entry in entries.flatten() {
+            if let Some(inode) = namespace_inode(&entry.path()) {
+                namespaces.entry(inode).or_insert_with(|| {
+                    namespace(
+                        inode,
+                        entry.file_name().to_string_lossy().to_string(),
+                        entry.path(),
+                    )
+                });
+            }
+        }
+    }
+
+    let mut pids: Vec<u32> = match fs::read_dir("/proc") {
+        Ok(entries) => entries
+            .flatten()
+            .filter_map(|entry| entry.file_name().to_str()?.parse().ok())
+            .collect(),
+        Err(_) => Vec::new(),
+    };
+
+    pids.sort_unstable();
+
+    // Other namespaces are named after the oldest process in them, like the peer column.
+    // Most processes share a namespace that is already known, their names aren't looked up.
+    for pid in pids {
+        let path = PathBuf::from(format!("/proc/{}/ns/net", pid));
+
+        if let Some(Entry::Vacant(entry)) =
+            namespace_inode(&path).map(|inode| namespaces.entry(inode))
+        {
+            let inode = *entry.key();
+            let name = match Some(inode) == host {
+                true => String::from("host"),
+                false => format!("{}/{}", pid, get_proc_name(pid)),
+            };
+
+            entry.insert(namespace(inode, name, path));
+        }
+    }
+
+    let mut namespaces: Vec<NetNamespace> = namespaces.into_values().collect();
+    namespaces.sort_by_key(|namespace| (!namespace.is_current, namespace.name.clone()));
+    namespaces
+}
+
+fn setns(namespace: &File) -> io::Result<()> {
+    match unsafe { libc::setns(namespace.as_raw_fd(), libc::CLONE_NEWNET) } {
+        0 => Ok(()),
+        _ => Err(io::Error::last_os_error()),
+    }
+}
+
+/// Runs `f` in the namespace at `path`, then returns to the current one. /proc/net follows
+/// the namespace of the main thread, so this has to be called from it.
+pub fn in_os_net_namespace<T>(path: &Path, f: impl FnOnce() -> T) -> Result<T, String> {
+    if namespace_inode(path) == namespace_inode(Path::new("/proc/thread-self/ns/net")) {
+        return Ok(f());
+    }
+
+    let own = File::open("/proc/thread-self/ns/net")
+        .map_err(|e| format!("can't open the current network namespace: {}", e))?;
+    let target = File::open(path).map_err(|e| format!("can't open {}: {}", path.display(), e))?;
+
+    setns(&target).map_err(|e| match e.raw_os_error() {
+        Some(libc::EPERM) => String::from("entering network namespaces needs CAP_SYS_ADMIN"),
+        _ => format!("can't enter {}: {}", path.display(), e),
+    })?;
+
+    let result = f();
+
+    setns(&own).map_err(|e| format!("can't return to the network namespace: {}", e))?;
+
+    Ok(result)
+}
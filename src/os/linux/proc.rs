@@ -1,7 +1,20 @@
+use std::collections::{HashMap, HashSet};
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::ops::RangeInclusive;
 use std::os::raw::c_int;
 
 use std::{error::Error, fmt};
 
+use netstat2::TcpState;
+use procfs::process::FDTarget;
+
+use crate::os::linux::capabilities::{has_capability, CAP_NET_ADMIN};
+use crate::os::linux::sock_diag;
+use crate::os::shared::{
+    InetSocketDiag, IpSocketInfo, PacketSocketInfo, Protocol, SocketMemory, SocketQueues,
+    TcpInternals, UnixSocketInfo, UnixSocketState, UnixSocketType,
+};
+
 #[derive(Debug)]
 struct ProcNameFail;
 
@@ -14,10 +27,694 @@ impl fmt::Display for ProcNameFail {
 }
 
 pub fn get_os_proc_name(pid: c_int) -> Result<String, String> {
-    let process = procfs::process::Process::new(pid).unwrap();
+    // The process may have exited since its pid was listed
+    let process = procfs::process::Process::new(pid).map_err(|_| ProcNameFail.to_string())?;
 
     match process.stat() {
         Ok(stat) => Ok(stat.comm),
         _ => Err(ProcNameFail.to_string()),
     }
 }
+
+pub fn get_os_ephemeral_port_range() -> Result<RangeInclusive<u16>, Box<dyn Error>> {
+    let range = std::fs::read_to_string("/proc/sys/net/ipv4/ip_local_port_range")?;
+    let mut bounds = range.split_whitespace().map(|bound| bound.parse::<u16>());
+
+    match (bounds.next(), bounds.next()) {
+        (Some(Ok(low)), Some(Ok(high))) => Ok(low..=high),
+        _ => Err("Unable to parse the local port range".into()),
+    }
+}
+
+/// Maps socket inodes to the pids holding a file descriptor on them.
+pub fn get_os_socket_owners() -> HashMap<u64, Vec<u32>> {
+    let mut owners: HashMap<u64, Vec<u32>> = HashMap::new();
+
+    let processes = match procfs::process::all_processes() {
+        Ok(processes) => processes,
+        Err(_) => return owners,
+    };
+
+    for process in processes.flatten() {
+        let fds = match process.fd() {
+            Ok(fds) => fds,
+            Err(_) => continue,
+        };
+
+        for fd in fds.flatten() {
+            if let FDTarget::Socket(inode) = fd.target {
+                let pids = owners.entry(inode).or_default();
+
+                if !pids.contains(&(process.pid as u32)) {
+                    pids.push(process.pid as u32);
+                }
+            }
+        }
+    }
+
+    owners
+}
+
+pub fn get_os_unix_sockets(
+    owners: &HashMap<u64, Vec<u32>>,
+) -> Result<Vec<UnixSocketInfo>, Box<dyn Error>> {
+    let associated_pids = |inode: u64| owners.get(&inode).cloned().unwrap_or_default();
+
+    // sock_diag knows about peers, /proc/net/unix is the fallback when unix_diag isn't available
+    match sock_diag::unix_sockets() {
+        Ok(sockets) => Ok(sockets
+            .into_iter()
+            .map(|socket| UnixSocketInfo {
+                socket_type: UnixSocketType::from_raw(socket.socket_type),
+                state: match socket.state {
+                    1 => UnixSocketState::Connected,
+                    2 => UnixSocketState::Connecting,
+                    10 => UnixSocketState::Listen,
+                    _ => UnixSocketState::Unconnected,
+                },
+                path: socket.path,
+                inode: socket.inode,
+                peer_inode: socket.peer_inode,
+                associated_pids: associated_pids(socket.inode),
+            })
+            .collect()),
+        // Like the other tables, an unreadable one only leaves its sockets out
+        Err(_) => Ok(std::fs::read_to_string("/proc/net/unix")
+            .unwrap_or_default()
+            .lines()
+            .skip(1)
+            .filter_map(|line| parse_unix_line(line, owners))
+            .collect()),
+    }
+}
+
+/// Listening sockets have __SO_ACCEPTCON in their flags rather than a state of their own.
+const SO_ACCEPTCON: u32 = 0x10000;
+
+/// Parses a line of /proc/net/unix, where the Flags, Type and St fields are in hex.
+/// `Num RefCount Protocol Flags Type St Inode Path`
+fn parse_unix_line(line: &str, owners: &HashMap<u64, Vec<u32>>) -> Option<UnixSocketInfo> {
+    let fields: Vec<&str> = line.split_whitespace().collect();
+    let flags = u32::from_str_radix(fields.get(3)?, 16).ok()?;
+    let inode: u64 = fields.get(6)?.parse().ok()?;
+
+    Some(UnixSocketInfo {
+        socket_type: UnixSocketType::from_raw(u8::from_str_radix(fields.get(4)?, 16).ok()?),
+        state: match u8::from_str_radix(fields.get(5)?, 16).ok()? {
+            _ if flags & SO_ACCEPTCON != 0 => UnixSocketState::Listen,
+            2 => UnixSocketState::Connecting,
+            3 => UnixSocketState::Connected,
+            4 => UnixSocketState::Disconnecting,
+            _ => UnixSocketState::Unconnected,
+        },
+        path: fields
+            .get(7..)
+            .filter(|path| !path.is_empty())
+            .map(|path| path.join(" ")),
+        inode,
+        peer_inode: None,
+        associated_pids: owners.get(&inode).cloned().unwrap_or_default(),
+    })
+}
+
+/// Parses an address from /proc/net, e.g. `0100007F:0035`.
+fn parse_proc_address(address: &str) -> Option<SocketAddr> {
+    let (ip, port) = address.split_once(':')?;
+    let port = u16::from_str_radix(port, 16).ok()?;
+
+    // Addresses are printed as native endian 32-bit words of the network order bytes
+    let mut bytes = Vec::with_capacity(16);
+    for i in (0..ip.len()).step_by(8) {
+        let word = u32::from_str_radix(ip.get(i..i + 8)?, 16).ok()?;
+        bytes.extend_from_slice(&word.to_ne_bytes());
+    }
+
+    let ip = match bytes.len() {
+        4 => IpAddr::V4(Ipv4Addr::from(<[u8; 4]>::try_from(bytes).ok()?)),
+        16 => IpAddr::V6(Ipv6Addr::from(<[u8; 16]>::try_from(bytes).ok()?)),
+        _ => return None,
+    };
+
+    Some(SocketAddr::new(ip, port))
+}
+
+/// Parses a line of a table shaped like /proc/net/udp, such as /proc/net/raw or
+/// /proc/net/icmp6.
+fn parse_ip_socket_line(
+    line: &str,
+    protocol: Protocol,
+    owners: &HashMap<u64, Vec<u32>>,
+) -> Option<IpSocketInfo> {
+    let fields: Vec<&str> = line.split_whitespace().collect();
+    let local = parse_proc_address(fields.get(1)?)?;
+    let remote = parse_proc_address(fields.get(2)?)?;
+    let inode: u64 = fields.get(9)?.parse().ok()?;
+
+    Some(IpSocketInfo {
+        protocol,
+        local_addr: local.ip(),
+        local_port: local.port(),
+        remote_addr: remote.ip(),
+        remote_port: remote.port(),
+        state: match u8::from_str_radix(fields.get(3)?, 16).ok()? {
+            1 => TcpState::Established,
+            _ => TcpState::Closed,
+        },
+        uid: fields.get(7)?.parse().ok()?,
+        inode,
+        associated_pids: owners.get(&inode).cloned().unwrap_or_default(),
+    })
+}
+
+fn read_ip_socket_table(
+    path: &str,
+    protocol: Protocol,
+    owners: &HashMap<u64, Vec<u32>>,
+) -> Vec<IpSocketInfo> {
+    let table = match std::fs::read_to_string(path) {
+        Ok(table) => table,
+        Err(_) => return Vec::new(),
+    };
+
+    table
+        .lines()
+        .skip(1)
+        .filter_map(|line| parse_ip_socket_line(line, protocol, owners))
+        .collect()
+}
+
+/// The addresses at the start of `fields`, the primary one of an association marked with a '*'.
+fn sctp_addresses(fields: &[&str]) -> Vec<IpAddr> {
+    fields
+        .iter()
+        .map_while(|field| field.trim_start_matches('*').parse::<IpAddr>().ok())
+        .collect()
+}
+
+/// Parses a line of /proc/net/sctp/eps, one listening socket per local address.
+/// `ENDPT SOCK STY SST HBKT LPORT UID INODE LADDRS`
+fn parse_sctp_endpoint_line(line: &str, owners: &HashMap<u64, Vec<u32>>) -> Vec<IpSocketInfo> {
+    let fields: Vec<&str> = line.split_whitespace().collect();
+
+    let (port, uid, inode) = match (fields.get(5), fields.get(6), fields.get(7)) {
+        (Some(port), Some(uid), Some(inode)) => match (port.parse(), uid.parse(), inode.parse()) {
+            (Ok(port), Ok(uid), Ok(inode)) => (port, uid, inode),
+            _ => return Vec::new(),
+        },
+        _ => return Vec::new(),
+    };
+
+    sctp_addresses(fields.get(8..).unwrap_or_default())
+        .into_iter()
+        .map(|local_addr| IpSocketInfo {
+            protocol: Protocol::SCTP,
+            local_addr,
+            local_port: port,
+            remote_addr: match local_addr {
+                IpAddr::V4(_) => IpAddr::V4(Ipv4Addr::UNSPECIFIED),
+                IpAddr::V6(_) => IpAddr::V6(Ipv6Addr::UNSPECIFIED),
+            },
+            remote_port: 0,
+            state: TcpState::Listen,
+            uid,
+            inode,
+            associated_pids: owners.get(&inode).cloned().unwrap_or_default(),
+        })
+        .collect()
+}
+
+/// Parses a line of /proc/net/sctp/assocs, `ASSOC SOCK STY SST ST HBKT ASSOC-ID TX_QUEUE
+/// RX_QUEUE UID INODE LPORT RPORT LADDRS <-> RADDRS ...`
+fn parse_sctp_association_line(
+    line: &str,
+    owners: &HashMap<u64, Vec<u32>>,
+) -> Option<IpSocketInfo> {
+    let fields: Vec<&str> = line.split_whitespace().collect();
+    let uid = fields.get(9)?.parse().ok()?;
+    let inode = fields.get(10)?.parse().ok()?;
+    let local_port = fields.get(11)?.parse().ok()?;
+    let remote_port = fields.get(12)?.parse().ok()?;
+
+    let separator = fields.iter().position(|field| *field == "<->")?;
+    let local_addr = *sctp_addresses(fields.get(13..separator)?).first()?;
+
+    // The primary path of the association is marked with a '*'
+    let remote_fields = &fields[separator + 1..];
+    let remote_addr = remote_fields
+        .iter()
+        .find_map(|field| field.strip_prefix('*')?.parse::<IpAddr>().ok())
+        .or_else(|| sctp_addresses(remote_fields).first().copied())?;
+
+    Some(IpSocketInfo {
+        protocol: Protocol::SCTP,
+        local_addr,
+        local_port,
+        remote_addr,
+        remote_port,
+        state: TcpState::Established,
+        uid,
+        inode,
+        associated_pids: owners.get(&inode).cloned().unwrap_or_default(),
+    })
+}
+
+/// SCTP endpoints and associations, only present once the sctp module is loaded.
+fn read_sctp_sockets(owners: &HashMap<u64, Vec<u32>>) -> Vec<IpSocketInfo> {
+    let mut sockets = Vec::new();
+
+    if let Ok(endpoints) = std::fs::read_to_string("/proc/net/sctp/eps") {
+        for line in endpoints.lines().skip(1) {
+            sockets.extend(parse_sctp_endpoint_line(line, owners));
+        }
+    }
+
+    if let Ok(associations) = std::fs::read_to_string("/proc/net/sctp/assocs") {
+        for line in associations.lines().skip(1) {
+            sockets.extend(parse_sctp_association_line(line, owners));
+        }
+    }
+
+    sockets
+}
+
+pub fn get_os_ip_sockets(
+    protocol: Protocol,
+    owners: &HashMap<u64, Vec<u32>>,
+) -> Result<Vec<IpSocketInfo>, Box<dyn Error>> {
+    let mut sockets = Vec::new();
+
+    if protocol.contains(Protocol::RAW) {
+        sockets.extend(read_ip_socket_table("/proc/net/raw", Protocol::RAW, owners));
+        sockets.extend(read_ip_socket_table(
+            "/proc/net/raw6",
+            Protocol::RAW,
+            owners,
+        ));
+    }
+
+    if protocol.contains(Protocol::ICMP) {
+        sockets.extend(read_ip_socket_table(
+            "/proc/net/icmp",
+            Protocol::ICMP,
+            owners,
+        ));
+        sockets.extend(read_ip_socket_table(
+            "/proc/net/icmp6",
+            Protocol::ICMP,
+            owners,
+        ));
+    }
+
+    if protocol.contains(Protocol::SCTP) {
+        sockets.extend(read_sctp_sockets(owners));
+    }
+
+    Ok(sockets)
+}
+
+fn get_interface_name(index: u32) -> Option<String> {
+    let mut buffer = [0 as libc::c_char; libc::IF_NAMESIZE];
+
+    let name = unsafe { libc::if_indextoname(index, buffer.as_mut_ptr()) };
+
+    if name.is_null() {
+        return None;
+    }
+
+    let name = unsafe { std::ffi::CStr::from_ptr(name) };
+    Some(name.to_string_lossy().to_string())
+}
+
+pub fn get_os_packet_sockets(
+    owners: &HashMap<u64, Vec<u32>>,
+) -> Result<Vec<PacketSocketInfo>, Box<dyn Error>> {
+    let table = match std::fs::read_to_string("/proc/net/packet") {
+        Ok(table) => table,
+        Err(_) => return Ok(Vec::new()),
+    };
+
+    Ok(table
+        .lines()
+        .skip(1)
+        .filter_map(|line| parse_packet_line(line, owners))
+        .collect())
+}
+
+/// Parses a line of /proc/net/packet.
+/// `sk RefCnt Type Proto Iface R Rmem User Inode`
+fn parse_packet_line(line: &str, owners: &HashMap<u64, Vec<u32>>) -> Option<PacketSocketInfo> {
+    let fields: Vec<&str> = line.split_whitespace().collect();
+    let inode: u64 = fields.get(8)?.parse().ok()?;
+    let interface: u32 = fields.get(4)?.parse().ok()?;
+
+    Some(PacketSocketInfo {
+        socket_type: fields.get(2)?.parse().ok()?,
+        ether_type: u16::from_str_radix(fields.get(3)?, 16).ok()?,
+        interface: match interface {
+            0 => None,
+            index => get_interface_name(index),
+        },
+        uid: fields.get(7)?.parse().ok()?,
+        inode,
+        associated_pids: owners.get(&inode).cloned().unwrap_or_default(),
+    })
+}
+
+/// Queues of TCP and UDP sockets and the internals of TCP ones from inet_diag, by inode.
+pub fn get_os_inet_diag(
+    protocol: Protocol,
+) -> Result<HashMap<u64, InetSocketDiag>, Box<dyn Error>> {
+    let mut sockets = Vec::new();
+
+    for (flag, ip_protocol) in [
+        (Protocol::TCP, libc::IPPROTO_TCP),
+        (Protocol::UDP, libc::IPPROTO_UDP),
+    ] {
+        if protocol.contains(flag) {
+            sockets.extend(sock_diag::inet_sockets(ip_protocol)?);
+        }
+    }
+
+    Ok(sockets
+        .into_iter()
+        .filter(|socket| socket.inode != 0)
+        .map(|socket| {
+            let queues = SocketQueues {
+                recv_queue: socket.recv_queue,
+                send_queue: socket.send_queue,
+                memory: socket.memory.map(|memory| SocketMemory {
+                    rmem_alloc: memory.rmem_alloc,
+                    rcvbuf: memory.rcvbuf,
+                    wmem_alloc: memory.wmem_alloc,
+                    sndbuf: memory.sndbuf,
+                    drops: memory.drops,
+                }),
+            };
+
+            let tcp_internals = socket.info.map(|info| TcpInternals {
+                rtt_us: info.rtt,
+                rttvar_us: info.rttvar,
+                cwnd: info.snd_cwnd,
+                ssthresh: info.snd_ssthresh,
+                retransmits: u32::from(info.retransmits),
+                total_retransmits: info.total_retrans,
+                lost: info.lost,
+                unacked: info.unacked,
+                mss: info.snd_mss,
+                bytes_acked: info.bytes_acked,
+                bytes_received: info.bytes_received,
+                congestion: socket.congestion,
+            });
+
+            (
+                socket.inode,
+                InetSocketDiag {
+                    queues,
+                    tcp_internals,
+                },
+            )
+        })
+        .collect())
+}
+
+/// Inodes of the IPv6 sockets that don't accept IPv4, `None` when sock_diag can't tell.
+pub fn get_os_ipv6_only_sockets() -> Option<HashSet<u64>> {
+    sock_diag::ipv6_only_sockets().ok()
+}
+
+/// Why connections can't be closed, if they can't.
+pub fn check_os_close_tcp_connection() -> Result<(), String> {
+    match has_capability(CAP_NET_ADMIN) {
+        true => Ok(()),
+        false => Err(String::from(
+            "closing connections needs CAP_NET_ADMIN, run nets as root or give it the capability \
+             with setcap cap_net_admin+ep",
+        )),
+    }
+}
+
+pub fn close_os_tcp_connection(local: SocketAddr, remote: SocketAddr) -> Result<(), String> {
+    check_os_close_tcp_connection()?;
+
+    sock_diag::destroy_tcp_socket(local, remote).map_err(|e| match e.raw_os_error() {
+        Some(libc::EOPNOTSUPP) => {
+            String::from("the kernel can't close connections, it needs CONFIG_INET_DIAG_DESTROY")
+        }
+        Some(libc::ENOENT) => String::from("the connection is already gone"),
+        Some(libc::EPERM) => String::from("permission denied (EPERM), CAP_NET_ADMIN is needed"),
+        _ => format!("SOCK_DESTROY failed: {}", e),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // The tables print addresses as native endian words
+    #[cfg(target_endian = "little")]
+    #[test]
+    fn parses_proc_addresses() {
+        assert_eq!(
+            parse_proc_address("0100007F:0035"),
+            Some("127.0.0.1:53".parse().unwrap())
+        );
+        assert_eq!(
+            parse_proc_address("00000000000000000000000001000000:003A"),
+            Some("[::1]:58".parse().unwrap())
+        );
+        assert_eq!(
+            parse_proc_address("0000000000000000FFFF00000100007F:1F90"),
+            Some("[::ffff:127.0.0.1]:8080".parse().unwrap())
+        );
+    }
+
+    #[test]
+    fn rejects_malformed_proc_addresses() {
+        assert_eq!(parse_proc_address("0100007F"), None);
+        assert_eq!(parse_proc_address("0100007F:ZZ"), None);
+        assert_eq!(parse_proc_address("0100:0035"), None);
+        assert_eq!(parse_proc_address("0100007F00:0035"), None);
+    }
+
+    #[cfg(target_endian = "little")]
+    #[test]
+    fn parses_raw_and_icmp_lines() {
+        let owners = HashMap::from([(12345, vec![812])]);
+
+        assert_eq!(
+            parse_ip_socket_line(
+                "   1: 00000000:0001 00000000:0000 07 00000000:00000000 00:00000000 00000000     0        0 12345 2 0000000000000000 0",
+                Protocol::RAW,
+                &owners,
+            ),
+            Some(IpSocketInfo {
+                protocol: Protocol::RAW,
+                local_addr: "0.0.0.0".parse().unwrap(),
+                local_port: 1,
+                remote_addr: "0.0.0.0".parse().unwrap(),
+                remote_port: 0,
+                state: TcpState::Closed,
+                uid: 0,
+                inode: 12345,
+                associated_pids: vec![812],
+            })
+        );
+
+        let ping = parse_ip_socket_line(
+            "  52: 0100007F:0007 0100007F:0000 01 00000000:00000000 00:00000000 00000000  1000        0 999 2 0000000000000000 0",
+            Protocol::ICMP,
+            &owners,
+        )
+        .unwrap();
+
+        assert_eq!(ping.state, TcpState::Established);
+        assert_eq!(ping.uid, 1000);
+        assert!(ping.associated_pids.is_empty());
+
+        assert_eq!(
+            parse_ip_socket_line(
+                "  sl  local_address rem_address   st",
+                Protocol::RAW,
+                &owners
+            ),
+            None
+        );
+        assert_eq!(
+            parse_ip_socket_line(
+                "   1: 00000000:0001 00000000:0000 07",
+                Protocol::RAW,
+                &owners
+            ),
+            None
+        );
+    }
+
+    #[test]
+    fn parses_sctp_endpoints() {
+        let owners = HashMap::new();
+
+        let endpoints = parse_sctp_endpoint_line(
+            "ffff88017e0a0200 ffff880299f7fa00 2   10  29   5000      0 17880 10.0.0.1 127.0.0.1",
+            &owners,
+        );
+
+        assert_eq!(
+            endpoints
+                .iter()
+                .map(|socket| (socket.local_addr, socket.local_port, socket.state))
+                .collect::<Vec<_>>(),
+            [
+                ("10.0.0.1".parse().unwrap(), 5000, TcpState::Listen),
+                ("127.0.0.1".parse().unwrap(), 5000, TcpState::Listen),
+            ]
+        );
+        assert_eq!(endpoints[0].inode, 17880);
+        assert!(
+            parse_sctp_endpoint_line("ffff88017e0a0200 ffff880299f7fa00 2", &owners).is_empty()
+        );
+    }
+
+    #[test]
+    fn parses_sctp_associations() {
+        let owners = HashMap::new();
+
+        let association = parse_sctp_association_line(
+            "ffff8800ac4c6000 ffff88007d9c4800 2   1   3  8874 2        0        0       0 23151 5000  39852  10.0.0.1 127.0.0.1 <-> 10.0.0.2 *127.0.0.2 \t    7500    10    10    2    0    0        0        1        0   212992   212992",
+            &owners,
+        )
+        .unwrap();
+
+        assert_eq!(
+            association.local_addr,
+            "10.0.0.1".parse::<IpAddr>().unwrap()
+        );
+        assert_eq!(association.local_port, 5000);
+        // The primary path rather than the first address
+        assert_eq!(
+            association.remote_addr,
+            "127.0.0.2".parse::<IpAddr>().unwrap()
+        );
+        assert_eq!(association.remote_port, 39852);
+        assert_eq!(association.inode, 23151);
+    }
+
+    #[test]
+    fn skips_malformed_sctp_associations() {
+        let owners = HashMap::new();
+
+        // The separator before the addresses are due
+        assert_eq!(
+            parse_sctp_association_line(
+                "ffff8800ac4c6000 <-> 2 1 3 8874 2 0 0 0 23151 5000 39852 127.0.0.1",
+                &owners
+            ),
+            None
+        );
+        assert_eq!(
+            parse_sctp_association_line(
+                "ffff8800ac4c6000 ffff88007d9c4800 2 1 3 8874 2 0 0 0 23151 5000 39852 127.0.0.1",
+                &owners
+            ),
+            None
+        );
+        assert_eq!(
+            parse_sctp_association_line(
+                "ffff8800ac4c6000 ffff88007d9c4800 2 1 3 8874 2 0 0 0 23151 5000 39852 <-> 127.0.0.1",
+                &owners
+            ),
+            None
+        );
+    }
+
+    #[test]
+    fn parses_packet_lines() {
+        let owners = HashMap::from([(21838, vec![640])]);
+
+        assert_eq!(
+            parse_packet_line(
+                "ffff8d6c8a2a8000 3      3    0003   0     1 0      0      21838",
+                &owners
+            ),
+            Some(PacketSocketInfo {
+                socket_type: 3,
+                ether_type: 0x0003,
+                interface: None,
+                uid: 0,
+                inode: 21838,
+                associated_pids: vec![640],
+            })
+        );
+        assert_eq!(
+            parse_packet_line(
+                "sk       RefCnt Type Proto  Iface R Rmem   User   Inode",
+                &owners
+            ),
+            None
+        );
+    }
+
+    #[test]
+    fn parses_unix_sockets() {
+        let owners = HashMap::from([(70957, vec![812])]);
+
+        assert_eq!(
+            parse_unix_line(
+                "00000000ed31825d: 00000002 00000000 00010000 0001 01 70957 /run/app.sock",
+                &owners
+            ),
+            Some(UnixSocketInfo {
+                socket_type: UnixSocketType::Stream,
+                state: UnixSocketState::Listen,
+                path: Some(String::from("/run/app.sock")),
+                inode: 70957,
+                peer_inode: None,
+                associated_pids: vec![812],
+            })
+        );
+
+        let connected = parse_unix_line(
+            "0000000085e93370: 00000003 00000000 00000000 0002 03   930",
+            &owners,
+        )
+        .unwrap();
+
+        assert_eq!(connected.socket_type, UnixSocketType::Datagram);
+        assert_eq!(connected.state, UnixSocketState::Connected);
+        assert_eq!(connected.path, None);
+        assert!(connected.associated_pids.is_empty());
+    }
+
+    #[test]
+    fn keeps_abstract_and_spaced_unix_paths() {
+        let owners = HashMap::new();
+
+        let path = |line| parse_unix_line(line, &owners).and_then(|socket| socket.path);
+
+        assert_eq!(
+            path("0000000000000000: 00000002 00000000 00000000 0001 01 1 @/tmp/.X11-unix/X0"),
+            Some(String::from("@/tmp/.X11-unix/X0"))
+        );
+        assert_eq!(
+            path("0000000000000000: 00000002 00000000 00000000 0001 01 1 /tmp/my app.sock"),
+            Some(String::from("/tmp/my app.sock"))
+        );
+    }
+
+    #[test]
+    fn skips_malformed_unix_lines() {
+        let owners = HashMap::new();
+
+        assert_eq!(parse_unix_line("", &owners), None);
+        assert_eq!(
+            parse_unix_line("0000000000000000: 00000002 00000000 zz 0001 01 1", &owners),
+            None
+        );
+        assert_eq!(
+            parse_unix_line(
+                "0000000000000000: 00000002 00000000 00000000 0001 01",
+                &owners
+            ),
+            None
+        );
+    }
+}
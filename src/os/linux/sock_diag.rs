@@ -0,0 +1,629 @@
+// A minimal sock_diag(7) netlink client, covering the parts of the kernel socket
+// information that aren't available from netstat2 or /proc/net.
+use std::collections::HashSet;
+use std::io;
+use std::mem::size_of;
+use std::net::{IpAddr, SocketAddr};
+use std::os::fd::{AsRawFd, FromRawFd, OwnedFd};
+
+const SOCK_DIAG_BY_FAMILY: u16 = 20;
+const SOCK_DESTROY: u16 = 21;
+
+const NLMSG_ERROR: u16 = 2;
+const NLMSG_DONE: u16 = 3;
+
+const UDIAG_SHOW_NAME: u32 = 0x01;
+const UDIAG_SHOW_PEER: u32 = 0x04;
+
+const UNIX_DIAG_NAME: u16 = 0;
+const UNIX_DIAG_PEER: u16 = 2;
+
+const INET_DIAG_INFO: u16 = 2;
+const INET_DIAG_CONG: u16 = 4;
+const INET_DIAG_SKMEMINFO: u16 = 7;
+const INET_DIAG_SKV6ONLY: u16 = 11;
+
+const TCP_LISTEN: u32 = 10;
+
+#[repr(C)]
+#[derive(Clone, Copy)]
+struct NlMsgHdr {
+    len: u32,
+    kind: u16,
+    flags: u16,
+    seq: u32,
+    pid: u32,
+}
+
+#[repr(C)]
+#[derive(Clone, Copy)]
+struct UnixDiagReq {
+    family: u8,
+    protocol: u8,
+    pad: u16,
+    states: u32,
+    inode: u32,
+    show: u32,
+    cookie: [u32; 2],
+}
+
+#[repr(C)]
+#[derive(Clone, Copy)]
+struct UnixDiagMsg {
+    family: u8,
+    kind: u8,
+    state: u8,
+    pad: u8,
+    inode: u32,
+    cookie: [u32; 2],
+}
+
+#[repr(C)]
+#[derive(Clone, Copy)]
+struct InetDiagSockId {
+    source_port: [u8; 2],
+    destination_port: [u8; 2],
+    source: [u8; 16],
+    destination: [u8; 16],
+    interface: u32,
+    cookie: [u32; 2],
+}
+
+#[repr(C)]
+#[derive(Clone, Copy)]
+struct InetDiagReqV2 {
+    family: u8,
+    protocol: u8,
+    extensions: u8,
+    pad: u8,
+    states: u32,
+    id: InetDiagSockId,
+}
+
+#[repr(C)]
+#[derive(Clone, Copy)]
+struct InetDiagMsg {
+    family: u8,
+    state: u8,
+    timer: u8,
+    retrans: u8,
+    id: InetDiagSockId,
+    expires: u32,
+    rqueue: u32,
+    wqueue: u32,
+    uid: u32,
+    inode: u32,
+}
+
+/// The fields of `struct tcp_info` nets shows.
+pub struct TcpInfo {
+    pub retransmits: u8,
+    pub snd_mss: u32,
+    pub unacked: u32,
+    pub lost: u32,
+    pub rtt: u32,
+    pub rttvar: u32,
+    pub snd_ssthresh: u32,
+    pub snd_cwnd: u32,
+    pub total_retrans: u32,
+    /// Only reported by kernels since 4.1 and 4.2.
+    pub bytes_acked: Option<u64>,
+    pub bytes_received: Option<u64>,
+}
+
+impl TcpInfo {
+    /// Reads the fields by their offset in `struct tcp_info`.
+    fn parse(bytes: &[u8]) -> Option<TcpInfo> {
+        let u32_at = |offset: usize| read_u32(bytes.get(offset..)?);
+        let u64_at = |offset: usize| read_u64(bytes.get(offset..)?);
+
+        Some(TcpInfo {
+            retransmits: *bytes.get(2)?,
+            snd_mss: u32_at(16)?,
+            unacked: u32_at(24)?,
+            lost: u32_at(32)?,
+            rtt: u32_at(68)?,
+            rttvar: u32_at(72)?,
+            snd_ssthresh: u32_at(76)?,
+            snd_cwnd: u32_at(80)?,
+            total_retrans: u32_at(100)?,
+            bytes_acked: u64_at(120),
+            bytes_received: u64_at(128),
+        })
+    }
+}
+
+/// Socket memory accounting, the `SK_MEMINFO_*` values in linux/sock_diag.h.
+pub struct SkMemInfo {
+    pub rmem_alloc: u32,
+    pub rcvbuf: u32,
+    pub wmem_alloc: u32,
+    pub sndbuf: u32,
+    pub drops: u32,
+}
+
+impl SkMemInfo {
+    fn parse(bytes: &[u8]) -> Option<SkMemInfo> {
+        let u32_at = |index: usize| read_u32(bytes.get(index * 4..)?);
+
+        Some(SkMemInfo {
+            rmem_alloc: u32_at(0)?,
+            rcvbuf: u32_at(1)?,
+            wmem_alloc: u32_at(2)?,
+            sndbuf: u32_at(3)?,
+            drops: u32_at(8).unwrap_or(0),
+        })
+    }
+}
+
+/// A TCP or UDP socket as reported by inet_diag.
+pub struct InetDiag {
+    pub inode: u64,
+    /// For TCP listeners these are the accept queue length and the configured backlog.
+    pub recv_queue: u32,
+    pub send_queue: u32,
+    pub info: Option<TcpInfo>,
+    pub congestion: Option<String>,
+    pub memory: Option<SkMemInfo>,
+}
+
+/// A Unix domain socket as reported by the kernel.
+#[derive(Debug, PartialEq, Eq)]
+pub struct UnixDiag {
+    pub inode: u64,
+    pub socket_type: u8,
+    pub state: u8,
+    pub path: Option<String>,
+    pub peer_inode: Option<u64>,
+}
+
+fn align(len: usize) -> usize {
+    (len + 3) & !3
+}
+
+fn as_bytes<T: Copy>(value: &T) -> &[u8] {
+    unsafe { std::slice::from_raw_parts(value as *const T as *const u8, size_of::<T>()) }
+}
+
+fn read_struct<T: Copy>(bytes: &[u8]) -> Option<T> {
+    if bytes.len() < size_of::<T>() {
+        return None;
+    }
+
+    Some(unsafe { std::ptr::read_unaligned(bytes.as_ptr() as *const T) })
+}
+
+/// Splits the route attributes following a message header into (type, payload) pairs.
+fn attributes(mut bytes: &[u8]) -> Vec<(u16, &[u8])> {
+    let mut attributes = Vec::new();
+
+    while bytes.len() >= 4 {
+        let len = u16::from_ne_bytes([bytes[0], bytes[1]]) as usize;
+        let kind = u16::from_ne_bytes([bytes[2], bytes[3]]);
+
+        if len < 4 || len > bytes.len() {
+            break;
+        }
+
+        attributes.push((kind, &bytes[4..len]));
+        bytes = &bytes[align(len).min(bytes.len())..];
+    }
+
+    attributes
+}
+
+fn read_u32(bytes: &[u8]) -> Option<u32> {
+    Some(u32::from_ne_bytes(bytes.get(..4)?.try_into().ok()?))
+}
+
+fn read_u64(bytes: &[u8]) -> Option<u64> {
+    Some(u64::from_ne_bytes(bytes.get(..8)?.try_into().ok()?))
+}
+
+/// Opens a sock_diag netlink socket and sends it a request.
+fn send_request<T: Copy>(kind: u16, flags: u16, request: &T) -> io::Result<OwnedFd> {
+    let fd = unsafe {
+        libc::socket(
+            libc::AF_NETLINK,
+            libc::SOCK_DGRAM | libc::SOCK_CLOEXEC,
+            libc::NETLINK_SOCK_DIAG,
+        )
+    };
+
+    if fd < 0 {
+        return Err(io::Error::last_os_error());
+    }
+
+    let socket = unsafe { OwnedFd::from_raw_fd(fd) };
+
+    let header = NlMsgHdr {
+        len: (size_of::<NlMsgHdr>() + size_of::<T>()) as u32,
+        kind,
+        flags,
+        seq: 1,
+        pid: 0,
+    };
+
+    let mut message = as_bytes(&header).to_vec();
+    message.extend_from_slice(as_bytes(request));
+
+    let sent = unsafe {
+        libc::send(
+            socket.as_raw_fd(),
+            message.as_ptr() as *const libc::c_void,
+            message.len(),
+            0,
+        )
+    };
+
+    if sent < 0 {
+        return Err(io::Error::last_os_error());
+    }
+
+    Ok(socket)
+}
+
+/// Sends a SOCK_DIAG_BY_FAMILY dump request and returns the payload of every reply.
+fn dump<T: Copy>(request: &T) -> io::Result<Vec<Vec<u8>>> {
+    let socket = send_request(
+        SOCK_DIAG_BY_FAMILY,
+        (libc::NLM_F_REQUEST | libc::NLM_F_DUMP) as u16,
+        request,
+    )?;
+
+    let mut replies = Vec::new();
+    let mut buffer = vec![0u8; 32768];
+
+    loop {
+        let received = unsafe {
+            libc::recv(
+                socket.as_raw_fd(),
+                buffer.as_mut_ptr() as *mut libc::c_void,
+                buffer.len(),
+                0,
+            )
+        };
+
+        if received < 0 {
+            return Err(io::Error::last_os_error());
+        }
+
+        let mut bytes = &buffer[..received as usize];
+
+        while let Some(header) = read_struct::<NlMsgHdr>(bytes) {
+            let len = header.len as usize;
+
+            if len < size_of::<NlMsgHdr>() || len > bytes.len() {
+                break;
+            }
+
+            let payload = &bytes[size_of::<NlMsgHdr>()..len];
+
+            match header.kind {
+                NLMSG_DONE => return Ok(replies),
+                NLMSG_ERROR => {
+                    let error = read_u32(payload).unwrap_or(0) as i32;
+
+                    if error != 0 {
+                        return Err(io::Error::from_raw_os_error(-error));
+                    }
+                }
+                _ => replies.push(payload.to_vec()),
+            }
+
+            bytes = &bytes[align(len).min(bytes.len())..];
+        }
+    }
+}
+
+pub fn unix_sockets() -> io::Result<Vec<UnixDiag>> {
+    let request = UnixDiagReq {
+        family: libc::AF_UNIX as u8,
+        protocol: 0,
+        pad: 0,
+        states: u32::MAX,
+        inode: 0,
+        show: UDIAG_SHOW_NAME | UDIAG_SHOW_PEER,
+        cookie: [u32::MAX; 2],
+    };
+
+    Ok(dump(&request)?
+        .iter()
+        .filter_map(|payload| parse_unix_message(payload))
+        .collect())
+}
+
+/// A unix_diag_msg followed by its name and peer attributes.
+fn parse_unix_message(payload: &[u8]) -> Option<UnixDiag> {
+    let message = read_struct::<UnixDiagMsg>(payload)?;
+
+    let mut socket = UnixDiag {
+        inode: u64::from(message.inode),
+        socket_type: message.kind,
+        state: message.state,
+        path: None,
+        peer_inode: None,
+    };
+
+    for (kind, value) in attributes(&payload[size_of::<UnixDiagMsg>()..]) {
+        match kind {
+            UNIX_DIAG_NAME => socket.path = Some(unix_path(value)),
+            UNIX_DIAG_PEER => socket.peer_inode = read_u32(value).map(u64::from),
+            _ => {}
+        }
+    }
+
+    Some(socket)
+}
+
+/// Abstract socket names start with a NUL byte and are shown with a leading '@' like ss does.
+fn unix_path(bytes: &[u8]) -> String {
+    match bytes.split_first() {
+        Some((0, name)) => format!("@{}", String::from_utf8_lossy(name)),
+        _ => String::from_utf8_lossy(bytes)
+            .trim_end_matches('\0')
+            .to_string(),
+    }
+}
+
+/// Sockets of `protocol`, `libc::IPPROTO_TCP` or `libc::IPPROTO_UDP`. Only TCP sockets
+/// have `info` and `congestion`.
+pub fn inet_sockets(protocol: i32) -> io::Result<Vec<InetDiag>> {
+    let mut sockets = Vec::new();
+
+    for family in [libc::AF_INET, libc::AF_INET6] {
+        let request = InetDiagReqV2 {
+            family: family as u8,
+            protocol: protocol as u8,
+            extensions: (1 << (INET_DIAG_INFO - 1))
+                | (1 << (INET_DIAG_CONG - 1))
+                | (1 << (INET_DIAG_SKMEMINFO - 1)),
+            pad: 0,
+            states: u32::MAX,
+            id: InetDiagSockId {
+                source_port: [0; 2],
+                destination_port: [0; 2],
+                source: [0; 16],
+                destination: [0; 16],
+                interface: 0,
+                cookie: [u32::MAX; 2],
+            },
+        };
+
+        for payload in dump(&request)? {
+            let message = match read_struct::<InetDiagMsg>(&payload) {
+                Some(message) => message,
+                None => continue,
+            };
+
+            let mut socket = InetDiag {
+                inode: u64::from(message.inode),
+                recv_queue: message.rqueue,
+                send_queue: message.wqueue,
+                info: None,
+                congestion: None,
+                memory: None,
+            };
+
+            for (kind, value) in attributes(&payload[size_of::<InetDiagMsg>()..]) {
+                match kind {
+                    INET_DIAG_INFO => socket.info = TcpInfo::parse(value),
+                    INET_DIAG_CONG => {
+                        socket.congestion = Some(
+                            String::from_utf8_lossy(value)
+                                .trim_end_matches('\0')
+                                .to_string(),
+                        )
+                    }
+                    INET_DIAG_SKMEMINFO => socket.memory = SkMemInfo::parse(value),
+                    _ => {}
+                }
+            }
+
+            sockets.push(socket);
+        }
+    }
+
+    Ok(sockets)
+}
+
+/// Inodes of the IPv6 TCP listeners and UDP sockets that have IPV6_V6ONLY set, the others
+/// also accept IPv4 through mapped addresses.
+pub fn ipv6_only_sockets() -> io::Result<HashSet<u64>> {
+    let mut inodes = HashSet::new();
+
+    for (protocol, states) in [
+        (libc::IPPROTO_TCP, 1 << TCP_LISTEN),
+        (libc::IPPROTO_UDP, u32::MAX),
+    ] {
+        let request = InetDiagReqV2 {
+            family: libc::AF_INET6 as u8,
+            protocol: protocol as u8,
+            extensions: 0,
+            pad: 0,
+            states,
+            id: InetDiagSockId {
+                source_port: [0; 2],
+                destination_port: [0; 2],
+                source: [0; 16],
+                destination: [0; 16],
+                interface: 0,
+                cookie: [u32::MAX; 2],
+            },
+        };
+
+        for payload in dump(&request)? {
+            let message = match read_struct::<InetDiagMsg>(&payload) {
+                Some(message) => message,
+                None => continue,
+            };
+
+            // Always sent for IPv6 sockets, no extension needs to be asked for
+            let v6only = attributes(&payload[size_of::<InetDiagMsg>()..])
+                .into_iter()
+                .any(|(kind, value)| kind == INET_DIAG_SKV6ONLY && value.first() == Some(&1));
+
+            if v6only {
+                inodes.insert(u64::from(message.inode));
+            }
+        }
+    }
+
+    Ok(inodes)
+}
+
+/// Closes a TCP connection with SOCK_DESTROY, which needs CAP_NET_ADMIN and a kernel
+/// built with CONFIG_INET_DIAG_DESTROY. The application sees ECONNABORTED.
+pub fn destroy_tcp_socket(local: SocketAddr, remote: SocketAddr) -> io::Result<()> {
+    let (family, source, destination) = match (local.ip(), remote.ip()) {
+        (IpAddr::V4(source), IpAddr::V4(destination)) => (
+            libc::AF_INET,
+            padded(&source.octets()),
+            padded(&destination.octets()),
+        ),
+        (IpAddr::V6(source), IpAddr::V6(destination)) => {
+            (libc::AF_INET6, source.octets(), destination.octets())
+        }
+        _ => {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "mixed address families",
+            ))
+        }
+    };
+
+    let request = InetDiagReqV2 {
+        family: family as u8,
+        protocol: libc::IPPROTO_TCP as u8,
+        extensions: 0,
+        pad: 0,
+        states: u32::MAX,
+        id: InetDiagSockId {
+            source_port: local.port().to_be_bytes(),
+            destination_port: remote.port().to_be_bytes(),
+            source,
+            destination,
+            interface: 0,
+            cookie: [u32::MAX; 2],
+        },
+    };
+
+    let socket = send_request(
+        SOCK_DESTROY,
+        (libc::NLM_F_REQUEST | libc::NLM_F_ACK) as u16,
+        &request,
+    )?;
+
+    let mut buffer = vec![0u8; 4096];
+
+    let received = unsafe {
+        libc::recv(
+            socket.as_raw_fd(),
+            buffer.as_mut_ptr() as *mut libc::c_void,
+            buffer.len(),
+            0,
+        )
+    };
+
+    if received < 0 {
+        return Err(io::Error::last_os_error());
+    }
+
+    // The acknowledgement is an NLMSG_ERROR with an error of 0 on success
+    let bytes = &buffer[..received as usize];
+
+    match read_struct::<NlMsgHdr>(bytes) {
+        Some(header) if header.kind == NLMSG_ERROR => {
+            match read_u32(&bytes[size_of::<NlMsgHdr>()..]).unwrap_or(0) as i32 {
+                0 => Ok(()),
+                error => Err(io::Error::from_raw_os_error(-error)),
+            }
+        }
+        _ => Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "unexpected reply to SOCK_DESTROY",
+        )),
+    }
+}
+
+/// IPv4 addresses take the first 4 bytes of the address fields.
+fn padded(octets: &[u8; 4]) -> [u8; 16] {
+    let mut address = [0; 16];
+    address[..4].copy_from_slice(octets);
+    address
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A route attribute, padded like the kernel does.
+    fn attribute(kind: u16, value: &[u8]) -> Vec<u8> {
+        let len = 4 + value.len();
+        let mut bytes = Vec::new();
+        bytes.extend_from_slice(&(len as u16).to_ne_bytes());
+        bytes.extend_from_slice(&kind.to_ne_bytes());
+        bytes.extend_from_slice(value);
+        bytes.resize(align(len), 0);
+        bytes
+    }
+
+    #[test]
+    fn splits_attributes() {
+        let mut bytes = attribute(1, b"abc");
+        bytes.extend(attribute(2, &7u32.to_ne_bytes()));
+
+        assert_eq!(
+            attributes(&bytes),
+            [(1, &b"abc"[..]), (2, &7u32.to_ne_bytes()[..])]
+        );
+    }
+
+    #[test]
+    fn stops_at_truncated_attributes() {
+        let mut bytes = attribute(1, b"abcd");
+        bytes.extend(attribute(2, b"efgh"));
+        bytes.truncate(bytes.len() - 2);
+
+        assert_eq!(attributes(&bytes), [(1, &b"abcd"[..])]);
+        assert!(attributes(&[0, 0, 1, 0]).is_empty());
+    }
+
+    #[test]
+    fn names_unix_paths_like_ss() {
+        assert_eq!(
+            unix_path(b"/run/dbus/system_bus_socket\0"),
+            "/run/dbus/system_bus_socket"
+        );
+        assert_eq!(unix_path(b"\0/tmp/.X11-unix/X0"), "@/tmp/.X11-unix/X0");
+        assert_eq!(unix_path(b""), "");
+    }
+
+    #[test]
+    fn parses_unix_diag_messages() {
+        let message = UnixDiagMsg {
+            family: libc::AF_UNIX as u8,
+            kind: libc::SOCK_STREAM as u8,
+            state: 1,
+            pad: 0,
+            inode: 4242,
+            cookie: [0; 2],
+        };
+
+        let mut payload = as_bytes(&message).to_vec();
+        payload.extend(attribute(UNIX_DIAG_NAME, b"/run/app.sock"));
+        payload.extend(attribute(UNIX_DIAG_PEER, &4343u32.to_ne_bytes()));
+
+        assert_eq!(
+            parse_unix_message(&payload),
+            Some(UnixDiag {
+                inode: 4242,
+                socket_type: libc::SOCK_STREAM as u8,
+                state: 1,
+                path: Some(String::from("/run/app.sock")),
+                peer_inode: Some(4343),
+            })
+        );
+        assert_eq!(parse_unix_message(&payload[..4]), None);
+    }
+}
@@ -1,4 +1,14 @@
+use std::collections::{HashMap, HashSet};
+use std::error::Error;
+use std::net::SocketAddr;
+use std::ops::RangeInclusive;
 use std::os::raw::c_int;
+use std::path::Path;
+
+use crate::os::shared::{
+    Capability, Cgroup, DataSource, InetSocketDiag, IpSocketInfo, NetNamespace, PacketSocketInfo,
+    Protocol, UnixSocketInfo,
+};
 pub const PROC_PIDPATHINFO_MAXSIZE: u32 = 4096;
 
 extern "C" {
@@ -33,3 +43,78 @@ pub fn get_os_proc_name(pid: c_int) -> Result<String, String> {
         }
     }
 }
+
+pub fn get_os_ephemeral_port_range() -> Result<RangeInclusive<u16>, Box<dyn Error>> {
+    // Defaults of net.inet.ip.portrange.first and net.inet.ip.portrange.last
+    Ok(49152..=65535)
+}
+
+pub fn get_os_socket_owners() -> HashMap<u64, Vec<u32>> {
+    HashMap::new()
+}
+
+pub fn get_os_unix_sockets(
+    _owners: &HashMap<u64, Vec<u32>>,
+) -> Result<Vec<UnixSocketInfo>, Box<dyn Error>> {
+    // FIXME: Walk the PROC_PIDFDSOCKETINFO of every process to list Unix domain sockets
+    Ok(Vec::new())
+}
+
+pub fn get_os_ip_sockets(
+    _protocol: Protocol,
+    _owners: &HashMap<u64, Vec<u32>>,
+) -> Result<Vec<IpSocketInfo>, Box<dyn Error>> {
+    Ok(Vec::new())
+}
+
+pub fn get_os_packet_sockets(
+    _owners: &HashMap<u64, Vec<u32>>,
+) -> Result<Vec<PacketSocketInfo>, Box<dyn Error>> {
+    // Packet sockets are Linux only, BPF devices are the macOS equivalent
+    Ok(Vec::new())
+}
+
+pub fn get_os_inet_diag(
+    _protocol: Protocol,
+) -> Result<HashMap<u64, InetSocketDiag>, Box<dyn Error>> {
+    // FIXME: TCP_CONNECTION_INFO is only available to the socket owner on macOS
+    Ok(HashMap::new())
+}
+
+pub fn get_os_ipv6_only_sockets() -> Option<HashSet<u64>> {
+    // FIXME: IPV6_V6ONLY of other processes' sockets isn't exposed by libproc
+    None
+}
+
+pub fn check_os_close_tcp_connection() -> Result<(), String> {
+    Err(String::from(
+        "closing connections is only supported on Linux",
+    ))
+}
+
+pub fn close_os_tcp_connection(_local: SocketAddr, _remote: SocketAddr) -> Result<(), String> {
+    check_os_close_tcp_connection()
+}
+
+pub fn get_os_net_namespaces() -> Vec<NetNamespace> {
+    // Network namespaces are Linux only
+    Vec::new()
+}
+
+pub fn in_os_net_namespace<T>(_path: &Path, f: impl FnOnce() -> T) -> Result<T, String> {
+    Ok(f())
+}
+
+pub fn get_os_cgroup(_pid: u32) -> Option<Cgroup> {
+    // cgroups are Linux only, launchd jobs could be the macOS equivalent
+    None
+}
+
+pub fn get_os_capabilities() -> Vec<Capability> {
+    // FIXME: Check for root, which is what reading other users' sockets needs on macOS
+    Vec::new()
+}
+
+pub fn get_os_data_sources() -> Vec<DataSource> {
+    Vec::new()
+}
@@ -1,28 +1,368 @@
+use std::collections::HashMap;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::ops::RangeInclusive;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use bitflags::bitflags;
 use netstat2::{
-    get_sockets_info, AddressFamilyFlags, ProtocolFlags, ProtocolSocketInfo, SocketInfo,
+    get_sockets_info, AddressFamilyFlags, ProtocolFlags, ProtocolSocketInfo, SocketInfo, TcpState,
 };
 use regex::Regex;
+use serde::Deserialize;
 
 #[cfg(target_os = "macos")]
 use crate::os::macos::libproc::*;
 
+#[cfg(target_os = "linux")]
+use crate::os::linux::capabilities::*;
+#[cfg(target_os = "linux")]
+use crate::os::linux::cgroup::*;
+#[cfg(target_os = "linux")]
+use crate::os::linux::diagnostics::*;
+#[cfg(target_os = "linux")]
+use crate::os::linux::netns::*;
 #[cfg(target_os = "linux")]
 use crate::os::linux::proc::*;
 
+/// Index of the local port cell in `printable_string`.
+const LOCAL_PORT_CELL: usize = 2;
+
+/// Index of the remote address cell in `printable_string`.
+const REMOTE_ADDRESS_CELL: usize = 3;
+
+/// Index of the remote port cell in `printable_string`.
+const REMOTE_PORT_CELL: usize = 4;
+
+bitflags! {
+    /// The kinds of sockets nets knows how to list.
+    pub struct Protocol: u32 {
+        const TCP = 0b0001;
+        const UDP = 0b0010;
+        const UNIX = 0b0100;
+        const RAW = 0b1000;
+        const ICMP = 0b1_0000;
+        const SCTP = 0b10_0000;
+        const PACKET = 0b100_0000;
+    }
+}
+
+impl Protocol {
+    /// Parses the lowercase name of a tab, like `tcp` or `all`.
+    pub fn from_name(name: &str) -> Option<Protocol> {
+        match name.to_lowercase().as_str() {
+            "all" => Some(Protocol::all()),
+            "tcp" => Some(Protocol::TCP),
+            "udp" => Some(Protocol::UDP),
+            "unix" => Some(Protocol::UNIX),
+            "raw" => Some(Protocol::RAW),
+            "icmp" => Some(Protocol::ICMP),
+            "sctp" => Some(Protocol::SCTP),
+            "packet" => Some(Protocol::PACKET),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum UnixSocketType {
+    Stream,
+    Datagram,
+    SeqPacket,
+    Unknown,
+}
+
+impl UnixSocketType {
+    /// Converts a `SOCK_*` socket type.
+    pub fn from_raw(socket_type: u8) -> UnixSocketType {
+        match socket_type {
+            1 => UnixSocketType::Stream,
+            2 => UnixSocketType::Datagram,
+            5 => UnixSocketType::SeqPacket,
+            _ => UnixSocketType::Unknown,
+        }
+    }
+}
+
+impl std::fmt::Display for UnixSocketType {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        // Same short names as ss
+        match self {
+            UnixSocketType::Stream => write!(f, "u_str"),
+            UnixSocketType::Datagram => write!(f, "u_dgr"),
+            UnixSocketType::SeqPacket => write!(f, "u_seq"),
+            UnixSocketType::Unknown => write!(f, "unix"),
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum UnixSocketState {
+    Listen,
+    Connecting,
+    Connected,
+    Disconnecting,
+    Unconnected,
+}
+
+impl std::fmt::Display for UnixSocketState {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            UnixSocketState::Listen => write!(f, "LISTEN"),
+            UnixSocketState::Connecting => write!(f, "CONNECTING"),
+            UnixSocketState::Connected => write!(f, "CONNECTED"),
+            UnixSocketState::Disconnecting => write!(f, "DISCONNECTING"),
+            UnixSocketState::Unconnected => write!(f, "UNCONNECTED"),
+        }
+    }
+}
+
+/// A Unix domain socket.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct UnixSocketInfo {
+    pub socket_type: UnixSocketType,
+    pub state: UnixSocketState,
+    /// Bound path, abstract names start with '@'.
+    pub path: Option<String>,
+    pub inode: u64,
+    pub peer_inode: Option<u64>,
+    pub associated_pids: Vec<u32>,
+}
+
+/// A raw, ICMP (ping) or SCTP socket.
+#[derive(Clone, PartialEq, Debug)]
+pub struct IpSocketInfo {
+    pub protocol: Protocol,
+    pub local_addr: IpAddr,
+    /// For raw sockets this is the IP protocol number, for ICMP the echo identifier.
+    pub local_port: u16,
+    pub remote_addr: IpAddr,
+    pub remote_port: u16,
+    pub state: TcpState,
+    pub uid: u32,
+    pub inode: u64,
+    pub associated_pids: Vec<u32>,
+}
+
+/// An AF_PACKET socket, as used by packet capture tools or DHCP clients.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct PacketSocketInfo {
+    /// `SOCK_RAW` or `SOCK_DGRAM`
+    pub socket_type: u8,
+    pub ether_type: u16,
+    /// `None` when bound to all interfaces.
+    pub interface: Option<String>,
+    pub uid: u32,
+    pub inode: u64,
+    pub associated_pids: Vec<u32>,
+}
+
+#[derive(Clone)]
+pub enum SocketDetails {
+    Inet(SocketInfo),
+    Unix(UnixSocketInfo),
+    Ip(IpSocketInfo),
+    Packet(PacketSocketInfo),
+}
+
+fn ip_protocol_name(protocol: u16) -> String {
+    match protocol {
+        1 => String::from("icmp"),
+        2 => String::from("igmp"),
+        6 => String::from("tcp"),
+        17 => String::from("udp"),
+        58 => String::from("ipv6-icmp"),
+        89 => String::from("ospf"),
+        112 => String::from("vrrp"),
+        255 => String::from("raw"),
+        _ => protocol.to_string(),
+    }
+}
+
+fn ether_type_name(ether_type: u16) -> String {
+    match ether_type {
+        0x0003 => String::from("ALL"),
+        0x0800 => String::from("IP"),
+        0x0806 => String::from("ARP"),
+        0x86dd => String::from("IPv6"),
+        0x88cc => String::from("LLDP"),
+        _ => format!("0x{:04x}", ether_type),
+    }
+}
+
+/// Kernel TCP state of a connection, see tcp_info in linux/tcp.h.
+#[derive(Clone)]
+pub struct TcpInternals {
+    pub rtt_us: u32,
+    pub rttvar_us: u32,
+    pub cwnd: u32,
+    pub ssthresh: u32,
+    /// Retransmissions of the current unacknowledged segment.
+    pub retransmits: u32,
+    pub total_retransmits: u32,
+    pub lost: u32,
+    pub unacked: u32,
+    pub mss: u32,
+    /// Bytes sent and acknowledged by the peer.
+    pub bytes_acked: Option<u64>,
+    pub bytes_received: Option<u64>,
+    pub congestion: Option<String>,
+}
+
+/// Queues of a TCP or UDP socket from inet_diag.
+#[derive(Clone, Copy)]
+pub struct SocketQueues {
+    /// Bytes waiting to be read and unsent or unacknowledged bytes, for TCP listeners
+    /// the accept queue length and the configured backlog.
+    pub recv_queue: u32,
+    pub send_queue: u32,
+    pub memory: Option<SocketMemory>,
+}
+
+/// What inet_diag reports about a TCP or UDP socket.
+pub struct InetSocketDiag {
+    pub queues: SocketQueues,
+    pub tcp_internals: Option<TcpInternals>,
+}
+
+/// Socket buffer usage in bytes, as shown by `ss -m`.
+#[derive(Clone, Copy)]
+pub struct SocketMemory {
+    pub rmem_alloc: u32,
+    pub rcvbuf: u32,
+    pub wmem_alloc: u32,
+    pub sndbuf: u32,
+    pub drops: u32,
+}
+
+/// Transfer rates in bytes per second.
+#[derive(Clone, Copy, Default)]
+pub struct Throughput {
+    pub rx: f64,
+    pub tx: f64,
+}
+
+impl Throughput {
+    pub fn total(&self) -> f64 {
+        self.rx + self.tx
+    }
+}
+
+impl std::ops::Add for Throughput {
+    type Output = Throughput;
+
+    fn add(self, other: Throughput) -> Throughput {
+        Throughput {
+            rx: self.rx + other.rx,
+            tx: self.tx + other.tx,
+        }
+    }
+}
+
+/// The process owning the other end of a connection made on this host.
+#[derive(Clone)]
+pub struct PeerProcess {
+    pub pid: Option<u32>,
+    pub process_name: String,
+}
+
+impl std::fmt::Display for PeerProcess {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self.pid {
+            Some(pid) => write!(f, "{}/{}", pid, self.process_name),
+            None => write!(f, "{}", self.process_name),
+        }
+    }
+}
+
+/// What a process belongs to, according to its cgroup.
+#[derive(Clone, Default, Debug, PartialEq, Eq)]
+pub struct Cgroup {
+    /// systemd unit, e.g. nginx.service.
+    pub unit: Option<String>,
+    /// Docker, containerd, CRI-O or podman container ID.
+    pub container: Option<String>,
+    /// Kubernetes pod UID.
+    pub pod: Option<String>,
+}
+
+/// Which addresses a socket accepts traffic on.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BindScope {
+    /// Bound to the wildcard address, reachable on every interface.
+    All,
+    /// Bound to the address of one interface.
+    Address,
+    Loopback,
+}
+
+impl std::fmt::Display for BindScope {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            BindScope::All => write!(f, "all"),
+            BindScope::Address => write!(f, "address"),
+            BindScope::Loopback => write!(f, "loopback"),
+        }
+    }
+}
+
+/// The IP versions a socket accepts.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum AddressFamilies {
+    V4,
+    V6,
+    /// An IPv6 wildcard socket without IPV6_V6ONLY, also reached over IPv4.
+    Dual,
+}
+
+impl std::fmt::Display for AddressFamilies {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            AddressFamilies::V4 => write!(f, "v4"),
+            AddressFamilies::V6 => write!(f, "v6"),
+            AddressFamilies::Dual => write!(f, "dual"),
+        }
+    }
+}
+
 #[derive(Clone)]
 pub struct SocketInfoWithProcName {
-    pub info: SocketInfo,
+    pub info: SocketDetails,
     pub process_name: String,
     pub printable_string: Vec<String>,
-    pub protocol_flags: ProtocolFlags,
+    pub protocol_flags: Protocol,
+    pub peer: Option<PeerProcess>,
+    pub tcp_internals: Option<TcpInternals>,
+    pub queues: Option<SocketQueues>,
+    /// Measured between two snapshots, see `ThroughputTracker`.
+    pub throughput: Option<Throughput>,
+    /// Time since the socket was first seen, see `AgeTracker`.
+    pub age: Option<Duration>,
+    /// Reverse DNS name of the remote address, once resolved.
+    pub remote_host: Option<String>,
+    /// Name from /etc/services of the well-known port of the connection.
+    pub service: Option<String>,
+    /// Name of the socket's owner, from its uid.
+    pub user: Option<String>,
+    /// Name of the network namespace the socket is in, when namespaces are known.
+    pub namespace: Option<String>,
+    /// Unit or container of the owning process.
+    pub cgroup: Option<Cgroup>,
+    /// Whether an IPv6 TCP listener or UDP socket has IPV6_V6ONLY set, when known.
+    pub ipv6_only: Option<bool>,
+    /// Bound to all interfaces without being on the allowed listeners, see `Allowlist`.
+    pub exposed: bool,
+    /// A listener or outbound connection missing from the loaded baseline, see `Baseline`.
+    pub drift: bool,
 }
 
 impl SocketInfoWithProcName {
     pub fn new(info: SocketInfo, name: String) -> SocketInfoWithProcName {
         match &info.protocol_socket_info {
             ProtocolSocketInfo::Tcp(tcp_si) => SocketInfoWithProcName {
-                protocol_flags: ProtocolFlags::TCP,
-                info: info.clone(),
+                protocol_flags: Protocol::TCP,
+                info: SocketDetails::Inet(info.clone()),
                 process_name: name.clone(),
                 printable_string: vec![
                     match tcp_si.local_addr.is_ipv4() {
@@ -40,10 +380,23 @@ impl SocketInfoWithProcName {
                     },
                     name,
                 ],
+                peer: None,
+                tcp_internals: None,
+                queues: None,
+                throughput: None,
+                age: None,
+                remote_host: None,
+                service: None,
+                user: None,
+                namespace: None,
+                cgroup: None,
+                ipv6_only: None,
+                exposed: false,
+                drift: false,
             },
             ProtocolSocketInfo::Udp(udp_si) => SocketInfoWithProcName {
-                protocol_flags: ProtocolFlags::UDP,
-                info: info.clone(),
+                protocol_flags: Protocol::UDP,
+                info: SocketDetails::Inet(info.clone()),
                 process_name: name.clone(),
                 printable_string: vec![
                     match udp_si.local_addr.is_ipv4() {
@@ -61,18 +414,382 @@ impl SocketInfoWithProcName {
                     },
                     name,
                 ],
+                peer: None,
+                tcp_internals: None,
+                queues: None,
+                throughput: None,
+                age: None,
+                remote_host: None,
+                service: None,
+                user: None,
+                namespace: None,
+                cgroup: None,
+                ipv6_only: None,
+                exposed: false,
+                drift: false,
             },
         }
     }
 
-    pub fn should_print(&self, regex: &Option<Regex>) -> bool {
-        if regex.is_none() {
+    /// Like ss, the inode of a Unix socket is shown in place of a port.
+    pub fn from_unix(info: UnixSocketInfo, name: String) -> SocketInfoWithProcName {
+        SocketInfoWithProcName {
+            protocol_flags: Protocol::UNIX,
+            process_name: name.clone(),
+            printable_string: vec![
+                info.socket_type.to_string(),
+                info.path.clone().unwrap_or_else(|| String::from("*")),
+                info.inode.to_string(),
+                String::from("*"),
+                match info.peer_inode {
+                    Some(inode) => inode.to_string(),
+                    _ => String::from("*"),
+                },
+                info.state.to_string(),
+                match info.associated_pids.last() {
+                    Some(pid) => pid.to_string(),
+                    _ => "-".to_string(),
+                },
+                name,
+            ],
+            info: SocketDetails::Unix(info),
+            peer: None,
+            tcp_internals: None,
+            queues: None,
+            throughput: None,
+            age: None,
+            remote_host: None,
+            service: None,
+            user: None,
+            namespace: None,
+            cgroup: None,
+            ipv6_only: None,
+            exposed: false,
+            drift: false,
+        }
+    }
+
+    pub fn from_ip(info: IpSocketInfo, name: String) -> SocketInfoWithProcName {
+        let family = match info.local_addr.is_ipv4() {
+            true => "4",
+            _ => "6",
+        };
+
+        let protocol = match info.protocol {
+            Protocol::ICMP => "icmp",
+            Protocol::SCTP => "sctp",
+            _ => "raw",
+        };
+
+        SocketInfoWithProcName {
+            protocol_flags: info.protocol,
+            process_name: name.clone(),
+            printable_string: vec![
+                format!("{}{}", protocol, family),
+                info.local_addr.to_string(),
+                match info.protocol {
+                    Protocol::RAW => ip_protocol_name(info.local_port),
+                    _ => info.local_port.to_string(),
+                },
+                info.remote_addr.to_string(),
+                info.remote_port.to_string(),
+                info.state.to_string(),
+                match info.associated_pids.last() {
+                    Some(pid) => pid.to_string(),
+                    _ => "-".to_string(),
+                },
+                name,
+            ],
+            info: SocketDetails::Ip(info),
+            peer: None,
+            tcp_internals: None,
+            queues: None,
+            throughput: None,
+            age: None,
+            remote_host: None,
+            service: None,
+            user: None,
+            namespace: None,
+            cgroup: None,
+            ipv6_only: None,
+            exposed: false,
+            drift: false,
+        }
+    }
+
+    pub fn from_packet(info: PacketSocketInfo, name: String) -> SocketInfoWithProcName {
+        SocketInfoWithProcName {
+            protocol_flags: Protocol::PACKET,
+            process_name: name.clone(),
+            printable_string: vec![
+                String::from("packet"),
+                info.interface.clone().unwrap_or_else(|| String::from("*")),
+                ether_type_name(info.ether_type),
+                String::from(""),
+                String::from(""),
+                String::from(""),
+                match info.associated_pids.last() {
+                    Some(pid) => pid.to_string(),
+                    _ => "-".to_string(),
+                },
+                name,
+            ],
+            info: SocketDetails::Packet(info),
+            peer: None,
+            tcp_internals: None,
+            queues: None,
+            throughput: None,
+            age: None,
+            remote_host: None,
+            service: None,
+            user: None,
+            namespace: None,
+            cgroup: None,
+            ipv6_only: None,
+            exposed: false,
+            drift: false,
+        }
+    }
+
+    /// The netstat2 information of TCP and UDP sockets.
+    pub fn inet(&self) -> Option<&SocketInfo> {
+        match &self.info {
+            SocketDetails::Inet(info) => Some(info),
+            _ => None,
+        }
+    }
+
+    pub fn inode(&self) -> Option<u64> {
+        match &self.info {
+            #[cfg(target_os = "linux")]
+            SocketDetails::Inet(info) => Some(u64::from(info.inode)),
+            #[cfg(not(target_os = "linux"))]
+            SocketDetails::Inet(_) => None,
+            SocketDetails::Unix(info) => Some(info.inode),
+            SocketDetails::Ip(info) => Some(info.inode),
+            SocketDetails::Packet(info) => Some(info.inode),
+        }
+    }
+
+    /// Owner of the socket, unknown for Unix sockets.
+    pub fn uid(&self) -> Option<u32> {
+        match &self.info {
+            #[cfg(target_os = "linux")]
+            SocketDetails::Inet(info) => Some(info.uid),
+            #[cfg(not(target_os = "linux"))]
+            SocketDetails::Inet(_) => None,
+            SocketDetails::Unix(_) => None,
+            SocketDetails::Ip(info) => Some(info.uid),
+            SocketDetails::Packet(info) => Some(info.uid),
+        }
+    }
+
+    pub fn associated_pids(&self) -> &[u32] {
+        match &self.info {
+            SocketDetails::Inet(info) => &info.associated_pids,
+            SocketDetails::Unix(info) => &info.associated_pids,
+            SocketDetails::Ip(info) => &info.associated_pids,
+            SocketDetails::Packet(info) => &info.associated_pids,
+        }
+    }
+
+    /// Whether the socket belongs to a process that couldn't be found, usually another
+    /// user's while nets isn't privileged. Sockets without an inode have no process.
+    pub fn is_unattributed(&self) -> bool {
+        self.associated_pids().is_empty() && self.inode().is_some_and(|inode| inode != 0)
+    }
+
+    pub fn pid(&self) -> Option<u32> {
+        self.associated_pids().last().copied()
+    }
+
+    pub fn local_port(&self) -> Option<u16> {
+        match &self.info {
+            SocketDetails::Inet(info) => Some(info.local_port()),
+            SocketDetails::Ip(info) => Some(info.local_port),
+            _ => None,
+        }
+    }
+
+    pub fn remote_endpoint(&self) -> Option<(IpAddr, u16)> {
+        match &self.info {
+            SocketDetails::Inet(info) => match &info.protocol_socket_info {
+                ProtocolSocketInfo::Tcp(tcp_si) => Some((tcp_si.remote_addr, tcp_si.remote_port)),
+                ProtocolSocketInfo::Udp(_) => None,
+            },
+            SocketDetails::Ip(info) => Some((info.remote_addr, info.remote_port)),
+            _ => None,
+        }
+    }
+
+    /// Name of the protocol in /etc/services, for the sockets whose ports are real ports.
+    pub fn service_protocol(&self) -> Option<&'static str> {
+        match self.protocol_flags {
+            Protocol::TCP => Some("tcp"),
+            Protocol::UDP => Some("udp"),
+            Protocol::SCTP => Some("sctp"),
+            _ => None,
+        }
+    }
+
+    pub fn tcp_state(&self) -> Option<TcpState> {
+        match &self.inet()?.protocol_socket_info {
+            ProtocolSocketInfo::Tcp(tcp_si) => Some(tcp_si.state),
+            ProtocolSocketInfo::Udp(_) => None,
+        }
+    }
+
+    /// Returns the (local, remote) endpoints of a connected TCP socket.
+    pub fn tcp_endpoints(&self) -> Option<(SocketAddr, SocketAddr)> {
+        match &self.inet()?.protocol_socket_info {
+            ProtocolSocketInfo::Tcp(tcp_si) if tcp_si.state != TcpState::Listen => Some((
+                SocketAddr::new(tcp_si.local_addr, tcp_si.local_port),
+                SocketAddr::new(tcp_si.remote_addr, tcp_si.remote_port),
+            )),
+            _ => None,
+        }
+    }
+
+    /// TCP listeners and UDP sockets, which can be reached from outside.
+    pub fn is_listener(&self) -> bool {
+        match self.protocol_flags {
+            Protocol::TCP => self.tcp_state() == Some(TcpState::Listen),
+            Protocol::UDP => true,
+            _ => false,
+        }
+    }
+
+    fn local_addr(&self) -> Option<IpAddr> {
+        match &self.inet()?.protocol_socket_info {
+            ProtocolSocketInfo::Tcp(tcp_si) => Some(tcp_si.local_addr),
+            ProtocolSocketInfo::Udp(udp_si) => Some(udp_si.local_addr),
+        }
+    }
+
+    pub fn bind_scope(&self) -> Option<BindScope> {
+        let addr = match self.local_addr()? {
+            IpAddr::V6(addr) => addr.to_ipv4_mapped().map_or(IpAddr::V6(addr), IpAddr::V4),
+            addr => addr,
+        };
+
+        Some(if addr.is_unspecified() {
+            BindScope::All
+        } else if addr.is_loopback() {
+            BindScope::Loopback
+        } else {
+            BindScope::Address
+        })
+    }
+
+    pub fn address_families(&self) -> Option<AddressFamilies> {
+        Some(match self.local_addr()? {
+            IpAddr::V4(_) => AddressFamilies::V4,
+            IpAddr::V6(addr) if addr.to_ipv4_mapped().is_some() => AddressFamilies::V4,
+            IpAddr::V6(addr) if addr == Ipv6Addr::UNSPECIFIED && self.ipv6_only == Some(false) => {
+                AddressFamilies::Dual
+            }
+            IpAddr::V6(_) => AddressFamilies::V6,
+        })
+    }
+
+    /// Whether the socket is bound to `address`, IPv4-mapped or not. The IPv4 wildcard counts
+    /// for dual-stack sockets, which are bound to it too.
+    pub fn is_bound_to(&self, address: IpAddr) -> bool {
+        let local = match self.local_addr() {
+            Some(local) => local.to_canonical(),
+            None => return false,
+        };
+
+        local == address.to_canonical()
+            || (address == IpAddr::V4(Ipv4Addr::UNSPECIFIED)
+                && self.address_families() == Some(AddressFamilies::Dual))
+    }
+
+    /// Whether connections to `address` reach the socket, bound to it or to a wildcard
+    /// covering it.
+    pub fn accepts_on(&self, address: IpAddr) -> bool {
+        if self.is_bound_to(address) {
             return true;
         }
 
+        self.bind_scope() == Some(BindScope::All)
+            && match address.to_canonical() {
+                IpAddr::V4(_) => self.address_families() != Some(AddressFamilies::V6),
+                IpAddr::V6(_) => self.address_families() != Some(AddressFamilies::V4),
+            }
+    }
+
+    /// Whether `other` is the opposite end of this connection.
+    pub fn is_peer_of(&self, other: &SocketInfoWithProcName) -> bool {
+        if let (SocketDetails::Unix(unix), SocketDetails::Unix(other_unix)) =
+            (&self.info, &other.info)
+        {
+            return unix.peer_inode == Some(other_unix.inode);
+        }
+
+        match (self.tcp_endpoints(), other.tcp_endpoints()) {
+            (Some((local, remote)), Some((other_local, other_remote))) => {
+                local == other_remote && remote == other_local
+            }
+            _ => false,
+        }
+    }
+
+    /// Accept queue length and configured backlog of a listening TCP socket.
+    pub fn accept_queue(&self) -> Option<(u32, u32)> {
+        match self.tcp_state()? {
+            TcpState::Listen => {
+                let queues = self.queues.as_ref()?;
+                Some((queues.recv_queue, queues.send_queue))
+            }
+            _ => None,
+        }
+    }
+
+    /// A listener whose accept queue is full drops or delays new connections. Like
+    /// sk_acceptq_is_full, the queue holds one more than the backlog.
+    pub fn is_accept_queue_saturated(&self) -> bool {
+        self.accept_queue()
+            .is_some_and(|(queued, backlog)| queued > backlog)
+    }
+
+    pub fn has_queued_data(&self) -> bool {
+        match (&self.queues, self.accept_queue()) {
+            (_, Some((queued, _))) => queued > 0,
+            (Some(queues), None) => queues.recv_queue > 0 || queues.send_queue > 0,
+            _ => false,
+        }
+    }
+
+    pub fn set_peer(&mut self, peer: PeerProcess) {
+        self.peer = Some(peer);
+    }
+
+    /// Shows a host name in place of the remote address.
+    pub fn set_remote_host(&mut self, host: String) {
+        self.printable_string[REMOTE_ADDRESS_CELL] = host.clone();
+        self.remote_host = Some(host);
+    }
+
+    /// Shows service names in place of the port numbers they are known for.
+    pub fn set_service_names(&mut self, local: Option<String>, remote: Option<String>) {
+        if let Some(local) = local {
+            self.printable_string[LOCAL_PORT_CELL] = local;
+        }
+
+        if let Some(remote) = remote {
+            self.printable_string[REMOTE_PORT_CELL] = remote;
+        }
+    }
+
+    pub fn should_print(&self, regex: &Regex) -> bool {
         self.printable_string
             .iter()
-            .any(|cell| regex.as_ref().unwrap().is_match(cell))
+            .any(|cell| regex.is_match(cell))
+            || self
+                .peer
+                .as_ref()
+                .is_some_and(|peer| regex.is_match(&peer.to_string()))
     }
 }
 
@@ -85,22 +802,318 @@ pub fn get_proc_name(pid: u32) -> String {
     }
 }
 
+/// Range the OS picks ephemeral (client side) ports from.
+pub fn get_ephemeral_port_range() -> RangeInclusive<u16> {
+    get_os_ephemeral_port_range().unwrap_or(49152..=65535)
+}
+
+/// A privilege nets uses, and whether it has it.
+pub struct Capability {
+    pub name: &'static str,
+    pub granted: bool,
+    /// What it allows, e.g. "close connections (X)".
+    pub needed_for: &'static str,
+    /// Whether it is needed to find the processes of sockets.
+    pub attributes_sockets: bool,
+}
+
+/// Something nets reads its data from, and what it found there or why it can't.
+pub struct DataSource {
+    pub name: &'static str,
+    pub status: Result<String, String>,
+}
+
+/// The capabilities that change what nets can show.
+pub fn get_capabilities() -> Vec<Capability> {
+    get_os_capabilities()
+}
+
+/// Every place nets reads from, checked for `nets doctor`.
+pub fn get_data_sources() -> Vec<DataSource> {
+    get_os_data_sources()
+}
+
+/// A network namespace, named after `ip netns` or the first process in it.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct NetNamespace {
+    pub inode: u64,
+    pub name: String,
+    /// What to open to enter it, a /run/netns file or a /proc/<pid>/ns/net link.
+    pub path: PathBuf,
+    /// Whether nets runs in it.
+    pub is_current: bool,
+}
+
+/// Every network namespace that can be seen, the current one first.
+pub fn get_net_namespaces() -> Vec<NetNamespace> {
+    get_os_net_namespaces()
+}
+
+/// Lists the sockets of a network namespace, labelled with its name.
+pub fn get_socket_info_in_namespace(
+    protocol: Protocol,
+    namespace: &NetNamespace,
+) -> Result<Vec<SocketInfoWithProcName>, Box<dyn std::error::Error>> {
+    let mut sockets = in_os_net_namespace(&namespace.path, || get_all_socket_info(protocol))??;
+
+    for socket in &mut sockets {
+        socket.namespace = Some(namespace.name.clone());
+    }
+
+    Ok(sockets)
+}
+
+/// Whether connections can be closed, with the reason when they can't.
+pub fn can_close_tcp_connections() -> Result<(), String> {
+    check_os_close_tcp_connection()
+}
+
+/// Forcibly closes a TCP connection, as if reset by the network. `namespace` is the network
+/// namespace it is in when that isn't the current one.
+pub fn close_tcp_connection(
+    local: SocketAddr,
+    remote: SocketAddr,
+    namespace: Option<&NetNamespace>,
+) -> Result<(), String> {
+    match namespace {
+        Some(namespace) => {
+            in_os_net_namespace(&namespace.path, || close_os_tcp_connection(local, remote))?
+        }
+        None => close_os_tcp_connection(local, remote),
+    }
+}
+
 pub fn get_all_socket_info(
-    protocol: ProtocolFlags,
+    protocol: Protocol,
 ) -> Result<Vec<SocketInfoWithProcName>, Box<dyn std::error::Error>> {
     let mut open_sockets: Vec<SocketInfoWithProcName> = Vec::new();
 
-    let af_flags = AddressFamilyFlags::IPV4 | AddressFamilyFlags::IPV6;
-    let sockets_info = get_sockets_info(af_flags, protocol)?;
+    let mut proto_flags = ProtocolFlags::empty();
+
+    if protocol.contains(Protocol::TCP) {
+        proto_flags |= ProtocolFlags::TCP;
+    }
 
-    for mut si in sockets_info {
-        open_sockets.push(SocketInfoWithProcName::new(si.clone(), {
-            match si.associated_pids.pop() {
-                Some(pid) => get_proc_name(pid),
-                _ => "-".to_string(),
+    if protocol.contains(Protocol::UDP) {
+        proto_flags |= ProtocolFlags::UDP;
+    }
+
+    if !proto_flags.is_empty() {
+        let af_flags = AddressFamilyFlags::IPV4 | AddressFamilyFlags::IPV6;
+        let sockets_info = get_sockets_info(af_flags, proto_flags)?;
+
+        for mut si in sockets_info {
+            open_sockets.push(SocketInfoWithProcName::new(si.clone(), {
+                match si.associated_pids.pop() {
+                    Some(pid) => get_proc_name(pid),
+                    _ => "-".to_string(),
+                }
+            }));
+        }
+    }
+
+    if protocol.intersects(Protocol::TCP | Protocol::UDP) {
+        let mut diag = get_os_inet_diag(protocol).unwrap_or_default();
+
+        for socket in open_sockets.iter_mut() {
+            if let Some(found) = socket.inode().and_then(|inode| diag.remove(&inode)) {
+                socket.queues = Some(found.queues);
+                socket.tcp_internals = found.tcp_internals;
+            }
+        }
+    }
+
+    if protocol.intersects(Protocol::TCP | Protocol::UDP) {
+        if let Some(ipv6_only) = get_os_ipv6_only_sockets() {
+            for socket in open_sockets.iter_mut() {
+                if socket.local_addr().is_some_and(|addr| addr.is_ipv6()) {
+                    socket.ipv6_only = socket.inode().map(|inode| ipv6_only.contains(&inode));
+                }
             }
-        }));
+        }
     }
 
+    // Sockets netstat2 doesn't know about are matched to their processes by inode
+    let others =
+        Protocol::UNIX | Protocol::RAW | Protocol::ICMP | Protocol::SCTP | Protocol::PACKET;
+    let owners = match protocol.intersects(others) {
+        true => get_os_socket_owners(),
+        false => HashMap::new(),
+    };
+
+    let name_of = |pids: &[u32]| match pids.last() {
+        Some(pid) => get_proc_name(*pid),
+        _ => "-".to_string(),
+    };
+
+    if protocol.contains(Protocol::UNIX) {
+        for si in get_os_unix_sockets(&owners)? {
+            let name = name_of(&si.associated_pids);
+            open_sockets.push(SocketInfoWithProcName::from_unix(si, name));
+        }
+    }
+
+    if protocol.intersects(Protocol::RAW | Protocol::ICMP | Protocol::SCTP) {
+        for si in get_os_ip_sockets(protocol, &owners)? {
+            let name = name_of(&si.associated_pids);
+            open_sockets.push(SocketInfoWithProcName::from_ip(si, name));
+        }
+    }
+
+    if protocol.contains(Protocol::PACKET) {
+        for si in get_os_packet_sockets(&owners)? {
+            let name = name_of(&si.associated_pids);
+            open_sockets.push(SocketInfoWithProcName::from_packet(si, name));
+        }
+    }
+
+    // Processes usually own several sockets, their cgroup is only read once
+    let mut cgroups: HashMap<u32, Option<Cgroup>> = HashMap::new();
+
+    for socket in open_sockets.iter_mut() {
+        if let Some(pid) = socket.pid() {
+            socket.cgroup = cgroups
+                .entry(pid)
+                .or_insert_with(|| get_os_cgroup(pid))
+                .clone();
+        }
+    }
+
+    pair_local_connections(&mut open_sockets);
+
     Ok(open_sockets)
 }
+
+/// Annotates both ends of every connection whose endpoints are both present in the snapshot
+/// (e.g. two services talking over loopback or a Unix socket) with the process on the other end.
+pub fn pair_local_connections(sockets: &mut [SocketInfoWithProcName]) {
+    let endpoints: HashMap<(SocketAddr, SocketAddr), usize> = sockets
+        .iter()
+        .enumerate()
+        .filter_map(|(index, socket)| socket.tcp_endpoints().map(|endpoints| (endpoints, index)))
+        .collect();
+
+    let unix_inodes: HashMap<u64, usize> = sockets
+        .iter()
+        .enumerate()
+        .filter_map(|(index, socket)| match &socket.info {
+            SocketDetails::Unix(unix) => Some((unix.inode, index)),
+            _ => None,
+        })
+        .collect();
+
+    let peers: Vec<(usize, usize)> = sockets
+        .iter()
+        .enumerate()
+        .filter_map(|(index, socket)| {
+            let peer = match &socket.info {
+                SocketDetails::Unix(unix) => unix_inodes.get(&unix.peer_inode?)?,
+                SocketDetails::Inet(_) => {
+                    let (local, remote) = socket.tcp_endpoints()?;
+                    endpoints.get(&(remote, local))?
+                }
+                _ => return None,
+            };
+
+            Some((index, *peer))
+        })
+        .collect();
+
+    for (index, peer) in peers {
+        if let SocketDetails::Unix(unix) = &sockets[peer].info {
+            if let Some(path) = unix.path.clone() {
+                sockets[index].printable_string[REMOTE_ADDRESS_CELL] = path;
+            }
+        }
+
+        let peer = PeerProcess {
+            pid: sockets[peer].pid(),
+            process_name: sockets[peer].process_name.clone(),
+        };
+
+        sockets[index].set_peer(peer);
+    }
+}
+
+/// Sockets for unit tests, of a process with pid 100.
+#[cfg(test)]
+pub mod fixtures {
+    use netstat2::{TcpSocketInfo, UdpSocketInfo};
+
+    use super::*;
+
+    fn socket(protocol_socket_info: ProtocolSocketInfo, process: &str) -> SocketInfoWithProcName {
+        let info = SocketInfo {
+            protocol_socket_info,
+            associated_pids: vec![100],
+            #[cfg(target_os = "linux")]
+            inode: 1,
+            #[cfg(target_os = "linux")]
+            uid: 0,
+        };
+
+        SocketInfoWithProcName::new(info, process.to_string())
+    }
+
+    /// Addresses are written like `127.0.0.1:80` or `[::]:80`.
+    pub fn tcp(
+        local: &str,
+        remote: &str,
+        state: TcpState,
+        process: &str,
+    ) -> SocketInfoWithProcName {
+        let local: SocketAddr = local.parse().unwrap();
+        let remote: SocketAddr = remote.parse().unwrap();
+
+        socket(
+            ProtocolSocketInfo::Tcp(TcpSocketInfo {
+                local_addr: local.ip(),
+                local_port: local.port(),
+                remote_addr: remote.ip(),
+                remote_port: remote.port(),
+                state,
+            }),
+            process,
+        )
+    }
+
+    pub fn tcp_listener(local: &str, process: &str) -> SocketInfoWithProcName {
+        let remote = match local.starts_with('[') {
+            true => "[::]:0",
+            false => "0.0.0.0:0",
+        };
+
+        tcp(local, remote, TcpState::Listen, process)
+    }
+
+    pub fn udp(local: &str, process: &str) -> SocketInfoWithProcName {
+        let local: SocketAddr = local.parse().unwrap();
+
+        socket(
+            ProtocolSocketInfo::Udp(UdpSocketInfo {
+                local_addr: local.ip(),
+                local_port: local.port(),
+            }),
+            process,
+        )
+    }
+
+    /// The socket of another process, or of none that could be found.
+    pub fn owned_by(socket: SocketInfoWithProcName, pid: Option<u32>) -> SocketInfoWithProcName {
+        let mut info = socket.inet().unwrap().clone();
+        info.associated_pids = pid.into_iter().collect();
+
+        let name = match pid {
+            Some(_) => socket.process_name,
+            None => String::from("-"),
+        };
+
+        SocketInfoWithProcName::new(info, name)
+    }
+
+    /// A `[::]` listener that also accepts IPv4, or not.
+    pub fn dual_stack(mut socket: SocketInfoWithProcName, dual: bool) -> SocketInfoWithProcName {
+        socket.ipv6_only = Some(!dual);
+        socket
+    }
+}
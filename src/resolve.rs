@@ -0,0 +1,186 @@
+// Host, service and user names shown in place of addresses, port numbers and uids.
+use std::collections::HashMap;
+use std::ffi::CStr;
+use std::mem::size_of;
+use std::net::IpAddr;
+use std::sync::mpsc::{self, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+/// Port names from /etc/services, keyed by port and protocol (tcp, udp or sctp).
+pub struct Services {
+    names: HashMap<(u16, String), String>,
+}
+
+impl Services {
+    pub fn load() -> Services {
+        Services::parse(&std::fs::read_to_string("/etc/services").unwrap_or_default())
+    }
+
+    /// Reads the contents of a services file.
+    pub fn parse(services: &str) -> Services {
+        let names = services
+            .lines()
+            .filter_map(|line| {
+                // e.g. "https   443/tcp   # http protocol over TLS/SSL"
+                let mut fields = line.split('#').next()?.split_whitespace();
+                let name = fields.next()?;
+                let (port, protocol) = fields.next()?.split_once('/')?;
+
+                Some(((port.parse().ok()?, protocol.to_string()), name.to_string()))
+            })
+            .collect();
+
+        Services { names }
+    }
+
+    pub fn name(&self, port: u16, protocol: &str) -> Option<&str> {
+        self.names
+            .get(&(port, protocol.to_string()))
+            .map(String::as_str)
+    }
+}
+
+/// User names from /etc/passwd, keyed by uid.
+pub struct UserNames {
+    names: HashMap<u32, String>,
+}
+
+impl UserNames {
+    pub fn load() -> UserNames {
+        UserNames::parse(&std::fs::read_to_string("/etc/passwd").unwrap_or_default())
+    }
+
+    /// Reads the contents of a passwd file. Like getpwuid, the first name of a uid wins.
+    pub fn parse(passwd: &str) -> UserNames {
+        let mut names = HashMap::new();
+
+        for line in passwd.lines() {
+            // e.g. "www-data:x:33:33:www-data:/var/www:/usr/sbin/nologin"
+            let mut fields = line.split(':');
+            let name = fields.next().filter(|name| !name.is_empty());
+
+            if let (Some(name), Some(Ok(uid))) = (name, fields.nth(1).map(str::parse)) {
+                names.entry(uid).or_insert_with(|| name.to_string());
+            }
+        }
+
+        UserNames { names }
+    }
+
+    pub fn name(&self, uid: u32) -> Option<&str> {
+        self.names.get(&uid).map(String::as_str)
+    }
+}
+
+/// Reverse DNS lookups, resolved on a background thread so drawing never waits on them.
+pub struct HostNames {
+    names: Arc<Mutex<HashMap<IpAddr, Option<String>>>>,
+    requests: Sender<IpAddr>,
+}
+
+impl HostNames {
+    pub fn new() -> HostNames {
+        let names = Arc::new(Mutex::new(HashMap::new()));
+        let (requests, pending) = mpsc::channel::<IpAddr>();
+
+        let resolved = Arc::clone(&names);
+
+        thread::spawn(move || {
+            for addr in pending {
+                let name = lookup_host(addr);
+                resolved.lock().unwrap().insert(addr, name);
+            }
+        });
+
+        HostNames { names, requests }
+    }
+
+    /// Returns the name of `addr` if it is known, otherwise queues it to be looked up.
+    pub fn get(&self, addr: IpAddr) -> Option<String> {
+        if addr.is_unspecified() {
+            return None;
+        }
+
+        let mut names = self.names.lock().unwrap();
+
+        match names.get(&addr) {
+            Some(name) => name.clone(),
+            None => {
+                // Marks the address as pending so it is only requested once
+                names.insert(addr, None);
+                let _ = self.requests.send(addr);
+                None
+            }
+        }
+    }
+}
+
+fn lookup_host(addr: IpAddr) -> Option<String> {
+    let mut storage: libc::sockaddr_storage = unsafe { std::mem::zeroed() };
+
+    let len = match addr {
+        IpAddr::V4(v4) => {
+            let sin = unsafe { &mut *(&mut storage as *mut _ as *mut libc::sockaddr_in) };
+            sin.sin_family = libc::AF_INET as libc::sa_family_t;
+            sin.sin_addr.s_addr = u32::from_ne_bytes(v4.octets());
+            size_of::<libc::sockaddr_in>()
+        }
+        IpAddr::V6(v6) => {
+            let sin6 = unsafe { &mut *(&mut storage as *mut _ as *mut libc::sockaddr_in6) };
+            sin6.sin6_family = libc::AF_INET6 as libc::sa_family_t;
+            sin6.sin6_addr.s6_addr = v6.octets();
+            size_of::<libc::sockaddr_in6>()
+        }
+    };
+
+    let mut host = [0 as libc::c_char; libc::NI_MAXHOST as usize];
+
+    let result = unsafe {
+        libc::getnameinfo(
+            &storage as *const _ as *const libc::sockaddr,
+            len as libc::socklen_t,
+            host.as_mut_ptr(),
+            host.len() as libc::socklen_t,
+            std::ptr::null_mut(),
+            0,
+            libc::NI_NAMEREQD,
+        )
+    };
+
+    match result {
+        0 => Some(
+            unsafe { CStr::from_ptr(host.as_ptr()) }
+                .to_string_lossy()
+                .into_owned(),
+        ),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_user_names_from_passwd() {
+        let users = UserNames::parse(
+            "root:x:0:0:root:/root:/bin/bash\n\
+             toor:x:0:0:root:/root:/bin/sh\n\
+             # a comment\n\
+             \n\
+             www-data:x:33:33:www-data:/var/www:/usr/sbin/nologin\n\
+             +@netgroup::::::\n\
+             broken:x:notanumber:0::/:/bin/false\n\
+             :x:5:5::/:/bin/false\n\
+             nobody:x:65534:65534:nobody:/nonexistent:/usr/sbin/nologin",
+        );
+
+        assert_eq!(users.name(0), Some("root"));
+        assert_eq!(users.name(33), Some("www-data"));
+        assert_eq!(users.name(65534), Some("nobody"));
+        assert_eq!(users.name(5), None);
+        assert_eq!(users.name(1000), None);
+        assert_eq!(users.names.len(), 3);
+    }
+}
@@ -0,0 +1,477 @@
+// Expectations about open ports and connections, checked against a snapshot by `nets assert`.
+use std::net::{IpAddr, SocketAddr};
+use std::path::Path;
+
+use itertools::Itertools;
+use netstat2::TcpState;
+use serde::{Deserialize, Serialize};
+
+use crate::os::{BindScope, Protocol, SocketInfoWithProcName};
+
+#[derive(Clone, Copy, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum Transport {
+    Tcp,
+    Udp,
+}
+
+impl Transport {
+    fn protocol(self) -> Protocol {
+        match self {
+            Transport::Tcp => Protocol::TCP,
+            Transport::Udp => Protocol::UDP,
+        }
+    }
+}
+
+/// Listeners on a port, narrowed down by the other fields.
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ListenRule {
+    port: u16,
+    protocol: Option<Transport>,
+    address: Option<IpAddr>,
+    /// For must_listen, every listener on the port has to be in this scope.
+    scope: Option<BindScope>,
+    process: Option<String>,
+}
+
+impl ListenRule {
+    /// Whether the socket is a listener of this rule, leaving the scope out.
+    fn matches(&self, socket: &SocketInfoWithProcName) -> bool {
+        self.matches_port(socket)
+            && self
+                .process
+                .as_ref()
+                .is_none_or(|process| *process == socket.process_name)
+    }
+
+    /// Whether the socket listens on the port of this rule, by any process.
+    fn matches_port(&self, socket: &SocketInfoWithProcName) -> bool {
+        socket.is_listener()
+            && socket.local_port() == Some(self.port)
+            && self
+                .protocol
+                .is_none_or(|protocol| protocol.protocol() == socket.protocol_flags)
+            && self
+                .address
+                .is_none_or(|address| socket.is_bound_to(address))
+    }
+
+    /// `only` is for must_listen, where the scope is the only one allowed.
+    fn describe(&self, only: bool) -> String {
+        let mut text = match self.protocol {
+            Some(Transport::Tcp) => format!("tcp {}", self.port),
+            Some(Transport::Udp) => format!("udp {}", self.port),
+            None => format!("port {}", self.port),
+        };
+
+        if let Some(address) = self.address {
+            text.push_str(&format!(" on {}", address));
+        }
+
+        match (self.scope, only) {
+            (Some(scope), true) => text.push_str(&format!(" ({} only)", scope)),
+            (Some(scope), false) => text.push_str(&format!(" ({})", scope)),
+            (None, _) => {}
+        }
+
+        if let Some(process) = &self.process {
+            text.push_str(&format!(" by {}", process));
+        }
+
+        text
+    }
+}
+
+/// At most `max` established TCP connections to a remote endpoint.
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct MaxConnectionsRule {
+    host: Option<IpAddr>,
+    port: u16,
+    max: usize,
+    process: Option<String>,
+}
+
+impl MaxConnectionsRule {
+    fn matches(&self, socket: &SocketInfoWithProcName) -> bool {
+        let remote = match socket.tcp_endpoints() {
+            Some((_, remote)) if socket.tcp_state() == Some(TcpState::Established) => remote,
+            _ => return false,
+        };
+
+        remote.port() == self.port
+            // IPv6 sockets see IPv4 peers as mapped addresses
+            && self.host.is_none_or(|host| remote.ip().to_canonical() == host.to_canonical())
+            && self
+                .process
+                .as_ref()
+                .is_none_or(|process| *process == socket.process_name)
+    }
+
+    fn describe(&self) -> String {
+        let host = self
+            .host
+            .map_or_else(|| String::from("*"), |host| host.to_string());
+
+        let mut text = format!("at most {} to {}:{}", self.max, host, self.port);
+
+        if let Some(process) = &self.process {
+            text.push_str(&format!(" from {}", process));
+        }
+
+        text
+    }
+}
+
+/// A rules file, every kind of rule is optional.
+#[derive(Deserialize, Default)]
+#[serde(deny_unknown_fields)]
+pub struct Rules {
+    #[serde(default)]
+    must_listen: Vec<ListenRule>,
+    #[serde(default)]
+    must_not_listen: Vec<ListenRule>,
+    #[serde(default)]
+    max_connections_to: Vec<MaxConnectionsRule>,
+    /// Processes that must not have any socket open.
+    #[serde(default)]
+    no_process: Vec<String>,
+}
+
+/// The outcome of one rule, with what broke it.
+#[derive(Serialize)]
+pub struct RuleResult {
+    pub kind: &'static str,
+    pub rule: String,
+    pub passed: bool,
+    pub failures: Vec<String>,
+}
+
+impl RuleResult {
+    fn new(kind: &'static str, rule: String, failures: Vec<String>) -> RuleResult {
+        RuleResult {
+            kind,
+            rule,
+            passed: failures.is_empty(),
+            failures,
+        }
+    }
+}
+
+/// Why some sockets show no process, for the rules that can't pass without it.
+const UNATTRIBUTED_HINT: &str = "Run as root or see nets doctor.";
+
+fn local_addr(socket: &SocketInfoWithProcName) -> Option<IpAddr> {
+    Some(socket.inet()?.local_addr())
+}
+
+/// e.g. `redis-server (pid 812) on 0.0.0.0:6379`
+fn describe_socket(socket: &SocketInfoWithProcName) -> String {
+    let process = match socket.pid() {
+        Some(pid) => format!("{} (pid {})", socket.process_name, pid),
+        None => String::from("an unknown process"),
+    };
+
+    match (local_addr(socket), socket.local_port()) {
+        (Some(addr), Some(port)) => format!("{} on {}", process, SocketAddr::new(addr, port)),
+        _ => process,
+    }
+}
+
+impl Rules {
+    pub fn load(path: &Path) -> Result<Rules, String> {
+        let contents = std::fs::read_to_string(path)
+            .map_err(|e| format!("can't read rules {}: {}", path.display(), e))?;
+
+        let rules: Rules = toml::from_str(&contents)
+            .map_err(|e| format!("invalid rules {}: {}", path.display(), e))?;
+
+        if rules.is_empty() {
+            return Err(format!("{} has no rules", path.display()));
+        }
+
+        Ok(rules)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.must_listen.is_empty()
+            && self.must_not_listen.is_empty()
+            && self.max_connections_to.is_empty()
+            && self.no_process.is_empty()
+    }
+
+    /// Checks every rule against a snapshot, in the order of the kinds of rules.
+    pub fn evaluate(&self, sockets: &[SocketInfoWithProcName]) -> Vec<RuleResult> {
+        let mut results = Vec::new();
+
+        for rule in &self.must_listen {
+            let listeners: Vec<&SocketInfoWithProcName> = sockets
+                .iter()
+                .filter(|socket| rule.matches(socket))
+                .collect();
+
+            let failures = match rule.scope {
+                _ if listeners.is_empty() => vec![String::from("nothing listens")],
+                Some(scope) => {
+                    let mut failures: Vec<String> = listeners
+                        .iter()
+                        .filter(|socket| socket.bind_scope() != Some(scope))
+                        .map(|socket| format!("{} is not {}", describe_socket(socket), scope))
+                        .collect();
+
+                    if listeners.len() == failures.len() {
+                        failures.push(format!("nothing listens on {}", scope));
+                    }
+
+                    failures
+                }
+                None => Vec::new(),
+            };
+
+            results.push(RuleResult::new(
+                "must_listen",
+                rule.describe(true),
+                failures,
+            ));
+        }
+
+        for rule in &self.must_not_listen {
+            let failures = sockets
+                .iter()
+                .filter(|socket| {
+                    rule.scope
+                        .is_none_or(|scope| socket.bind_scope() == Some(scope))
+                })
+                .filter_map(|socket| match &rule.process {
+                    _ if rule.matches(socket) => {
+                        Some(format!("{} listens", describe_socket(socket)))
+                    }
+                    // Without privileges the listener could be the process of the rule
+                    Some(process) if rule.matches_port(socket) && socket.is_unattributed() => {
+                        Some(format!(
+                            "{} listens, it could be {}. {}",
+                            describe_socket(socket),
+                            process,
+                            UNATTRIBUTED_HINT
+                        ))
+                    }
+                    _ => None,
+                })
+                .collect();
+
+            results.push(RuleResult::new(
+                "must_not_listen",
+                rule.describe(false),
+                failures,
+            ));
+        }
+
+        for rule in &self.max_connections_to {
+            let count = sockets.iter().filter(|socket| rule.matches(socket)).count();
+
+            let failures = match count > rule.max {
+                true => vec![format!("{} established connections", count)],
+                false => Vec::new(),
+            };
+
+            results.push(RuleResult::new(
+                "max_connections_to",
+                rule.describe(),
+                failures,
+            ));
+        }
+
+        for name in &self.no_process {
+            let owned: Vec<&SocketInfoWithProcName> = sockets
+                .iter()
+                .filter(|socket| socket.process_name == *name)
+                .collect();
+
+            let mut failures = match owned.is_empty() {
+                true => Vec::new(),
+                false => vec![format!(
+                    "pid {} has {} sockets",
+                    owned
+                        .iter()
+                        .filter_map(|socket| socket.pid())
+                        .unique()
+                        .join(", "),
+                    owned.len()
+                )],
+            };
+
+            // The process can't be ruled out when some sockets have none
+            let unattributed = sockets
+                .iter()
+                .filter(|socket| socket.is_unattributed())
+                .count();
+
+            if unattributed > 0 {
+                failures.push(format!(
+                    "{} sockets show no process, some could be {}'s. {}",
+                    unattributed, name, UNATTRIBUTED_HINT
+                ));
+            }
+
+            results.push(RuleResult::new("no_process", name.clone(), failures));
+        }
+
+        results
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use netstat2::TcpState;
+
+    use super::*;
+    use crate::os::fixtures::{dual_stack, owned_by, tcp, tcp_listener, udp};
+
+    fn evaluate(rules: &str, sockets: &[SocketInfoWithProcName]) -> Vec<(String, bool)> {
+        let rules: Rules = toml::from_str(rules).unwrap();
+
+        rules
+            .evaluate(sockets)
+            .into_iter()
+            .map(|result| (result.rule, result.passed))
+            .collect()
+    }
+
+    #[test]
+    fn must_listen_checks_the_scope_of_every_listener() {
+        let rules = "[[must_listen]]\nport = 5432\nscope = \"loopback\"";
+
+        let loopback = [tcp_listener("127.0.0.1:5432", "postgres")];
+        assert_eq!(
+            evaluate(rules, &loopback),
+            [(String::from("port 5432 (loopback only)"), true)]
+        );
+
+        let both = [
+            tcp_listener("127.0.0.1:5432", "postgres"),
+            tcp_listener("0.0.0.0:5432", "postgres"),
+        ];
+        assert!(!evaluate(rules, &both)[0].1);
+        assert!(!evaluate(rules, &[])[0].1);
+    }
+
+    #[test]
+    fn must_listen_narrows_by_protocol_and_process() {
+        let rules = "[[must_listen]]\nport = 53\nprotocol = \"udp\"\nprocess = \"unbound\"";
+
+        assert!(evaluate(rules, &[udp("127.0.0.1:53", "unbound")])[0].1);
+        assert!(!evaluate(rules, &[tcp_listener("127.0.0.1:53", "unbound")])[0].1);
+        assert!(!evaluate(rules, &[udp("127.0.0.1:53", "dnsmasq")])[0].1);
+    }
+
+    #[test]
+    fn must_not_listen_on_the_ipv4_wildcard_matches_dual_stack_listeners() {
+        let rules = "[[must_not_listen]]\nport = 6379\naddress = \"0.0.0.0\"";
+
+        assert!(!evaluate(rules, &[tcp_listener("0.0.0.0:6379", "redis")])[0].1);
+        assert!(
+            !evaluate(
+                rules,
+                &[dual_stack(tcp_listener("[::]:6379", "redis"), true)]
+            )[0]
+            .1
+        );
+        assert!(
+            evaluate(
+                rules,
+                &[dual_stack(tcp_listener("[::]:6379", "redis"), false)]
+            )[0]
+            .1
+        );
+        assert!(evaluate(rules, &[tcp_listener("127.0.0.1:6379", "redis")])[0].1);
+    }
+
+    #[test]
+    fn must_not_listen_by_scope() {
+        let rules = "[[must_not_listen]]\nport = 6379\nscope = \"all\"";
+
+        assert!(!evaluate(rules, &[tcp_listener("[::]:6379", "redis")])[0].1);
+        assert!(evaluate(rules, &[tcp_listener("[::1]:6379", "redis")])[0].1);
+    }
+
+    #[test]
+    fn max_connections_to_counts_ipv4_mapped_peers() {
+        let rules = "[[max_connections_to]]\nhost = \"10.0.0.5\"\nport = 5432\nmax = 1";
+
+        let connections = [
+            tcp(
+                "10.0.0.1:40000",
+                "10.0.0.5:5432",
+                TcpState::Established,
+                "app",
+            ),
+            tcp(
+                "[::ffff:10.0.0.1]:40001",
+                "[::ffff:10.0.0.5]:5432",
+                TcpState::Established,
+                "app",
+            ),
+            tcp("10.0.0.1:40002", "10.0.0.5:5432", TcpState::TimeWait, "app"),
+        ];
+
+        assert!(evaluate(rules, &connections[..1])[0].1);
+        assert_eq!(
+            evaluate(rules, &connections),
+            [(String::from("at most 1 to 10.0.0.5:5432"), false)]
+        );
+    }
+
+    #[test]
+    fn no_process_fails_on_any_socket() {
+        let rules = "no_process = [\"telnetd\"]";
+
+        assert!(evaluate(rules, &[udp("0.0.0.0:68", "dhclient")])[0].1);
+        assert!(!evaluate(rules, &[tcp_listener("0.0.0.0:23", "telnetd")])[0].1);
+    }
+
+    #[test]
+    fn no_process_fails_when_sockets_have_no_process() {
+        let rules = "no_process = [\"telnetd\"]";
+
+        assert!(evaluate(rules, &[tcp_listener("0.0.0.0:22", "sshd")])[0].1);
+        assert!(!evaluate(rules, &[tcp_listener("0.0.0.0:23", "telnetd")])[0].1);
+
+        let unattributed = [owned_by(tcp_listener("0.0.0.0:23", "telnetd"), None)];
+        let results = Rules {
+            no_process: vec![String::from("telnetd")],
+            ..Rules::default()
+        }
+        .evaluate(&unattributed);
+
+        assert!(!results[0].passed);
+        assert_eq!(
+            results[0].failures,
+            ["1 sockets show no process, some could be telnetd's. Run as root or see nets doctor."]
+        );
+    }
+
+    #[test]
+    fn must_not_listen_by_process_fails_on_unknown_listeners() {
+        let rules = "[[must_not_listen]]\nport = 6379\nprocess = \"redis-server\"";
+
+        assert!(evaluate(rules, &[tcp_listener("0.0.0.0:6379", "keydb")])[0].1);
+        assert!(!evaluate(rules, &[tcp_listener("0.0.0.0:6379", "redis-server")])[0].1);
+        assert!(
+            !evaluate(
+                rules,
+                &[owned_by(tcp_listener("0.0.0.0:6379", "redis-server"), None)]
+            )[0]
+            .1
+        );
+        // Only the port of the rule
+        assert!(
+            evaluate(
+                rules,
+                &[owned_by(tcp_listener("0.0.0.0:6380", "redis-server"), None)]
+            )[0]
+            .1
+        );
+    }
+}
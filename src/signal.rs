@@ -0,0 +1,104 @@
+// Signals sent to the processes that own sockets.
+use std::io;
+
+/// Signals that can be given by name, without their SIG prefix.
+pub const SIGNALS: [(&str, libc::c_int); 10] = [
+    ("TERM", libc::SIGTERM),
+    ("KILL", libc::SIGKILL),
+    ("HUP", libc::SIGHUP),
+    ("INT", libc::SIGINT),
+    ("QUIT", libc::SIGQUIT),
+    ("USR1", libc::SIGUSR1),
+    ("USR2", libc::SIGUSR2),
+    ("STOP", libc::SIGSTOP),
+    ("CONT", libc::SIGCONT),
+    ("PIPE", libc::SIGPIPE),
+];
+
+/// Parses a signal name like `TERM`, `SIGKILL` or `hup`, or a signal number.
+pub fn parse_signal(text: &str) -> Option<libc::c_int> {
+    if let Ok(number) = text.parse::<libc::c_int>() {
+        return (1..=64).contains(&number).then_some(number);
+    }
+
+    let upper = text.to_uppercase();
+    let name = upper.strip_prefix("SIG").unwrap_or(&upper);
+
+    SIGNALS
+        .iter()
+        .find(|(signal, _)| *signal == name)
+        .map(|(_, number)| *number)
+}
+
+pub fn signal_name(signal: libc::c_int) -> String {
+    match SIGNALS.iter().find(|(_, number)| *number == signal) {
+        Some((name, _)) => name.to_string(),
+        None => signal.to_string(),
+    }
+}
+
+pub fn send_signal(pid: u32, signal: libc::c_int) -> io::Result<()> {
+    let pid = libc::pid_t::try_from(pid)
+        .map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "invalid pid"))?;
+
+    match unsafe { libc::kill(pid, signal) } {
+        0 => Ok(()),
+        _ => Err(io::Error::last_os_error()),
+    }
+}
+
+/// Whether the process is still running, found with the null signal. Processes of other
+/// users exist too, they just refuse it.
+pub fn process_exists(pid: u32) -> bool {
+    match send_signal(pid, 0) {
+        Ok(()) => true,
+        Err(e) => e.raw_os_error() != Some(libc::ESRCH),
+    }
+}
+
+/// Sends a signal to every pid, describing what was sent or which pids refused it.
+pub fn signal_processes(
+    pids: &[u32],
+    signal: libc::c_int,
+    process_name: &str,
+) -> Result<String, String> {
+    let name = signal_name(signal);
+    let mut sent = Vec::new();
+    let mut failed = Vec::new();
+
+    for pid in pids {
+        match send_signal(*pid, signal) {
+            Ok(()) => sent.push(pid.to_string()),
+            Err(e) => failed.push(format!("{}: {}", pid, describe_error(&e))),
+        }
+    }
+
+    match (sent.is_empty(), failed.is_empty()) {
+        (_, true) => Ok(format!(
+            "sent {} to {} ({})",
+            name,
+            sent.join(", "),
+            process_name
+        )),
+        (true, false) => Err(format!(
+            "can't send {} to {}: {}",
+            name,
+            process_name,
+            failed.join(", ")
+        )),
+        (false, false) => Err(format!(
+            "sent {} to {}, but {}",
+            name,
+            sent.join(", "),
+            failed.join(", ")
+        )),
+    }
+}
+
+fn describe_error(e: &io::Error) -> String {
+    match e.raw_os_error() {
+        Some(libc::EPERM) => String::from("permission denied (EPERM), it belongs to another user"),
+        Some(libc::ESRCH) => String::from("no such process (ESRCH), it has already exited"),
+        _ => e.to_string(),
+    }
+}
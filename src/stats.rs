@@ -0,0 +1,99 @@
+use std::collections::{HashSet, VecDeque};
+use std::net::SocketAddr;
+use std::time::Instant;
+
+use netstat2::TcpState;
+
+use crate::app::App;
+use crate::os::{Protocol, SocketInfoWithProcName};
+use crate::utils::*;
+
+/// Samples kept for the statistics panel, a few minutes at the default tick rate.
+const HISTORY_LENGTH: usize = 600;
+
+/// The header counters at one point in time.
+pub struct StatsSample {
+    pub time: Instant,
+    pub total: u64,
+    pub unique: u64,
+    pub tcp: u64,
+    pub udp: u64,
+    pub ipv4: u64,
+    pub ipv6: u64,
+    /// Number of sockets in each of `TCP_STATES`, in the same order.
+    pub states: Vec<u64>,
+    /// TCP connections that weren't there in the previous sample, per second.
+    pub new_per_second: f64,
+}
+
+impl StatsSample {
+    pub fn new(app: &App) -> StatsSample {
+        let count = |value: usize| value as u64;
+
+        StatsSample {
+            time: Instant::now(),
+            total: count(get_total_sockets_protocol_count(
+                app,
+                Protocol::TCP | Protocol::UDP,
+            )),
+            unique: count(get_total_sockets_unique_count(app)),
+            tcp: count(get_total_sockets_protocol_count(app, Protocol::TCP)),
+            udp: count(get_total_sockets_protocol_count(app, Protocol::UDP)),
+            ipv4: count(get_total_sockets_ip_count(app, AddressFamily::IPv4)),
+            ipv6: count(get_total_sockets_ip_count(app, AddressFamily::IPv6)),
+            states: TCP_STATES
+                .iter()
+                .map(|state| count(get_total_sockets_state_count(app, *state)))
+                .collect(),
+            new_per_second: 0.0,
+        }
+    }
+
+    pub fn state_count(&self, state: TcpState) -> u64 {
+        TCP_STATES
+            .iter()
+            .position(|s| *s == state)
+            .map_or(0, |i| self.states[i])
+    }
+}
+
+/// A rolling window of samples backing the statistics panel.
+#[derive(Default)]
+pub struct StatsHistory {
+    pub samples: VecDeque<StatsSample>,
+    connections: HashSet<(SocketAddr, SocketAddr)>,
+}
+
+impl StatsHistory {
+    pub fn record(&mut self, mut sample: StatsSample, connections: &[SocketInfoWithProcName]) {
+        let current: HashSet<(SocketAddr, SocketAddr)> = connections
+            .iter()
+            .filter(|connection| connection.tcp_state() != Some(TcpState::Listen))
+            .filter_map(|connection| connection.tcp_endpoints())
+            .collect();
+
+        // Everything would count as new on the first sample
+        if let Some(previous) = self.samples.back() {
+            let elapsed = sample.time.duration_since(previous.time).as_secs_f64();
+            let new = current.difference(&self.connections).count();
+
+            if elapsed > 0.0 {
+                sample.new_per_second = new as f64 / elapsed;
+            }
+        }
+
+        self.connections = current;
+        self.samples.push_back(sample);
+
+        if self.samples.len() > HISTORY_LENGTH {
+            self.samples.pop_front();
+        }
+    }
+
+    /// The most recent samples, oldest first.
+    pub fn last(&self, count: usize) -> impl Iterator<Item = &StatsSample> {
+        self.samples
+            .iter()
+            .skip(self.samples.len().saturating_sub(count))
+    }
+}
@@ -0,0 +1,294 @@
+// Colours and text styles used by the interface.
+use std::collections::BTreeMap;
+
+use netstat2::TcpState;
+use serde::Deserialize;
+use tui::style::{Color, Modifier, Style};
+
+use crate::utils::TCP_STATES;
+
+pub const BUILTIN_THEMES: [&str; 4] = ["dark", "light", "high-contrast", "monochrome"];
+
+#[derive(Clone, Copy)]
+pub struct Theme {
+    /// Titles and labels
+    pub title: Style,
+    /// Counts in the header and the keys in the help
+    pub value: Style,
+    /// Totals, rates and match counts
+    pub highlight: Style,
+    /// Persistent queues, paused refreshes and the filter while typing
+    pub warning: Style,
+    /// Saturated listeners, abnormal state counts and invalid filters
+    pub error: Style,
+    /// Things that are empty, e.g. zero state counts
+    pub muted: Style,
+    /// The selected row, tab or state
+    pub selected: Style,
+    /// Colour of each TCP state, in the order of `TCP_STATES`
+    pub states: [Color; TCP_STATES.len()],
+}
+
+/// A user theme in the config, every setting overrides its `base` theme.
+#[derive(Deserialize, Default)]
+#[serde(deny_unknown_fields)]
+pub struct ThemeFile {
+    base: Option<String>,
+    title: Option<String>,
+    value: Option<String>,
+    highlight: Option<String>,
+    warning: Option<String>,
+    error: Option<String>,
+    muted: Option<String>,
+    selected: Option<String>,
+    states: Option<BTreeMap<String, String>>,
+}
+
+impl Theme {
+    pub fn builtin(name: &str) -> Option<Theme> {
+        match name {
+            "dark" => Some(Theme::dark()),
+            "light" => Some(Theme::light()),
+            "high-contrast" => Some(Theme::high_contrast()),
+            "monochrome" => Some(Theme::monochrome()),
+            _ => None,
+        }
+    }
+
+    /// Looks `name` up in the user themes first, then in the built-in ones.
+    pub fn resolve(name: &str, themes: &BTreeMap<String, ThemeFile>) -> Result<Theme, String> {
+        match themes.get(name) {
+            Some(file) => file.apply(name),
+            None => Theme::builtin(name).ok_or_else(|| {
+                format!(
+                    "unknown theme '{}', expected {} or a theme from [themes]",
+                    name,
+                    BUILTIN_THEMES.join(", ")
+                )
+            }),
+        }
+    }
+
+    /// Colours are turned off when `NO_COLOR` is set or the terminal can't show them.
+    pub fn is_color_disabled() -> bool {
+        let no_color = std::env::var_os("NO_COLOR").is_some_and(|value| !value.is_empty());
+        let dumb = std::env::var("TERM").map_or(true, |term| term == "dumb");
+
+        no_color || dumb
+    }
+
+    pub fn state(&self, state: TcpState) -> Color {
+        TCP_STATES
+            .iter()
+            .position(|s| *s == state)
+            .map_or(Color::Reset, |i| self.states[i])
+    }
+
+    pub fn state_style(&self, state: TcpState) -> Style {
+        Style::default().fg(self.state(state))
+    }
+
+    fn dark() -> Theme {
+        Theme {
+            title: Style::default().add_modifier(Modifier::BOLD),
+            value: Style::default()
+                .fg(Color::Yellow)
+                .add_modifier(Modifier::BOLD),
+            highlight: Style::default()
+                .fg(Color::Green)
+                .add_modifier(Modifier::BOLD),
+            warning: Style::default().fg(Color::Yellow),
+            error: Style::default().fg(Color::Red).add_modifier(Modifier::BOLD),
+            muted: Style::default().fg(Color::DarkGray),
+            selected: Style::default().add_modifier(Modifier::REVERSED),
+            states: [
+                Color::Green,    // Established
+                Color::Cyan,     // SynSent
+                Color::Cyan,     // SynReceived
+                Color::Magenta,  // FinWait1
+                Color::Magenta,  // FinWait2
+                Color::Yellow,   // TimeWait
+                Color::Gray,     // Closed
+                Color::Red,      // CloseWait
+                Color::LightRed, // LastAck
+                Color::Blue,     // Listen
+                Color::LightRed, // Closing
+            ],
+        }
+    }
+
+    /// Avoids yellow and light colours, which are hard to read on a white background.
+    fn light() -> Theme {
+        Theme {
+            value: Style::default()
+                .fg(Color::Blue)
+                .add_modifier(Modifier::BOLD),
+            warning: Style::default().fg(Color::Magenta),
+            muted: Style::default().fg(Color::Gray),
+            states: [
+                Color::Green,
+                Color::Cyan,
+                Color::Cyan,
+                Color::Magenta,
+                Color::Magenta,
+                Color::DarkGray,
+                Color::Gray,
+                Color::Red,
+                Color::Red,
+                Color::Blue,
+                Color::Red,
+            ],
+            ..Theme::dark()
+        }
+    }
+
+    fn high_contrast() -> Theme {
+        Theme {
+            title: Style::default()
+                .fg(Color::White)
+                .add_modifier(Modifier::BOLD),
+            value: Style::default()
+                .fg(Color::White)
+                .add_modifier(Modifier::BOLD),
+            highlight: Style::default()
+                .fg(Color::LightGreen)
+                .add_modifier(Modifier::BOLD),
+            warning: Style::default()
+                .fg(Color::LightYellow)
+                .add_modifier(Modifier::BOLD),
+            error: Style::default()
+                .fg(Color::LightRed)
+                .add_modifier(Modifier::BOLD | Modifier::UNDERLINED),
+            muted: Style::default().fg(Color::Gray),
+            selected: Style::default().add_modifier(Modifier::REVERSED | Modifier::BOLD),
+            states: [
+                Color::LightGreen,
+                Color::LightCyan,
+                Color::LightCyan,
+                Color::LightMagenta,
+                Color::LightMagenta,
+                Color::LightYellow,
+                Color::White,
+                Color::LightRed,
+                Color::LightRed,
+                Color::LightBlue,
+                Color::LightRed,
+            ],
+        }
+    }
+
+    /// Only text attributes, for NO_COLOR and terminals without colours.
+    pub fn monochrome() -> Theme {
+        Theme {
+            title: Style::default().add_modifier(Modifier::BOLD),
+            value: Style::default().add_modifier(Modifier::BOLD),
+            highlight: Style::default().add_modifier(Modifier::BOLD),
+            warning: Style::default().add_modifier(Modifier::UNDERLINED),
+            error: Style::default().add_modifier(Modifier::BOLD | Modifier::UNDERLINED),
+            muted: Style::default().add_modifier(Modifier::DIM),
+            selected: Style::default().add_modifier(Modifier::REVERSED),
+            states: [Color::Reset; TCP_STATES.len()],
+        }
+    }
+}
+
+impl ThemeFile {
+    fn apply(&self, name: &str) -> Result<Theme, String> {
+        let base = self.base.as_deref().unwrap_or("dark");
+
+        let mut theme = Theme::builtin(base).ok_or_else(|| {
+            format!(
+                "themes.{}.base: unknown theme '{}', expected {}",
+                name,
+                base,
+                BUILTIN_THEMES.join(", ")
+            )
+        })?;
+
+        let styles = [
+            ("title", &self.title, &mut theme.title),
+            ("value", &self.value, &mut theme.value),
+            ("highlight", &self.highlight, &mut theme.highlight),
+            ("warning", &self.warning, &mut theme.warning),
+            ("error", &self.error, &mut theme.error),
+            ("muted", &self.muted, &mut theme.muted),
+            ("selected", &self.selected, &mut theme.selected),
+        ];
+
+        // Only the colour changes, the base theme keeps its bold, reversed, etc.
+        for (key, color, style) in styles {
+            if let Some(color) = color {
+                *style = style
+                    .fg(parse_color(color)
+                        .map_err(|e| format!("themes.{}.{}: {}", name, key, e))?);
+            }
+        }
+
+        for (key, color) in self.states.iter().flatten() {
+            let i = TCP_STATES
+                .iter()
+                .position(|state| state.to_string().eq_ignore_ascii_case(key))
+                .ok_or_else(|| {
+                    format!(
+                        "themes.{}.states: unknown state '{}', expected one of {}",
+                        name,
+                        key,
+                        TCP_STATES
+                            .iter()
+                            .map(|state| state.to_string().to_lowercase())
+                            .collect::<Vec<_>>()
+                            .join(", ")
+                    )
+                })?;
+
+            theme.states[i] =
+                parse_color(color).map_err(|e| format!("themes.{}.states.{}: {}", name, key, e))?;
+        }
+
+        Ok(theme)
+    }
+}
+
+/// A colour name (e.g. red, lightblue), a 256 colour index or a #rrggbb colour.
+fn parse_color(text: &str) -> Result<Color, String> {
+    if let Some(hex) = text.strip_prefix('#') {
+        let rgb = u32::from_str_radix(hex, 16)
+            .ok()
+            .filter(|_| hex.len() == 6)
+            .ok_or_else(|| format!("invalid colour '{}', expected #rrggbb", text))?;
+
+        return Ok(Color::Rgb((rgb >> 16) as u8, (rgb >> 8) as u8, rgb as u8));
+    }
+
+    if let Ok(index) = text.parse::<u8>() {
+        return Ok(Color::Indexed(index));
+    }
+
+    let color = match text.to_lowercase().replace(['-', '_', ' '], "").as_str() {
+        "default" | "reset" => Color::Reset,
+        "black" => Color::Black,
+        "red" => Color::Red,
+        "green" => Color::Green,
+        "yellow" => Color::Yellow,
+        "blue" => Color::Blue,
+        "magenta" => Color::Magenta,
+        "cyan" => Color::Cyan,
+        "gray" | "grey" => Color::Gray,
+        "darkgray" | "darkgrey" => Color::DarkGray,
+        "lightred" => Color::LightRed,
+        "lightgreen" => Color::LightGreen,
+        "lightyellow" => Color::LightYellow,
+        "lightblue" => Color::LightBlue,
+        "lightmagenta" => Color::LightMagenta,
+        "lightcyan" => Color::LightCyan,
+        "white" => Color::White,
+        _ => {
+            return Err(format!(
+                "unknown colour '{}', expected a name like red or lightblue, 0-255 or #rrggbb",
+                text
+            ))
+        }
+    };
+
+    Ok(color)
+}
@@ -1,15 +1,23 @@
+mod stats;
+
 use crate::utils::*;
 
 use super::app::App;
-use super::app::FilterMode;
-use super::app::ITEMS;
+use super::app::{FilterMode, KillDialog, NamespaceView, ScreenAreas};
+use crate::command::COMMANDS;
+use crate::config::LayoutMode;
+
+use crate::columns::Column;
+use crate::os::{Protocol, SocketInfoWithProcName};
+use crate::signal::signal_name;
+
+use itertools::Itertools;
 
-use netstat2::ProtocolFlags;
 use tui::layout::Alignment;
 use tui::{
-    style::{Color, Modifier, Style},
+    style::Style,
     text::{Span, Spans},
-    widgets::{Block, Borders, Cell, List, ListItem, Paragraph, Row, Table, Tabs, Wrap},
+    widgets::{Block, Borders, Cell, Clear, List, ListItem, Paragraph, Row, Table, Tabs, Wrap},
     Frame,
 };
 
@@ -21,134 +29,214 @@ use tui::{
 use unicode_width::UnicodeWidthStr;
 
 #[derive(Default)]
-pub struct TableHeaderItem<'a> {
-    text: &'a str,
+pub struct TableHeaderItem {
+    text: String,
     width: u16,
 }
 
-pub struct TableHeader<'a> {
-    items: Vec<TableHeaderItem<'a>>,
+pub struct TableHeader {
+    items: Vec<TableHeaderItem>,
 }
 
+/// Terminals narrower than this use the compact layout when it is set to auto.
+const COMPACT_WIDTH: u16 = 100;
+
 pub fn get_percentage_width(width: u16, percentage: f32) -> u16 {
     let padding = 3;
-    let width = width - padding;
+    let width = width.saturating_sub(padding);
     (f32::from(width) * percentage) as u16
 }
 
 pub fn draw_ui<B: Backend>(f: &mut Frame<B>, app: &mut App) {
     app.update_connections();
+    app.areas = ScreenAreas::default();
+    app.compact = match app.config.layout {
+        LayoutMode::Auto => f.size().width < COMPACT_WIDTH,
+        LayoutMode::Compact => true,
+        LayoutMode::Full => false,
+    };
+
+    // The compact layout leaves out the user counts to keep rows for the table
+    let header_height = match app.compact {
+        true => 4,
+        false => 5,
+    };
+
+    let mut constraints = vec![Constraint::Length(header_height), Constraint::Length(3)];
+
+    if app.show_stats {
+        constraints.push(Constraint::Length(12));
+    }
+
+    // Explains why rows have no process when nets isn't privileged enough to find them
+    let unattributed = get_total_unattributed_sockets(app);
+    let banner = !app.missing_capabilities.is_empty() && unattributed > 0;
+
+    if banner {
+        constraints.push(Constraint::Length(1));
+    }
+
+    constraints.push(Constraint::Min(0));
+
+    // A line at the bottom for the command being typed or the outcome of the last one
+    let command_line =
+        matches!(app.filter.mode, FilterMode::Command) || app.command.message.is_some();
+
+    if command_line {
+        constraints.push(Constraint::Length(1));
+    }
 
     let chunks = Layout::default()
         .direction(Direction::Vertical)
-        .constraints(
-            [
-                Constraint::Length(3),
-                Constraint::Length(3),
-                Constraint::Percentage(100),
-            ]
-            .as_ref(),
-        )
+        .constraints(constraints.as_slice())
         .split(f.size());
 
+    let chunks = match command_line {
+        true => {
+            draw_command_line(f, app, chunks[chunks.len() - 1]);
+            &chunks[..chunks.len() - 1]
+        }
+        false => &chunks[..],
+    };
+
     draw_header(f, app, chunks[0]);
     draw_filter_field(f, app, chunks[1]);
-    draw_connections(f, app, chunks[2]);
+
+    if app.show_stats {
+        stats::draw_stats(f, app, chunks[2]);
+    }
+
+    if banner {
+        let text = format!(
+            "{} sockets of other users show no process, finding it needs {}. Run as root or see nets doctor.",
+            unattributed,
+            app.missing_capabilities.join(" and ")
+        );
+
+        f.render_widget(
+            Paragraph::new(Span::styled(text, app.config.theme.warning)),
+            chunks[chunks.len() - 2],
+        );
+    }
+
+    draw_connections(f, app, chunks[chunks.len() - 1]);
+
+    if app.kill_dialog.is_some() {
+        draw_kill_dialog(f, app);
+    } else if app.close_dialog.is_some() {
+        draw_close_dialog(f, app);
+    }
 }
 
 fn draw_header<B>(f: &mut Frame<B>, app: &mut App, area: Rect)
 where
     B: Backend,
 {
+    let rows = Layout::default()
+        .constraints([
+            Constraint::Length(3),
+            Constraint::Length(1),
+            Constraint::Length(1),
+        ])
+        .direction(Direction::Vertical)
+        .split(area);
+
+    draw_state_counts(f, app, rows[1]);
+
+    if !app.compact {
+        draw_user_counts(f, app, rows[2]);
+    }
+
     let chunks = Layout::default()
         .constraints([Constraint::Percentage(75), Constraint::Percentage(25)])
         .direction(Direction::Horizontal)
-        .split(area);
+        .split(rows[0]);
 
-    let text: Vec<Spans> = vec![
-        // FIXME: There has to be a better way to get new lines printed
-        Spans::from(Span::styled(String::new(), Style::default())),
-        Spans::from(vec![
-            Span::styled("Total ", Style::default().add_modifier(Modifier::BOLD)),
-            Span::from("Connections "),
-            Span::styled(
-                format!(
-                    "{}",
-                    get_total_sockets_protocol_count(app, ProtocolFlags::TCP | ProtocolFlags::UDP)
-                ),
-                Style::default()
-                    .fg(Color::Green)
-                    .add_modifier(Modifier::BOLD),
-            ),
-            Span::from(" | Unique "),
-            Span::styled(
-                format!("{}", get_total_sockets_unique_count(app)),
-                Style::default()
-                    .fg(Color::Yellow)
-                    .add_modifier(Modifier::BOLD),
-            ),
-            Span::from(" | TCP "),
-            Span::styled(
-                format!(
-                    "{}",
-                    get_total_sockets_protocol_count(app, ProtocolFlags::TCP)
-                ),
-                Style::default()
-                    .fg(Color::Yellow)
-                    .add_modifier(Modifier::BOLD),
-            ),
-            Span::from(" | UDP "),
-            Span::styled(
-                format!(
-                    "{}",
-                    get_total_sockets_protocol_count(app, ProtocolFlags::UDP)
-                ),
-                Style::default()
-                    .fg(Color::Yellow)
-                    .add_modifier(Modifier::BOLD),
-            ),
-            Span::from(" | Established "),
-            Span::styled(
-                format!(
-                    "{}",
-                    get_total_sockets_state_count(app, netstat2::TcpState::Established)
-                ),
-                Style::default()
-                    .fg(Color::Yellow)
-                    .add_modifier(Modifier::BOLD),
-            ),
-            Span::from(" | Listening "),
-            Span::styled(
-                format!(
-                    "{}",
-                    get_total_sockets_state_count(app, netstat2::TcpState::Listen)
-                ),
-                Style::default()
-                    .fg(Color::Yellow)
-                    .add_modifier(Modifier::BOLD),
+    let theme = app.config.theme;
+
+    // Wraps onto the second line when the terminal is too narrow for every count
+    let text: Vec<Spans> = vec![Spans::from(vec![
+        Span::styled("Total ", theme.title),
+        Span::from("Connections "),
+        Span::styled(
+            format!(
+                "{}",
+                get_total_sockets_protocol_count(app, Protocol::TCP | Protocol::UDP)
             ),
-            Span::from(" | IPv4 "),
-            Span::styled(
-                format!("{}", get_total_sockets_ip_count(app, AddressFamily::IPv4)),
-                Style::default()
-                    .fg(Color::Yellow)
-                    .add_modifier(Modifier::BOLD),
+            theme.highlight,
+        ),
+        Span::from(" | Unique "),
+        Span::styled(
+            format!("{}", get_total_sockets_unique_count(app)),
+            theme.value,
+        ),
+        Span::from(" | TCP "),
+        Span::styled(
+            format!("{}", get_total_sockets_protocol_count(app, Protocol::TCP)),
+            theme.value,
+        ),
+        Span::from(" | UDP "),
+        Span::styled(
+            format!("{}", get_total_sockets_protocol_count(app, Protocol::UDP)),
+            theme.value,
+        ),
+        Span::from(" | Unix "),
+        Span::styled(
+            format!("{}", get_total_sockets_protocol_count(app, Protocol::UNIX)),
+            theme.value,
+        ),
+        Span::from(" | Established "),
+        Span::styled(
+            format!(
+                "{}",
+                get_total_sockets_state_count(app, netstat2::TcpState::Established)
             ),
-            Span::from(" | IPv6 "),
-            Span::styled(
-                format!("{}", get_total_sockets_ip_count(app, AddressFamily::IPv6)),
-                Style::default()
-                    .fg(Color::Yellow)
-                    .add_modifier(Modifier::BOLD),
+            theme.value,
+        ),
+        Span::from(" | Listening "),
+        Span::styled(
+            format!(
+                "{}",
+                get_total_sockets_state_count(app, netstat2::TcpState::Listen)
             ),
-        ]),
-    ];
+            theme.value,
+        ),
+        Span::from(" | IPv4 "),
+        Span::styled(
+            format!("{}", get_total_sockets_ip_count(app, AddressFamily::IPv4)),
+            theme.value,
+        ),
+        Span::from(" | IPv6 "),
+        Span::styled(
+            format!("{}", get_total_sockets_ip_count(app, AddressFamily::IPv6)),
+            theme.value,
+        ),
+        Span::from(" | Saturated "),
+        Span::styled(
+            format!("{}", get_total_saturated_listeners(app)),
+            match get_total_saturated_listeners(app) {
+                0 => theme.value,
+                _ => theme.error,
+            },
+        ),
+        Span::from(" | Unattributed "),
+        Span::styled(
+            format!("{}", get_total_unattributed_sockets(app)),
+            match get_total_unattributed_sockets(app) {
+                0 => theme.value,
+                _ => theme.warning,
+            },
+        ),
+        Span::from(" | Rx "),
+        Span::styled(format_rate(get_total_throughput(app).rx), theme.highlight),
+        Span::from(" | Tx "),
+        Span::styled(format_rate(get_total_throughput(app).tx), theme.highlight),
+    ])];
 
     let text_box = Paragraph::new(text).wrap(Wrap { trim: true }).block(
-        Block::default().borders(Borders::NONE).title(Span::styled(
-            "nets",
-            Style::default().add_modifier(Modifier::BOLD),
-        )),
+        Block::default()
+            .borders(Borders::NONE)
+            .title(Span::styled("nets", theme.title)),
     );
 
     f.render_widget(text_box, chunks[0]);
@@ -156,38 +244,133 @@ where
     let help_title = Block::default()
         .title_alignment(Alignment::Center)
         .borders(Borders::NONE)
-        .title(Span::styled(
-            "Help (?)",
-            Style::default().add_modifier(Modifier::BOLD),
-        ));
+        .title(Span::styled("Help (?)", theme.title));
 
     f.render_widget(help_title, chunks[1]);
 }
 
+/// One count per TCP state, each of them can be clicked to filter the table to that state.
+fn draw_state_counts<B>(f: &mut Frame<B>, app: &mut App, area: Rect)
+where
+    B: Backend,
+{
+    let theme = app.config.theme;
+
+    let mut spans = vec![Span::styled("TCP States ", theme.title)];
+
+    let mut x = area.x + spans[0].width() as u16;
+
+    for state in TCP_STATES {
+        let count = get_total_sockets_state_count(app, state);
+        let label = format!("{} {}", state, count);
+
+        let mut style = if is_abnormal_state_count(state, count) {
+            theme.error
+        } else if count == 0 {
+            theme.muted
+        } else {
+            theme.value
+        };
+
+        if app.state_filter == Some(state) {
+            style = style.patch(theme.selected);
+        }
+
+        let width = label.width() as u16;
+
+        if x + width <= area.right() {
+            app.areas
+                .states
+                .push((Rect::new(x, area.y, width, 1), state));
+        }
+
+        x += width + 3;
+
+        spans.push(Span::styled(label, style));
+        spans.push(Span::from(" | "));
+    }
+
+    spans.pop();
+
+    f.render_widget(Paragraph::new(Spans::from(spans)), area);
+}
+
+/// Sockets per owner, to spot service accounts that shouldn't have any.
+fn draw_user_counts<B>(f: &mut Frame<B>, app: &mut App, area: Rect)
+where
+    B: Backend,
+{
+    let theme = app.config.theme;
+
+    let mut spans = vec![Span::styled("Users ", theme.title)];
+
+    let mut x = area.x + spans[0].width() as u16;
+
+    for (user, count) in get_sockets_per_user(app) {
+        let label = format!("{} {}", user, count);
+        let width = label.width() as u16;
+
+        if x + width > area.right() {
+            break;
+        }
+
+        let style = match app.filter.input == format!("user:{}", user) {
+            true => theme.value.patch(theme.selected),
+            false => theme.value,
+        };
+
+        app.areas.users.push((Rect::new(x, area.y, width, 1), user));
+
+        x += width + 3;
+
+        spans.push(Span::styled(label, style));
+        spans.push(Span::from(" | "));
+    }
+
+    // The separator after the last user, if any fit
+    if spans.len() > 1 {
+        spans.pop();
+    }
+
+    f.render_widget(Paragraph::new(Spans::from(spans)), area);
+}
+
 fn draw_filter_field<B>(f: &mut Frame<B>, app: &mut App, area: Rect)
 where
     B: Backend,
 {
+    // The tabs take the width of their titles, up to almost half of the screen
+    let tabs_width = app
+        .tabs
+        .items
+        .iter()
+        .map(|tab| tab.title.width() as u16 + 3)
+        .sum::<u16>()
+        + 1;
+
     let chunks = Layout::default()
         .constraints([
-            Constraint::Percentage(75),
-            Constraint::Percentage(12),
-            Constraint::Percentage(12),
+            Constraint::Min(0),
+            Constraint::Length(tabs_width.min((u32::from(area.width) * 45 / 100) as u16)),
+            Constraint::Percentage(14),
         ])
         .direction(Direction::Horizontal)
         .split(area);
 
+    let theme = app.config.theme;
+
     let input = Paragraph::new(app.filter.input.as_ref())
         .style(match app.filter.mode {
-            FilterMode::Normal => Style::default(),
-            FilterMode::Typing => Style::default().fg(Color::Yellow),
+            FilterMode::Normal | FilterMode::Command => Style::default(),
+            FilterMode::Typing => theme.warning,
         })
         .block(Block::default().borders(Borders::ALL).title("Filter"));
 
     f.render_widget(input, chunks[0]);
+    app.areas.filter = chunks[0];
 
     match app.filter.mode {
-        FilterMode::Normal => {}
+        FilterMode::Normal | FilterMode::Command => {}
 
         FilterMode::Typing => f.set_cursor(
             chunks[0].x + app.filter.input.width() as u16 + 1,
@@ -195,6 +378,19 @@ where
         ),
     }
 
+    // Every title is padded by a space on both sides and followed by a divider
+    let mut x = chunks[1].x + 1;
+
+    for tab in &app.tabs.items {
+        let width = tab.title.width() as u16 + 2;
+
+        if x + width < chunks[1].right() {
+            app.areas.tabs.push(Rect::new(x, chunks[1].y + 1, width, 1));
+        }
+
+        x += width + 1;
+    }
+
     let tab_titles = app
         .tabs
         .items
@@ -204,7 +400,7 @@ where
 
     let tabs = Tabs::new(tab_titles)
         .block(Block::default().borders(Borders::ALL).title("View"))
-        .highlight_style(Style::default().add_modifier(Modifier::BOLD))
+        .highlight_style(theme.title)
         .select(app.tabs.index);
 
     f.render_widget(tabs, chunks[1]);
@@ -216,304 +412,858 @@ fn draw_connections<B>(f: &mut Frame<B>, app: &mut App, area: Rect)
 where
     B: Backend,
 {
-    let constraints = if app.show_connection_info || app.show_help {
-        vec![Constraint::Percentage(75), Constraint::Percentage(25)]
-    } else {
-        vec![Constraint::Percentage(100)]
+    let show_side =
+        app.show_connection_info || app.show_help || app.show_events || app.column_editor.is_some();
+
+    // The compact layout keeps the full width for the table and puts the panes below it
+    let (constraints, direction) = match (show_side, app.compact) {
+        (false, _) => (vec![Constraint::Percentage(100)], Direction::Horizontal),
+        (true, false) => (
+            vec![Constraint::Percentage(75), Constraint::Percentage(25)],
+            Direction::Horizontal,
+        ),
+        (true, true) => (
+            vec![Constraint::Percentage(60), Constraint::Percentage(40)],
+            Direction::Vertical,
+        ),
     };
 
     let chunks = Layout::default()
-        .constraints(constraints.as_ref())
-        .direction(Direction::Horizontal)
+        .constraints(constraints.as_slice())
+        .direction(direction)
         .split(area);
 
     draw_connection_table(f, app, chunks[0]);
 
-    if app.show_help || app.show_connection_info {
+    if app.column_editor.is_some() {
+        draw_column_editor(f, app, chunks[1]);
+    } else if show_side {
         draw_connection_info(f, app, chunks[1])
     }
 }
 
+/// The info, help and event panes that are open, stacked with equal heights.
 fn draw_connection_info<B>(f: &mut Frame<B>, app: &mut App, area: Rect)
 where
     B: Backend,
 {
-    let constraints = if app.show_connection_info && app.show_help {
-        vec![Constraint::Percentage(50), Constraint::Percentage(50)]
-    } else {
-        vec![Constraint::Percentage(100)]
-    };
+    let mut panes: Vec<fn(&mut Frame<B>, &mut App, Rect)> = Vec::new();
+
+    if app.show_connection_info {
+        panes.push(draw_connection_info_table);
+    }
+
+    if app.show_help {
+        panes.push(draw_help);
+    }
+
+    if app.show_events {
+        panes.push(draw_events);
+    }
+
+    let constraints = vec![Constraint::Ratio(1, panes.len().max(1) as u32); panes.len()];
 
     let chunks = Layout::default()
-        .constraints(constraints.as_ref())
+        .constraints(constraints.as_slice())
         .direction(Direction::Vertical)
         .split(area);
 
-    if app.show_help && app.show_connection_info {
-        draw_connection_info_table(f, app, chunks[0]);
-        draw_help(f, app, chunks[1]);
-    } else if app.show_connection_info {
-        draw_connection_info_table(f, app, chunks[0]);
-    } else if app.show_help {
-        draw_help(f, app, chunks[0])
+    for (draw, chunk) in panes.into_iter().zip(chunks) {
+        draw(f, app, chunk);
     }
 }
 
-fn draw_help<B>(f: &mut Frame<B>, app: &mut App, area: Rect)
+/// Signals sent and connections closed, newest first.
+fn draw_events<B>(f: &mut Frame<B>, app: &mut App, area: Rect)
 where
     B: Backend,
 {
-    let msg = match app.filter.mode {
-        FilterMode::Normal => {
-            vec![
-                Spans::from(Span::styled(String::new(), Style::default())),
-                Spans::from(vec![
-                    Span::styled(
-                        "/ ".to_string(),
-                        Style::default()
-                            .fg(Color::Yellow)
-                            .add_modifier(Modifier::BOLD),
-                    ),
-                    Span::styled(
-                        "- Type string and search based on filter\n".to_string(),
-                        Style::default(),
-                    ),
-                ]),
-                Spans::from(Span::styled(String::new(), Style::default())),
-                Spans::from(vec![
-                    Span::styled(
-                        "c ".to_string(),
-                        Style::default()
-                            .fg(Color::Yellow)
-                            .add_modifier(Modifier::BOLD),
-                    ),
-                    Span::styled("- Clear the current filter\n".to_string(), Style::default()),
-                ]),
-                Spans::from(Span::styled(String::new(), Style::default())),
-                Spans::from(vec![
-                    Span::styled(
-                        "i ".to_string(),
-                        Style::default()
-                            .fg(Color::Yellow)
-                            .add_modifier(Modifier::BOLD),
-                    ),
-                    Span::styled(
-                        "- Show/hide information chart\n".to_string(),
-                        Style::default(),
-                    ),
-                ]),
-                Spans::from(Span::styled(String::new(), Style::default())),
-                Spans::from(vec![
-                    Span::styled(
-                        "p ".to_string(),
-                        Style::default()
-                            .fg(Color::Yellow)
-                            .add_modifier(Modifier::BOLD),
-                    ),
-                    Span::styled(
-                        "- Pause on current connection list\n".to_string(),
-                        Style::default(),
-                    ),
-                ]),
-                Spans::from(Span::styled(String::new(), Style::default())),
-                Spans::from(vec![
-                    Span::styled(
-                        "↑/↓ | j/k  ".to_string(),
-                        Style::default()
-                            .fg(Color::Yellow)
-                            .add_modifier(Modifier::BOLD),
-                    ),
-                    Span::styled(
-                        "- Move up and down to scroll through connection list\n".to_string(),
-                        Style::default(),
-                    ),
-                ]),
-                Spans::from(Span::styled(String::new(), Style::default())),
-                Spans::from(vec![
-                    Span::styled(
-                        "←/→ | h/l  ".to_string(),
-                        Style::default()
-                            .fg(Color::Yellow)
-                            .add_modifier(Modifier::BOLD),
-                    ),
-                    Span::styled(
-                        "- Move left and right to scroll through the All, TCP, and UDP tabs\n"
-                            .to_string(),
-                        Style::default(),
-                    ),
-                ]),
-                Spans::from(Span::styled(String::new(), Style::default())),
-                Spans::from(vec![
-                    Span::styled(
-                        "gg ".to_string(),
-                        Style::default()
-                            .fg(Color::Yellow)
-                            .add_modifier(Modifier::BOLD),
-                    ),
-                    Span::styled(
-                        "- Jump to the top of the connection list\n".to_string(),
-                        Style::default(),
-                    ),
-                ]),
-                Spans::from(Span::styled(String::new(), Style::default())),
-                Spans::from(vec![
-                    Span::styled(
-                        "Shift+G ".to_string(),
-                        Style::default()
-                            .fg(Color::Yellow)
-                            .add_modifier(Modifier::BOLD),
-                    ),
-                    Span::styled(
-                        "- Jump to the bottom of the connection list\n".to_string(),
-                        Style::default(),
-                    ),
-                ]),
-                Spans::from(Span::styled(String::new(), Style::default())),
-                Spans::from(vec![
-                    Span::styled(
-                        "? ".to_string(),
-                        Style::default()
-                            .fg(Color::Yellow)
-                            .add_modifier(Modifier::BOLD),
-                    ),
-                    Span::styled("- Show/hide this help menu\n".to_string(), Style::default()),
-                ]),
-                Spans::from(Span::styled(String::new(), Style::default())),
-                Spans::from(vec![
-                    Span::styled(
-                        "q ".to_string(),
-                        Style::default()
-                            .fg(Color::Yellow)
-                            .add_modifier(Modifier::BOLD),
-                    ),
+    let theme = app.config.theme;
+
+    let items: Vec<ListItem> = match app.events.is_empty() {
+        true => vec![ListItem::new(Span::styled(
+            "Nothing sent or closed yet",
+            theme.muted,
+        ))],
+        false => app
+            .events
+            .iter()
+            .rev()
+            .map(|event| {
+                let (message, style) = match &event.result {
+                    Ok(message) => (message, Style::default()),
+                    Err(e) => (e, theme.error),
+                };
+
+                ListItem::new(Spans::from(vec![
                     Span::styled(
-                        "- Exit and quit the program\n".to_string(),
-                        Style::default(),
+                        format!("{} ago ", format_age(event.time.elapsed())),
+                        theme.muted,
                     ),
-                ]),
-            ]
-        }
-        FilterMode::Typing => vec![
-            Spans::from(Span::styled(String::new(), Style::default())),
-            Spans::from(Span::styled(
-                "Press ENTER to apply the filter while in typing mode\n".to_string(),
-                Style::default(),
-            )),
-            Spans::from(Span::styled(String::new(), Style::default())),
-            Spans::from(Span::styled(
-                "Press ESC to return\n".to_string(),
-                Style::default(),
-            )),
-        ],
+                    Span::styled(message.clone(), style),
+                ]))
+            })
+            .collect(),
     };
 
-    let help_message = Paragraph::new(msg).wrap(Wrap { trim: true }).block(
+    let list = List::new(items).block(
         Block::default()
-            .title_alignment(Alignment::Center)
             .borders(Borders::ALL)
-            .title(Span::styled(
-                "Help",
-                Style::default().add_modifier(Modifier::BOLD),
-            )),
+            .title(Span::styled("Events", theme.title)),
     );
 
+    f.render_widget(list, area);
+}
+
+fn draw_help<B>(f: &mut Frame<B>, app: &mut App, area: Rect)
+where
+    B: Backend,
+{
+    let typing = matches!(app.filter.mode, FilterMode::Typing);
+    let theme = app.config.theme;
+
+    // Generated from the keymap so it always shows the keys that are really bound
+    let mut msg: Vec<Spans> = Vec::new();
+
+    let commands = match app.filter.mode {
+        FilterMode::Command => COMMANDS
+            .iter()
+            .map(|(_, usage)| (format!(":{}", usage), String::new()))
+            .collect(),
+        _ => Vec::new(),
+    };
+
+    let entries: Vec<(String, String)> = commands
+        .into_iter()
+        .chain(
+            app.config
+                .keymap
+                .help(&app.filter.mode)
+                .into_iter()
+                .map(|(keys, description)| (keys, format!("- {}", description))),
+        )
+        .collect();
+
+    for (keys, description) in entries {
+        msg.push(Spans::from(Span::styled(String::new(), Style::default())));
+        msg.push(Spans::from(vec![
+            Span::styled(format!("{} ", keys), theme.value),
+            Span::styled(format!("{}\n", description), Style::default()),
+        ]));
+    }
+
+    if typing {
+        msg.push(Spans::from(Span::styled(String::new(), Style::default())));
+        msg.push(Spans::from(Span::styled(
+            "Use column:value to match a single column, numbers can be compared with < <= > >= (e.g. rtt:>100ms lport:<1024)\n"
+                .to_string(),
+            Style::default(),
+        )));
+    }
+
+    app.help_scroll = app.help_scroll.min(msg.len() as u16);
+    app.areas.help = area;
+
+    let help_message = Paragraph::new(msg)
+        .wrap(Wrap { trim: true })
+        .scroll((app.help_scroll, 0))
+        .block(
+            Block::default()
+                .title_alignment(Alignment::Center)
+                .borders(Borders::ALL)
+                .title(Span::styled("Help", theme.title)),
+        );
+
     f.render_widget(help_message, area);
 }
 
+/// Widths that fit the titles and cells of the columns into `available`. Columns with a
+/// configured share get it, the others are sized to their content and share what is left.
+/// Columns that don't fit even when shrunk are left out at the end.
+fn get_column_widths(
+    app: &App,
+    titles: &[String],
+    cells: &[Vec<String>],
+    available: u16,
+) -> Vec<u16> {
+    let text_width = |text: &str| text.lines().map(|line| line.width()).max().unwrap_or(0) as u16;
+
+    let mut widths: Vec<u16> = app
+        .columns
+        .iter()
+        .zip(titles)
+        .enumerate()
+        .map(
+            |(i, (column, title))| match app.config.column_widths.get(column) {
+                Some(share) => get_percentage_width(available, *share),
+                None => cells
+                    .iter()
+                    .map(|row| text_width(&row[i]))
+                    .fold(text_width(title), u16::max),
+            },
+        )
+        .collect();
+
+    let fixed: Vec<bool> = app
+        .columns
+        .iter()
+        .map(|column| app.config.column_widths.contains_key(column))
+        .collect();
+
+    // Titles can still be read when shrunk down to their width
+    let minimums: Vec<u16> = titles
+        .iter()
+        .zip(&widths)
+        .map(|(title, width)| (*width).min(text_width(title).max(3)))
+        .collect();
+
+    // One space between columns
+    let total = |widths: &[u16]| widths.iter().sum::<u16>() + widths.len().saturating_sub(1) as u16;
+
+    let mut count = widths.len();
+
+    while count > 1 && total(&minimums[..count]) > available {
+        count -= 1;
+    }
+
+    widths.truncate(count);
+
+    while total(&widths) > available {
+        let widest = (0..widths.len())
+            .filter(|i| widths[*i] > minimums[*i])
+            .max_by_key(|i| widths[*i] - minimums[*i]);
+
+        match widest {
+            Some(i) => widths[i] -= 1,
+            None => break,
+        }
+    }
+
+    let flexible: Vec<usize> = (0..widths.len()).filter(|i| !fixed[*i]).collect();
+    let spare = available.saturating_sub(total(&widths));
+
+    if !flexible.is_empty() {
+        for (n, i) in flexible.iter().enumerate() {
+            let share = spare / flexible.len() as u16;
+            let remainder = (n < (spare as usize % flexible.len())) as u16;
+            widths[*i] += share + remainder;
+        }
+    }
+
+    widths
+}
+
 fn draw_connection_table<B>(f: &mut Frame<B>, app: &mut App, area: Rect)
 where
     B: Backend,
 {
-    let selected_style = Style::default().add_modifier(Modifier::REVERSED);
+    let theme = app.config.theme;
+
+    // The compact layout uses the short names of the columns, as used in filters
+    let titles: Vec<String> = app
+        .columns
+        .iter()
+        .map(|column| {
+            let title = match app.compact {
+                true => column.key(),
+                false => column.title(),
+            };
+
+            match column == &app.sort.column {
+                true if app.sort.descending => format!("{} ▼", title),
+                true => format!("{} ▲", title),
+                false => title.to_string(),
+            }
+        })
+        .collect();
+
+    let cells: Vec<Vec<String>> = app
+        .connection_table
+        .items
+        .iter()
+        .map(|item| app.columns.iter().map(|column| column.cell(item)).collect())
+        .collect();
+
+    // The compact layout only keeps the top border, for the title
+    let (borders, inner) = match app.compact {
+        true => (
+            Borders::TOP,
+            Rect::new(area.x, area.y, area.width, area.height),
+        ),
+        false => (
+            Borders::ALL,
+            Rect::new(
+                area.x + 1,
+                area.y,
+                area.width.saturating_sub(2),
+                area.height,
+            ),
+        ),
+    };
+
+    let widths = get_column_widths(app, &titles, &cells, inner.width);
+    let hidden = app.columns.len() - widths.len();
 
     let header = TableHeader {
-        items: vec![
-            TableHeaderItem {
-                text: "Protocol",
-                width: get_percentage_width(area.width, 0.1),
-            },
-            TableHeaderItem {
-                text: "Local Address",
-                width: get_percentage_width(area.width, 0.16),
-            },
-            TableHeaderItem {
-                text: "Local Port",
-                width: get_percentage_width(area.width, 0.1),
-            },
-            TableHeaderItem {
-                text: "Remote Address",
-                width: get_percentage_width(area.width, 0.16),
-            },
-            TableHeaderItem {
-                text: "Remote Port",
-                width: get_percentage_width(area.width, 0.1),
-            },
-            TableHeaderItem {
-                text: "State",
-                width: get_percentage_width(area.width, 0.1),
-            },
-            TableHeaderItem {
-                text: "PID",
-                width: get_percentage_width(area.width, 0.1),
-            },
-            TableHeaderItem {
-                text: "Process Name",
-                width: get_percentage_width(area.width, 0.16),
-            },
-        ],
+        items: titles
+            .into_iter()
+            .zip(&widths)
+            .map(|(text, width)| TableHeaderItem {
+                text,
+                width: *width,
+            })
+            .collect(),
     };
 
-    let formatted_header = Row::new(header.items.iter().map(|h| h.text))
-        .style(Style::default().add_modifier(Modifier::BOLD));
+    app.areas.table = area;
 
-    let rows = app.connection_table.items.iter().map(|item| {
-        let printable = &item.printable_string;
+    // Columns are laid out from the left border with one space between them
+    let mut x = inner.x;
 
-        let height = printable
-            .iter()
-            .map(|content| content.chars().filter(|c| *c == '\n').count())
-            .max()
-            .unwrap_or(0)
-            + 1;
+    for (column, item) in app.columns.iter().zip(header.items.iter()) {
+        let width = item.width.min(inner.right().saturating_sub(x));
 
-        let cells = printable.iter().map(|c| Cell::from(c.clone()));
+        if width > 0 {
+            app.areas
+                .columns
+                .push((Rect::new(x, area.y + 1, width, 1), *column));
+        }
 
-        Row::new(cells).height(height as u16).bottom_margin(0)
-    });
+        x = x.saturating_add(item.width + 1);
+    }
 
-    let widths = header
+    let formatted_header = Row::new(header.items.iter().map(|h| h.text.clone())).style(theme.title);
+    let listeners = app.tabs.is_listeners();
+
+    let rows = app
+        .connection_table
+        .items
+        .iter()
+        .zip(cells)
+        .map(|(item, mut printable)| {
+            printable.truncate(widths.len());
+
+            let height = printable
+                .iter()
+                .map(|content| content.chars().filter(|c| *c == '\n').count())
+                .max()
+                .unwrap_or(0)
+                + 1;
+
+            let cells = printable.into_iter().map(Cell::from);
+
+            // Full accept queues and data stuck in a queue are common causes of latency,
+            // listeners open to every interface and anything new since the baseline stand
+            // out too
+            let style = if item.is_accept_queue_saturated() || (listeners && item.exposed) {
+                theme.error
+            } else if app.queues.is_persistent(item) || item.drift {
+                theme.warning
+            } else {
+                item.tcp_state()
+                    .map_or(Style::default(), |state| theme.state_style(state))
+            };
+
+            Row::new(cells)
+                .style(style)
+                .height(height as u16)
+                .bottom_margin(0)
+        });
+
+    let constraints = header
         .items
         .iter()
         .map(|h| Constraint::Length(h.width))
         .collect::<Vec<tui::layout::Constraint>>();
 
+    // The sort column isn't always one of the visible ones, e.g. the combined rate
+    let mut title = String::from(match listeners {
+        true => "Listeners",
+        false => "Connections",
+    });
+
+    if !app.columns.contains(&app.sort.column) {
+        title.push_str(&format!(" (sorted by {})", app.sort.column.title()));
+    }
+
+    if listeners {
+        let exposed = app
+            .connection_table
+            .items
+            .iter()
+            .filter(|item| item.exposed)
+            .count();
+
+        title.push_str(&format!(" ({} exposed)", exposed));
+    }
+
+    if app.baseline.is_some() {
+        let new = app
+            .connection_table
+            .items
+            .iter()
+            .filter(|item| item.drift)
+            .count();
+
+        title.push_str(&format!(" ({} not in baseline)", new));
+    }
+
+    match &app.namespace_view {
+        NamespaceView::Current => {}
+        NamespaceView::One(namespace) => title.push_str(&format!(" in {}", namespace.name)),
+        NamespaceView::All => title.push_str(" in all namespaces"),
+    }
+
+    if hidden > 0 {
+        title.push_str(&format!(" (+{} columns that don't fit)", hidden));
+    }
+
     let table = Table::new(rows)
         .header(formatted_header)
-        .block(Block::default().borders(Borders::ALL).title("Connections"))
-        .highlight_style(selected_style)
-        .widths(&widths);
+        .block(Block::default().borders(borders).title(title))
+        .highlight_style(theme.selected)
+        .widths(&constraints);
 
     f.render_stateful_widget(table, area, &mut app.connection_table.state);
+
+    app.connection_table.update_offset(app.page_size());
 }
 
-fn draw_connection_info_table<B>(f: &mut Frame<B>, _app: &mut App, area: Rect)
+fn draw_command_line<B>(f: &mut Frame<B>, app: &mut App, area: Rect)
 where
     B: Backend,
 {
-    let connections: Vec<ListItem> = ITEMS
+    let theme = app.config.theme;
+
+    let spans = match (&app.filter.mode, &app.command.message) {
+        (FilterMode::Command, _) => {
+            f.set_cursor(area.x + 1 + app.command.input.width() as u16, area.y);
+
+            let mut spans = vec![Span::raw(format!(":{}", app.command.input))];
+
+            // The other candidates of the completion, the current one is in the input
+            if let Some(completion) = &app.command.completion {
+                let others = completion
+                    .candidates
+                    .iter()
+                    .enumerate()
+                    .filter(|(i, _)| *i != completion.index)
+                    .map(|(_, candidate)| candidate.as_str())
+                    .join(" ");
+
+                spans.push(Span::styled(format!("  {}", others), theme.muted));
+            }
+
+            spans
+        }
+        (_, Some(Ok(message))) => vec![Span::raw(message.clone())],
+        (_, Some(Err(e))) => vec![Span::styled(e.clone(), theme.error)],
+        (_, None) => Vec::new(),
+    };
+
+    f.render_widget(Paragraph::new(Spans::from(spans)), area);
+}
+
+/// A box over the middle of the screen to choose a signal, then confirm sending it.
+fn draw_kill_dialog<B>(f: &mut Frame<B>, app: &mut App)
+where
+    B: Backend,
+{
+    let theme = app.config.theme;
+
+    let dialog = match &app.kill_dialog {
+        Some(dialog) => dialog,
+        None => return,
+    };
+
+    let pids = dialog.pids.iter().map(|pid| pid.to_string()).join(", ");
+
+    let mut text = vec![
+        Spans::from(vec![
+            Span::raw("Process "),
+            Span::styled(dialog.process_name.clone(), theme.value),
+        ]),
+        Spans::from(vec![
+            Span::raw(match dialog.pids.len() {
+                1 => "PID ",
+                _ => "PIDs ",
+            }),
+            Span::styled(pids.clone(), theme.value),
+        ]),
+        Spans::default(),
+    ];
+
+    match dialog.confirm {
+        Some(signal) => {
+            text.push(Spans::from(vec![
+                Span::raw("Send "),
+                Span::styled(signal_name(signal), theme.warning),
+                Span::raw(format!(" to {}?", pids)),
+            ]));
+            text.push(Spans::default());
+            text.push(Spans::from(Span::styled(
+                "y Enter - Send, n Esc - Back",
+                theme.muted,
+            )));
+        }
+        None => {
+            for (i, signal) in KillDialog::SIGNALS.iter().enumerate() {
+                let style = match i == dialog.cursor {
+                    true => theme.selected,
+                    false => Style::default(),
+                };
+
+                text.push(Spans::from(Span::styled(signal_name(*signal), style)));
+            }
+
+            let style = match dialog.is_custom() {
+                true => theme.selected,
+                false => Style::default(),
+            };
+
+            text.push(Spans::from(Span::styled(
+                format!("Custom: {}", dialog.custom),
+                style,
+            )));
+            text.push(Spans::default());
+
+            match &dialog.error {
+                Some(e) => text.push(Spans::from(Span::styled(e.clone(), theme.error))),
+                None => text.push(Spans::from(Span::styled(
+                    "Up Down - Choose, Enter - Send, Esc - Cancel",
+                    theme.muted,
+                ))),
+            }
+        }
+    }
+
+    let area = draw_dialog(f, app, "Send Signal", text);
+
+    if dialog.is_custom() && dialog.confirm.is_none() {
+        let row = 3 + KillDialog::SIGNALS.len() as u16;
+        let column = "Custom: ".width() as u16 + dialog.custom.width() as u16;
+        f.set_cursor(area.x + 1 + column, area.y + 1 + row);
+    }
+}
+
+fn draw_close_dialog<B>(f: &mut Frame<B>, app: &mut App)
+where
+    B: Backend,
+{
+    let theme = app.config.theme;
+
+    let dialog = match &app.close_dialog {
+        Some(dialog) => dialog,
+        None => return,
+    };
+
+    let mut text = vec![
+        Spans::from(vec![
+            Span::raw("Process "),
+            Span::styled(dialog.process_name.clone(), theme.value),
+        ]),
+        Spans::from(vec![
+            Span::raw("Local   "),
+            Span::styled(dialog.local.to_string(), theme.value),
+        ]),
+        Spans::from(vec![
+            Span::raw("Remote  "),
+            Span::styled(dialog.remote.to_string(), theme.value),
+        ]),
+    ];
+
+    if let Some(namespace) = &dialog.namespace {
+        text.push(Spans::from(vec![
+            Span::raw("Netns   "),
+            Span::styled(namespace.name.clone(), theme.value),
+        ]));
+    }
+
+    text.extend([
+        Spans::default(),
+        Spans::from(Span::styled(
+            "Close this connection? Its process sees it aborted.",
+            theme.warning,
+        )),
+        Spans::default(),
+        Spans::from(Span::styled("y Enter - Close, n Esc - Cancel", theme.muted)),
+    ]);
+
+    draw_dialog(f, app, "Close Connection", text);
+}
+
+/// Draws a box with `text` over the middle of the screen, returning where it went.
+fn draw_dialog<B>(f: &mut Frame<B>, app: &App, title: &str, text: Vec<Spans>) -> Rect
+where
+    B: Backend,
+{
+    let screen = f.size();
+    let width = screen.width.min(60);
+    let height = (text.len() as u16 + 2).min(screen.height);
+    let area = Rect::new(
+        screen.x + (screen.width - width) / 2,
+        screen.y + (screen.height - height) / 2,
+        width,
+        height,
+    );
+
+    let paragraph = Paragraph::new(text).wrap(Wrap { trim: false }).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .title(Span::styled(title.to_string(), app.config.theme.title)),
+    );
+
+    f.render_widget(Clear, area);
+    f.render_widget(paragraph, area);
+
+    area
+}
+
+/// Every column with whether it is shown, in the order of the table.
+fn draw_column_editor<B>(f: &mut Frame<B>, app: &mut App, area: Rect)
+where
+    B: Backend,
+{
+    let theme = app.config.theme;
+    let list = app.column_list();
+    let height = area.height.saturating_sub(2).max(1) as usize;
+
+    let editor = match &mut app.column_editor {
+        Some(editor) => editor,
+        None => return,
+    };
+
+    // Scrolls just enough to keep the cursor visible
+    if editor.cursor < editor.offset {
+        editor.offset = editor.cursor;
+    } else if editor.cursor >= editor.offset + height {
+        editor.offset = editor.cursor + 1 - height;
+    }
+
+    let items: Vec<ListItem> = list
         .iter()
-        .map(|i| ListItem::new(vec![Spans::from(Span::raw(*i))]))
+        .enumerate()
+        .skip(editor.offset)
+        .map(|(i, (column, visible))| {
+            let text = format!(
+                "[{}] {} ({})",
+                match visible {
+                    true => 'x',
+                    false => ' ',
+                },
+                column.title(),
+                column.key()
+            );
+
+            let style = match (i == editor.cursor, visible) {
+                (true, _) => theme.selected,
+                (false, true) => Style::default(),
+                (false, false) => theme.muted,
+            };
+
+            ListItem::new(Span::styled(text, style))
+        })
         .collect();
 
-    let connections = List::new(connections)
+    app.areas.column_editor = area;
+
+    let list = List::new(items).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .title(Span::styled("Columns", theme.title)),
+    );
+
+    f.render_widget(list, area);
+}
+
+/// Label and value of every detail known about a connection.
+fn get_connection_details(
+    app: &App,
+    connection: &SocketInfoWithProcName,
+) -> Vec<(&'static str, String)> {
+    let mut details = vec![
+        ("Protocol", Column::Protocol.text(connection)),
+        (
+            "Local",
+            format!(
+                "{} {}",
+                Column::LocalAddress.text(connection),
+                Column::LocalPort.text(connection)
+            ),
+        ),
+        (
+            "Remote",
+            format!(
+                "{} {}",
+                Column::RemoteAddress.text(connection),
+                Column::RemotePort.text(connection)
+            ),
+        ),
+        ("State", Column::State.text(connection)),
+        (
+            "PIDs",
+            connection
+                .associated_pids()
+                .iter()
+                .map(|pid| pid.to_string())
+                .join(", "),
+        ),
+        ("Process", connection.process_name.clone()),
+        ("Peer", Column::Peer.text(connection)),
+        (
+            "Inode",
+            connection
+                .inode()
+                .map(|inode| inode.to_string())
+                .unwrap_or_default(),
+        ),
+    ];
+
+    if let (true, Some(scope), Some(families)) = (
+        connection.is_listener(),
+        connection.bind_scope(),
+        connection.address_families(),
+    ) {
+        let exposed = match connection.exposed {
+            true => ", exposed",
+            false => "",
+        };
+
+        details.push(("Listens on", format!("{}, {}{}", scope, families, exposed)));
+    }
+
+    if let Some(namespace) = &connection.namespace {
+        details.push(("Namespace", namespace.clone()));
+    }
+
+    if let Some(cgroup) = &connection.cgroup {
+        let fields = [
+            ("Unit", &cgroup.unit),
+            ("Container", &cgroup.container),
+            ("Pod", &cgroup.pod),
+        ];
+
+        for (label, value) in fields {
+            if let Some(value) = value {
+                details.push((label, value.clone()));
+            }
+        }
+    }
+
+    if let Some(internals) = &connection.tcp_internals {
+        details.extend([
+            ("RTT", Column::Rtt.text(connection)),
+            ("RTT Var", Column::RttVar.text(connection)),
+            ("Cwnd", Column::Cwnd.text(connection)),
+            ("Ssthresh", Column::Ssthresh.text(connection)),
+            (
+                "Retransmits",
+                format!(
+                    "{} ({} total)",
+                    internals.retransmits, internals.total_retransmits
+                ),
+            ),
+            ("Lost", Column::Lost.text(connection)),
+            ("Unacked", Column::Unacked.text(connection)),
+            ("MSS", Column::Mss.text(connection)),
+            ("Congestion", Column::Congestion.text(connection)),
+        ]);
+    }
+
+    if let Some(queues) = &connection.queues {
+        match connection.accept_queue() {
+            Some((queued, backlog)) => details.push((
+                "Accept Queue",
+                match connection.is_accept_queue_saturated() {
+                    true => format!("{} / {} (saturated)", queued, backlog),
+                    false => format!("{} / {}", queued, backlog),
+                },
+            )),
+            None => details.extend([
+                ("Recv-Q", queues.recv_queue.to_string()),
+                ("Send-Q", queues.send_queue.to_string()),
+            ]),
+        }
+
+        if let Some(memory) = queues.memory {
+            details.push((
+                "Memory",
+                format!(
+                    "rmem {}/{} wmem {}/{} drops {}",
+                    memory.rmem_alloc,
+                    memory.rcvbuf,
+                    memory.wmem_alloc,
+                    memory.sndbuf,
+                    memory.drops
+                ),
+            ));
+        }
+    }
+
+    if let Some(throughput) = connection.throughput {
+        let process = get_process_throughput(app, &connection.process_name);
+
+        details.extend([
+            ("Rx/s", format_rate(throughput.rx)),
+            ("Tx/s", format_rate(throughput.tx)),
+            (
+                "Process Rx/Tx",
+                format!("{} / {}", format_rate(process.rx), format_rate(process.tx)),
+            ),
+        ]);
+
+        if let Some((_, remote)) = connection.tcp_endpoints() {
+            let host = get_remote_host_throughput(app, remote.ip());
+
+            details.push((
+                "Host Rx/Tx",
+                format!("{} / {}", format_rate(host.rx), format_rate(host.tx)),
+            ));
+        }
+    }
+
+    details
+}
+
+fn draw_connection_info_table<B>(f: &mut Frame<B>, app: &mut App, area: Rect)
+where
+    B: Backend,
+{
+    let selected = app
+        .connection_table
+        .state
+        .selected()
+        .and_then(|i| app.connection_table.items.get(i));
+
+    let theme = app.config.theme;
+
+    let details: Vec<ListItem> = match selected {
+        Some(connection) => get_connection_details(app, connection)
+            .into_iter()
+            .map(|(label, value)| {
+                ListItem::new(Spans::from(vec![
+                    Span::styled(format!("{}: ", label), theme.title),
+                    Span::raw(value),
+                ]))
+            })
+            .collect(),
+        None => vec![ListItem::new(Span::raw("No connection selected"))],
+    };
+
+    app.info_scroll = app.info_scroll.min(details.len().saturating_sub(1) as u16);
+    app.areas.info = area;
+
+    let details: Vec<ListItem> = details.into_iter().skip(app.info_scroll as usize).collect();
+
+    let details = List::new(details)
         .block(
             Block::default()
                 .borders(Borders::ALL)
                 .title("Connection Info"),
         )
-        .highlight_style(Style::default().add_modifier(Modifier::BOLD));
+        .highlight_style(theme.title);
 
-    f.render_widget(connections, area);
+    f.render_widget(details, area);
 }
 
 fn draw_status<B>(f: &mut Frame<B>, app: &mut App, area: Rect)
@@ -525,16 +1275,20 @@ where
         .borders(Borders::ALL)
         .border_style(Style::default());
 
+    let theme = app.config.theme;
+
     let mut info: Vec<Span> = vec![Span::from("Waiting")];
 
-    if app.filter.regex.is_some() {
+    if app.filter.error.is_some() {
+        info = vec![Span::styled("Invalid Filter", theme.error)];
+    } else if app.filter.query.is_some() || app.state_filter.is_some() {
         if app.connection_table.items.is_empty() {
-            info = vec![Span::styled("No Matches", Style::default().fg(Color::Red))];
+            info = vec![Span::styled("No Matches", theme.error)];
         } else {
             info = vec![
                 Span::styled(
                     app.connection_table.items.len().to_string(),
-                    Style::default().fg(Color::Green),
+                    theme.highlight,
                 ),
                 Span::from(" Matches"),
             ];
@@ -542,10 +1296,7 @@ where
     }
 
     if app.is_paused() {
-        info.push(Span::styled(
-            " (paused)",
-            Style::default().fg(Color::Yellow),
-        ));
+        info.push(Span::styled(" (paused)", theme.warning));
     }
 
     let status = Paragraph::new(Spans::from(info))
@@ -0,0 +1,153 @@
+use netstat2::TcpState;
+
+use tui::{
+    backend::Backend,
+    layout::{Constraint, Direction, Layout, Rect},
+    style::Style,
+    symbols,
+    text::Span,
+    widgets::{Axis, Block, Borders, Chart, Dataset, GraphType, Sparkline},
+    Frame,
+};
+
+use crate::app::App;
+use crate::stats::StatsSample;
+use crate::utils::TCP_STATES;
+
+pub fn draw_stats<B>(f: &mut Frame<B>, app: &mut App, area: Rect)
+where
+    B: Backend,
+{
+    let chunks = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Percentage(60), Constraint::Percentage(40)].as_slice())
+        .split(area);
+
+    draw_state_chart(f, app, chunks[0]);
+    draw_sparklines(f, app, chunks[1]);
+}
+
+fn draw_state_chart<B>(f: &mut Frame<B>, app: &mut App, area: Rect)
+where
+    B: Backend,
+{
+    let samples: Vec<&StatsSample> = app.stats.last(area.width as usize).collect();
+
+    let now = match samples.last() {
+        Some(sample) => sample.time,
+        None => return,
+    };
+
+    let window = now.duration_since(samples[0].time).as_secs_f64().max(1.0);
+
+    // Seconds before the latest sample, so the chart reads left to right
+    let series: Vec<(TcpState, Vec<(f64, f64)>)> = TCP_STATES
+        .iter()
+        .map(|state| {
+            let points = samples
+                .iter()
+                .map(|sample| {
+                    (
+                        -now.duration_since(sample.time).as_secs_f64(),
+                        sample.state_count(*state) as f64,
+                    )
+                })
+                .collect();
+
+            (*state, points)
+        })
+        .filter(|(_, points): &(TcpState, Vec<(f64, f64)>)| points.iter().any(|(_, y)| *y > 0.0))
+        .collect();
+
+    let max = series
+        .iter()
+        .flat_map(|(_, points)| points.iter().map(|(_, y)| *y))
+        .fold(1.0, f64::max);
+
+    let theme = app.config.theme;
+
+    let datasets = series
+        .iter()
+        .map(|(state, points)| {
+            Dataset::default()
+                .name(state.to_string())
+                .marker(symbols::Marker::Braille)
+                .graph_type(GraphType::Line)
+                .style(theme.state_style(*state))
+                .data(points)
+        })
+        .collect();
+
+    let chart = Chart::new(datasets)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title("TCP States (v)"),
+        )
+        .x_axis(Axis::default().bounds([-window, 0.0]).labels(vec![
+            Span::raw(format!("-{:.0}s", window)),
+            Span::raw("now"),
+        ]))
+        .y_axis(
+            Axis::default()
+                .bounds([0.0, max])
+                .labels(vec![Span::raw("0"), Span::raw(format!("{:.0}", max))]),
+        );
+
+    f.render_widget(chart, area);
+}
+
+type Counter = fn(&StatsSample) -> u64;
+
+fn draw_sparklines<B>(f: &mut Frame<B>, app: &mut App, area: Rect)
+where
+    B: Backend,
+{
+    let theme = app.config.theme;
+
+    let counters: [(&str, Style, Counter); 8] = [
+        ("Sockets", theme.value, |sample| sample.total),
+        ("Unique", theme.value, |sample| sample.unique),
+        ("TCP", theme.value, |sample| sample.tcp),
+        ("UDP", theme.value, |sample| sample.udp),
+        ("IPv4", theme.value, |sample| sample.ipv4),
+        ("IPv6", theme.value, |sample| sample.ipv6),
+        (
+            "Established",
+            theme.state_style(TcpState::Established),
+            |sample| sample.state_count(TcpState::Established),
+        ),
+        ("New/s", theme.highlight, |sample| {
+            sample.new_per_second.round() as u64
+        }),
+    ];
+
+    let columns = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Percentage(50), Constraint::Percentage(50)].as_slice())
+        .split(area);
+
+    let rows = [Constraint::Ratio(1, 4); 4];
+
+    let cells = columns.iter().flat_map(|column| {
+        Layout::default()
+            .direction(Direction::Vertical)
+            .constraints(rows.as_slice())
+            .split(*column)
+    });
+
+    for ((title, style, counter), area) in counters.iter().zip(cells) {
+        // One bar per sample, the newest on the right
+        let data: Vec<u64> = app.stats.last(area.width as usize).map(counter).collect();
+
+        let sparkline = Sparkline::default()
+            .block(Block::default().borders(Borders::TOP).title(Span::styled(
+                format!("{} {}", title, data.last().copied().unwrap_or(0)),
+                theme.title,
+            )))
+            .style(*style)
+            .data(&data);
+
+        f.render_widget(sparkline, area);
+    }
+}
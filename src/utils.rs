@@ -1,15 +1,48 @@
-use netstat2::{ProtocolFlags, ProtocolSocketInfo, TcpState};
+use std::net::IpAddr;
+use std::time::Duration;
+
+use netstat2::{ProtocolSocketInfo, TcpState};
 
 use crate::app::App;
+use crate::os::{Protocol, Throughput};
 
 use itertools::Itertools;
 
+/// Every state a TCP socket can be reported in.
+pub const TCP_STATES: [TcpState; 11] = [
+    TcpState::Established,
+    TcpState::SynSent,
+    TcpState::SynReceived,
+    TcpState::FinWait1,
+    TcpState::FinWait2,
+    TcpState::TimeWait,
+    TcpState::Closed,
+    TcpState::CloseWait,
+    TcpState::LastAck,
+    TcpState::Listen,
+    TcpState::Closing,
+];
+
+/// Whether there are enough sockets in a state to point at a problem, like a
+/// TIME_WAIT storm or an application that doesn't close its sockets (CLOSE_WAIT).
+pub fn is_abnormal_state_count(state: TcpState, count: usize) -> bool {
+    let threshold = match state {
+        TcpState::CloseWait => 10,
+        TcpState::LastAck | TcpState::Closing => 50,
+        TcpState::SynReceived | TcpState::FinWait1 | TcpState::FinWait2 => 100,
+        TcpState::TimeWait => 1000,
+        _ => return false,
+    };
+
+    count >= threshold
+}
+
 pub enum AddressFamily {
     IPv4,
     IPv6,
 }
 
-pub fn get_total_sockets_protocol_count(app: &App, flags: ProtocolFlags) -> usize {
+pub fn get_total_sockets_protocol_count(app: &App, flags: Protocol) -> usize {
     app.connections
         .clone()
         .into_iter()
@@ -19,14 +52,12 @@ pub fn get_total_sockets_protocol_count(app: &App, flags: ProtocolFlags) -> usiz
 
 pub fn get_total_sockets_unique_count(app: &App) -> usize {
     app.connections
-        .clone()
-        .into_iter()
-        .unique_by(
-            |connection| match connection.info.protocol_socket_info.clone() {
-                ProtocolSocketInfo::Tcp(tcp_si) => tcp_si.remote_addr,
-                ProtocolSocketInfo::Udp(udp_si) => udp_si.local_addr,
-            },
-        )
+        .iter()
+        .filter_map(|connection| connection.inet())
+        .unique_by(|info| match info.protocol_socket_info.clone() {
+            ProtocolSocketInfo::Tcp(tcp_si) => tcp_si.remote_addr,
+            ProtocolSocketInfo::Udp(udp_si) => udp_si.local_addr,
+        })
         .count()
 }
 
@@ -34,22 +65,204 @@ pub fn get_total_sockets_state_count(app: &App, state: TcpState) -> usize {
     app.connections
         .clone()
         .into_iter()
-        .filter(
-            |connection| match connection.info.protocol_socket_info.clone() {
-                ProtocolSocketInfo::Tcp(tcp_si) => tcp_si.state == state,
-                ProtocolSocketInfo::Udp(_) => false,
-            },
-        )
+        .filter(|connection| connection.tcp_state() == Some(state))
         .count()
 }
 
 pub fn get_total_sockets_ip_count(app: &App, flags: AddressFamily) -> usize {
     app.connections
-        .clone()
-        .into_iter()
-        .filter(|connection| match flags {
-            AddressFamily::IPv4 => connection.info.local_addr().is_ipv4(),
-            AddressFamily::IPv6 => connection.info.local_addr().is_ipv6(),
+        .iter()
+        .filter_map(|connection| connection.inet())
+        .filter(|info| match flags {
+            AddressFamily::IPv4 => info.local_addr().is_ipv4(),
+            AddressFamily::IPv6 => info.local_addr().is_ipv6(),
         })
         .count()
 }
+
+/// Number of sockets owned by each user, most sockets first.
+pub fn get_sockets_per_user(app: &App) -> Vec<(String, usize)> {
+    app.connections
+        .iter()
+        .filter_map(|connection| connection.user.clone())
+        .counts()
+        .into_iter()
+        .sorted_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)))
+        .collect()
+}
+
+pub fn get_total_unattributed_sockets(app: &App) -> usize {
+    app.connections
+        .iter()
+        .filter(|connection| connection.is_unattributed())
+        .count()
+}
+
+pub fn get_total_saturated_listeners(app: &App) -> usize {
+    app.connections
+        .iter()
+        .filter(|connection| connection.is_accept_queue_saturated())
+        .count()
+}
+
+pub fn get_total_throughput(app: &App) -> Throughput {
+    app.connections
+        .iter()
+        .filter_map(|connection| connection.throughput)
+        .fold(Throughput::default(), |total, throughput| {
+            total + throughput
+        })
+}
+
+pub fn get_process_throughput(app: &App, process_name: &str) -> Throughput {
+    app.connections
+        .iter()
+        .filter(|connection| connection.process_name == process_name)
+        .filter_map(|connection| connection.throughput)
+        .fold(Throughput::default(), |total, throughput| {
+            total + throughput
+        })
+}
+
+pub fn get_remote_host_throughput(app: &App, host: IpAddr) -> Throughput {
+    app.connections
+        .iter()
+        .filter(|connection| {
+            connection
+                .tcp_endpoints()
+                .is_some_and(|(_, remote)| remote.ip() == host)
+        })
+        .filter_map(|connection| connection.throughput)
+        .fold(Throughput::default(), |total, throughput| {
+            total + throughput
+        })
+}
+
+pub fn format_rate(bytes_per_second: f64) -> String {
+    let units = ["B/s", "KiB/s", "MiB/s", "GiB/s"];
+
+    let mut value = bytes_per_second;
+    let mut unit = 0;
+
+    while value >= 1024.0 && unit < units.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+
+    match unit {
+        0 => format!("{:.0} {}", value, units[unit]),
+        _ => format!("{:.1} {}", value, units[unit]),
+    }
+}
+
+/// Formats how long something has existed, e.g. `42s`, `5m12s`, `3h05m` or `2d04h`.
+pub fn format_age(age: Duration) -> String {
+    let seconds = age.as_secs();
+
+    match seconds {
+        0..=59 => format!("{}s", seconds),
+        60..=3599 => format!("{}m{:02}s", seconds / 60, seconds % 60),
+        3600..=86399 => format!("{}h{:02}m", seconds / 3600, seconds % 3600 / 60),
+        _ => format!("{}d{:02}h", seconds / 86400, seconds % 86400 / 3600),
+    }
+}
+
+/// Parses a human friendly duration such as `500ms`, `2s`, `5m` or `1h`. A bare number is
+/// taken as seconds.
+pub fn parse_duration(input: &str) -> Result<Duration, String> {
+    let input = input.trim();
+    let split = input
+        .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+        .unwrap_or(input.len());
+    let (value, unit) = input.split_at(split);
+
+    let value: f64 = value
+        .parse()
+        .map_err(|_| format!("invalid duration '{}'", input))?;
+
+    let seconds = match unit.trim() {
+        "ms" => value / 1000.0,
+        "" | "s" => value,
+        "m" => value * 60.0,
+        "h" => value * 3600.0,
+        _ => return Err(format!("invalid duration unit in '{}'", input)),
+    };
+
+    if !seconds.is_finite() || seconds < 0.0 {
+        return Err(format!("invalid duration '{}'", input));
+    }
+
+    // Still too large for a Duration, e.g. 99999999999999999999h
+    Duration::try_from_secs_f64(seconds).map_err(|_| format!("duration '{}' is too long", input))
+}
+
+/// Parses a rate in bytes per second, e.g. `512`, `10k`, `1.5MiB/s` or `2G`.
+pub fn parse_rate(input: &str) -> Result<f64, String> {
+    let input = input.trim();
+    let split = input
+        .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+        .unwrap_or(input.len());
+    let (value, unit) = input.split_at(split);
+
+    let value: f64 = value
+        .parse()
+        .map_err(|_| format!("invalid rate '{}'", input))?;
+
+    // Same binary units as format_rate shows
+    let unit = unit.trim().trim_end_matches("/s").to_ascii_lowercase();
+    let multiplier = match unit.trim_end_matches('b').trim_end_matches('i') {
+        "" => 1.0,
+        "k" => 1024.0,
+        "m" => 1024.0 * 1024.0,
+        "g" => 1024.0 * 1024.0 * 1024.0,
+        _ => return Err(format!("invalid rate unit in '{}'", input)),
+    };
+
+    Ok(value * multiplier)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_durations_with_units() {
+        assert_eq!(parse_duration("500ms"), Ok(Duration::from_millis(500)));
+        assert_eq!(parse_duration("2"), Ok(Duration::from_secs(2)));
+        assert_eq!(parse_duration(" 1.5s "), Ok(Duration::from_millis(1500)));
+        assert_eq!(parse_duration("5m"), Ok(Duration::from_secs(300)));
+        assert_eq!(parse_duration("1h"), Ok(Duration::from_secs(3600)));
+    }
+
+    #[test]
+    fn rejects_invalid_durations() {
+        assert!(parse_duration("").is_err());
+        assert!(parse_duration("-1s").is_err());
+        assert!(parse_duration("1.2.3s").is_err());
+        assert!(parse_duration("3d").is_err());
+    }
+
+    #[test]
+    fn rejects_durations_too_long_instead_of_panicking() {
+        assert!(parse_duration("99999999999999999999999s").is_err());
+        assert!(parse_duration("99999999999999999999h").is_err());
+        assert!(parse_duration(&"9".repeat(400)).is_err());
+    }
+
+    #[test]
+    fn parses_rates_with_units() {
+        assert_eq!(parse_rate("512"), Ok(512.0));
+        assert_eq!(parse_rate("10k"), Ok(10240.0));
+        assert_eq!(parse_rate("1.5MiB/s"), Ok(1.5 * 1024.0 * 1024.0));
+        assert_eq!(parse_rate("2G"), Ok(2.0 * 1024.0 * 1024.0 * 1024.0));
+        assert_eq!(parse_rate("100B/s"), Ok(100.0));
+    }
+
+    #[test]
+    fn rejects_invalid_rates() {
+        assert!(parse_rate("").is_err());
+        assert!(parse_rate("fast").is_err());
+        assert!(parse_rate("10T").is_err());
+        assert!(parse_rate("10kbit").is_err());
+    }
+}